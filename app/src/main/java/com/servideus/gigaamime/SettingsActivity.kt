@@ -23,9 +23,11 @@ import com.servideus.gigaamime.data.ModelRepository
 import com.servideus.gigaamime.data.ModelSelectionStore
 import com.servideus.gigaamime.data.SpeedProfile
 import com.servideus.gigaamime.nativebridge.GigaamNativeBridge
+import com.servideus.gigaamime.nativebridge.NativeCallResult
 import kotlinx.coroutines.Dispatchers
 import kotlinx.coroutines.launch
 import kotlinx.coroutines.withContext
+import java.io.File
 
 class SettingsActivity : AppCompatActivity() {
     private lateinit var radioModelGroup: RadioGroup
@@ -239,15 +241,15 @@ class SettingsActivity : AppCompatActivity() {
                     modelsRootDir = modelRepository.modelsRootDir.absolutePath,
                     modelId = model.id,
                 )
-            }.getOrElse { "error: ${it.message}" }
+            }.getOrElse { NativeCallResult.failure(it) }
         }
 
-        if (result.startsWith("ok")) {
+        if (result.isSuccess) {
             setDownloadStatus(getString(R.string.status_warmup_success, model.displayName))
         } else {
-            setDownloadStatus(getString(R.string.status_warmup_failed, result))
+            setDownloadStatus(getString(R.string.status_warmup_failed, result.errorMessage.orEmpty()))
             if (DEBUG_LOGS) {
-                Log.w(TAG, "Warmup failed for ${model.id}: $result")
+                Log.w(TAG, "Warmup failed for ${model.id}: ${result.errorCode} ${result.errorMessage}")
             }
         }
     }
@@ -260,6 +262,9 @@ class SettingsActivity : AppCompatActivity() {
         val activeModel = selectionStore.getActiveModel()
         lifecycleScope.launch(Dispatchers.Default) {
             runCatching {
+                GigaamNativeBridge.setModelCacheDir(
+                    File(cacheDir, GigaamNativeBridge.OPTIMIZED_MODEL_CACHE_DIR).absolutePath,
+                )
                 GigaamNativeBridge.setRuntimeOptions(
                     modelId = activeModel.id,
                     speedProfile = runtimeSettings.speedProfile.id,
@@ -298,7 +303,7 @@ class SettingsActivity : AppCompatActivity() {
                 getString(R.string.status_native_error, GigaamNativeBridge.loadErrorSummary())
             }
 
-            txtHardwareAccelerationHint.text = if (runtimeSettings.acceleratorMode == AcceleratorMode.AUTO) {
+            txtHardwareAccelerationHint.text = if (runtimeSettings.acceleratorMode != AcceleratorMode.CPU) {
                 getString(R.string.hardware_acceleration_hint_auto)
             } else {
                 getString(R.string.hardware_acceleration_hint_cpu)
@@ -335,7 +340,7 @@ class SettingsActivity : AppCompatActivity() {
             SpeedProfile.FAST -> if (!radioSpeedFast.isChecked) radioSpeedFast.isChecked = true
             SpeedProfile.QUALITY -> if (!radioSpeedQuality.isChecked) radioSpeedQuality.isChecked = true
         }
-        val accelerationEnabled = runtimeSettings.acceleratorMode == AcceleratorMode.AUTO
+        val accelerationEnabled = runtimeSettings.acceleratorMode != AcceleratorMode.CPU
         if (switchHardwareAcceleration.isChecked != accelerationEnabled) {
             switchHardwareAcceleration.isChecked = accelerationEnabled
         }
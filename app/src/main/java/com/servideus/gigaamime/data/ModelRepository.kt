@@ -121,7 +121,9 @@ class ModelRepository(context: Context) {
         }
 
         if (!tempFile.renameTo(targetFile)) {
-            tempFile.copyTo(targetFile, overwrite = true)
+            // Never rewrite the old file in place: the native side may have it mapped.
+            targetFile.delete()
+            tempFile.copyTo(targetFile)
             tempFile.delete()
         }
     }
@@ -46,6 +46,9 @@ enum class SpeedProfile(
 
 enum class AcceleratorMode(val id: String) {
     AUTO("auto"),
+
+    /** Like [AUTO], but model loading fails when neither XNNPACK nor NNAPI registers. */
+    REQUIRED("required"),
     CPU("cpu");
 
     companion object {
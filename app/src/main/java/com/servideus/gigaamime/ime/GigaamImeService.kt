@@ -22,6 +22,7 @@ import com.servideus.gigaamime.audio.AudioResampler
 import com.servideus.gigaamime.data.ModelRepository
 import com.servideus.gigaamime.data.ModelSelectionStore
 import com.servideus.gigaamime.nativebridge.GigaamNativeBridge
+import com.servideus.gigaamime.nativebridge.NativeCallResult
 import kotlinx.coroutines.CoroutineScope
 import kotlinx.coroutines.Dispatchers
 import kotlinx.coroutines.Job
@@ -33,6 +34,7 @@ import kotlinx.coroutines.launch
 import kotlinx.coroutines.sync.Mutex
 import kotlinx.coroutines.sync.withLock
 import kotlinx.coroutines.withContext
+import java.io.File
 import java.util.ArrayDeque
 
 class GigaamImeService : InputMethodService() {
@@ -559,9 +561,9 @@ class GigaamImeService : InputMethodService() {
             }
 
             val nativeStartNs = SystemClock.elapsedRealtimeNanos()
-            val textResult = runCatching {
+            val transcription = runCatching {
                 runNativeCall {
-                    GigaamNativeBridge.transcribe(
+                    GigaamNativeBridge.transcribeResult(
                         modelsRootDir = modelRepository.modelsRootDir.absolutePath,
                         modelId = activeModel.id,
                         pcm16 = normalizedAudio,
@@ -570,20 +572,17 @@ class GigaamImeService : InputMethodService() {
                 }
             }
             val nativeCallMs = nsToMs(SystemClock.elapsedRealtimeNanos() - nativeStartNs)
-            val nativeTimings = runCatching {
-                runNativeCall {
-                    GigaamNativeBridge.getLastProfilingSummary()
-                }
-            }.getOrDefault("native timings unavailable")
+            val nativeTimings = transcription.getOrNull()?.profileJson ?: "native timings unavailable"
 
             transcribing = false
             updateButtons()
 
-            textResult.onSuccess { text ->
-                if (text.startsWith("GigaAM error:")) {
-                    setStatus(getString(R.string.status_error, text.removePrefix("GigaAM error: ").trim()))
+            transcription.onSuccess { result ->
+                if (!result.isSuccess) {
+                    setStatus(getString(R.string.status_error, result.errorMessage.orEmpty()))
                     return@onSuccess
                 }
+                val text = result.text
                 if (text.isBlank()) {
                     setStatus(getString(R.string.ime_status_empty_audio))
                     return@onSuccess
@@ -681,6 +680,9 @@ class GigaamImeService : InputMethodService() {
 
         runCatching {
             runNativeCall {
+                GigaamNativeBridge.setModelCacheDir(
+                    File(cacheDir, GigaamNativeBridge.OPTIMIZED_MODEL_CACHE_DIR).absolutePath,
+                )
                 GigaamNativeBridge.setRuntimeOptions(
                     modelId = activeModel.id,
                     speedProfile = runtimeSettings.speedProfile.id,
@@ -710,11 +712,11 @@ class GigaamImeService : InputMethodService() {
                     modelId = activeModel.id,
                 )
             }
-        }.getOrElse { "warmup exception: ${it.message}" }
-        if (result.startsWith("ok")) {
+        }.getOrElse { NativeCallResult.failure(it) }
+        if (result.isSuccess) {
             lastWarmedModelId = activeModel.id
         } else if (DEBUG_LOGS) {
-            Log.w(TAG, "Warmup failed for ${activeModel.id}: $result")
+            Log.w(TAG, "Warmup failed for ${activeModel.id}: ${result.errorCode} ${result.errorMessage}")
         }
     }
 
@@ -1,6 +1,9 @@
 package com.servideus.gigaamime.nativebridge
 
 object GigaamNativeBridge {
+    /** Subdirectory of the app cache directory passed to [setModelCacheDir]. */
+    const val OPTIMIZED_MODEL_CACHE_DIR = "optimized_models"
+
     private val loadError: Throwable? = runCatching {
         System.loadLibrary("onnxruntime")
         System.loadLibrary("gigaam_core")
@@ -17,21 +20,258 @@ object GigaamNativeBridge {
         return nativeIsModelValid(modelsRootDir, modelId)
     }
 
+    /**
+     * Checks the SHA-256 of every file whose checksum is declared in the model's
+     * `manifest.json`. Reads the whole model, so call it after downloads only.
+     */
+    fun verifyModel(modelsRootDir: String, modelId: String): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeVerifyModel(modelsRootDir, modelId))
+    }
+
+    /**
+     * Lists the model directories under [modelsRootDir]. Like every call that returns a
+     * JSON string, it answers with the envelope parsed by
+     * [NativeTranscriptionResult.fromJson]; its `data` is
+     * `{"models": [{"id", "architecture", "directory", "sample_rate", "valid", "error"}]}`,
+     * where unusable directories carry `valid: false`, an `error` and `null` manifest fields.
+     */
+    fun listModels(modelsRootDir: String): String {
+        ensureAvailable()
+        return nativeListModels(modelsRootDir)
+    }
+
     fun transcribe(modelsRootDir: String, modelId: String, pcm16: ShortArray, sampleRate: Int): String {
         ensureAvailable()
         return nativeTranscribe(modelsRootDir, modelId, pcm16, sampleRate)
     }
 
-    fun warmup(modelsRootDir: String, modelId: String): String {
+    /**
+     * Same as [transcribe], but failures come back as a typed [NativeErrorCode] instead of
+     * a `GigaAM error:` prefix, together with the profiling data of the call.
+     */
+    fun transcribeResult(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: ShortArray,
+        sampleRate: Int,
+    ): NativeTranscriptionResult {
+        ensureAvailable()
+        return NativeTranscriptionResult.fromJson(
+            nativeTranscribeResult(modelsRootDir, modelId, pcm16, sampleRate),
+        )
+    }
+
+    /**
+     * Same as [transcribe], but the result envelope's `data` holds `text`, `confidence`,
+     * `words` and `tokens`, where every word and token carries `start_ms`/`end_ms` and a
+     * `confidence` in 0..1.
+     */
+    fun transcribeDetailed(modelsRootDir: String, modelId: String, pcm16: ShortArray, sampleRate: Int): String {
+        ensureAvailable()
+        return nativeTranscribeDetailed(modelsRootDir, modelId, pcm16, sampleRate)
+    }
+
+    /**
+     * Transcribes several independent recordings, sharing encoder runs as
+     * [setBatchOptions] allows. The result envelope's `data` is an array with one
+     * [transcribeDetailed] object per recording, in input order. Recordings longer
+     * than the [setLongFormOptions] window are split into chunks that are batched
+     * with the rest and stitched back together; VAD is not applied.
+     */
+    fun transcribeBatch(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: Array<ShortArray>,
+        sampleRate: Int,
+    ): String {
         ensureAvailable()
-        return nativeWarmup(modelsRootDir, modelId)
+        return nativeTranscribeBatch(modelsRootDir, modelId, pcm16, sampleRate)
     }
 
-    fun setRuntimeOptions(modelId: String, speedProfile: String, acceleratorMode: String): String {
+    /**
+     * Runs beam search; the result envelope's `data` holds the best `text`, up to
+     * [maxAlternatives] `alternatives` (each with `log_score` and `probability`) and, per
+     * word of the best transcript, the competing spellings under `words[].candidates`.
+     * Audio longer than the [setLongFormOptions] window fails with `invalid_audio`.
+     */
+    fun transcribeAlternatives(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: ShortArray,
+        sampleRate: Int,
+        maxAlternatives: Int = 5,
+    ): String {
         ensureAvailable()
-        return nativeSetRuntimeOptions(modelId, speedProfile, acceleratorMode)
+        return nativeTranscribeAlternatives(modelsRootDir, modelId, pcm16, sampleRate, maxAlternatives)
     }
 
+    /**
+     * Loads [modelId] and runs it once on silence. Like [setDecodeOptions] and
+     * [setHotwords], the result carries a warning when the model ignores the selected
+     * beam search or hotwords.
+     */
+    fun warmup(modelsRootDir: String, modelId: String): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeWarmup(modelsRootDir, modelId))
+    }
+
+    /**
+     * [acceleratorMode] is `auto` (XNNPACK, then NNAPI, then CPU), `required` (the same,
+     * but loading fails when no accelerator registers) or `cpu`. The registered providers
+     * and the nodes each one ran are reported under `model_load.provider` in
+     * [getLastProfilingSummary].
+     */
+    fun setRuntimeOptions(modelId: String, speedProfile: String, acceleratorMode: String): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetRuntimeOptions(modelId, speedProfile, acceleratorMode))
+    }
+
+    /**
+     * Like [setRuntimeOptions], but takes a JSON object. `speed_profile` and
+     * `accelerator_mode` select the preset; `intra_threads`, `inter_threads`,
+     * `parallel_execution`, `xnnpack_threads`, `providers` (ordered, from `xnnpack`,
+     * `nnapi`, `cpu`), `memory_arena`, `memory_pattern` and `optimization_level`
+     * (`disable`, `basic`, `extended`, `all`) override single settings of it.
+     * The result message lists the effective options; unknown keys and invalid values
+     * fail with [NativeErrorCode.INVALID_ARGUMENT].
+     */
+    fun setRuntimeOptionsJson(optionsJson: String): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetRuntimeOptionsJson(optionsJson))
+    }
+
+    fun setDecodeOptions(
+        decodeStrategy: String,
+        beamWidth: Int,
+        lmAlpha: Float = 0.5f,
+        lmBeta: Float = 1.0f,
+    ): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetDecodeOptions(decodeStrategy, beamWidth, lmAlpha, lmBeta))
+    }
+
+    /**
+     * Replaces the per-session boost phrases. Biasing is applied by beam search decoding
+     * and survives engine reloads until replaced; pass empty arrays to clear. The result
+     * carries a warning while the decode strategy is greedy, which ignores hotwords, or
+     * while a transducer model is loaded, which is always decoded greedily.
+     */
+    fun setHotwords(phrases: Array<String>, weights: FloatArray): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetHotwords(phrases, weights))
+    }
+
+    /**
+     * Selects the native voice activity detector: `off`, `energy` (adaptive energy and
+     * spectral flux) or `silero` (`silero_vad.onnx` in [modelsRootDir]). When enabled,
+     * transcription skips the audio outside the detected speech.
+     */
+    fun setVadOptions(
+        modelsRootDir: String,
+        vadMode: String,
+        threshold: Float = 0.5f,
+        minSpeechMs: Int = 250,
+        minSilenceMs: Int = 300,
+        speechPadMs: Int = 100,
+    ): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetVadOptions(modelsRootDir, vadMode, threshold, minSpeechMs, minSilenceMs, speechPadMs))
+    }
+
+    /**
+     * Audio longer than [maxSegmentMs] is transcribed in chunks cut at pauses (or fixed
+     * windows overlapping by [overlapMs]) and stitched back together. Pass 0 to always
+     * transcribe in one run. Both values are capped at 300 000 ms, and a nonzero
+     * [maxSegmentMs] is raised to at least 1 000 ms. Alternatives always use a single
+     * run, so [transcribeAlternatives] rejects audio longer than [maxSegmentMs].
+     */
+    fun setLongFormOptions(maxSegmentMs: Int = 20_000, overlapMs: Int = 2_000): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetLongFormOptions(maxSegmentMs, overlapMs))
+    }
+
+    /**
+     * Runs up to [maxBatchSize] (at most 64) long-form chunks or [transcribeBatch]
+     * recordings through the encoder at once, padded to the longest of them. With
+     * [sortByLength] items of similar length share a run. The default of 1 runs them
+     * one at a time.
+     */
+    fun setBatchOptions(maxBatchSize: Int = 1, sortByLength: Boolean = true): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetBatchOptions(maxBatchSize, sortByLength))
+    }
+
+    /**
+     * Selects the resampler used for non-16 kHz input: `linear`, `balanced` (default)
+     * or `high`. Streaming sessions keep the quality they were started with.
+     */
+    fun setResampleQuality(quality: String = "balanced"): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetResampleQuality(quality))
+    }
+
+    /**
+     * Stores ONNX Runtime's optimized copy of each loaded model in [cacheDir], keyed by
+     * model checksum and runtime options, so later loads skip graph optimization. The
+     * first inference is also profiled there to count the nodes each execution provider
+     * runs. The directory may be cleared at any time; an empty string turns the cache
+     * and the node counts off.
+     */
+    fun setModelCacheDir(cacheDir: String): NativeCallResult {
+        ensureAvailable()
+        return NativeCallResult.fromJson(nativeSetModelCacheDir(cacheDir))
+    }
+
+    /**
+     * The result envelope's `data` is `{"backend", "speech_ms", "segments": [{"start_ms",
+     * "end_ms"}]}` for the configured detector, or the energy detector when VAD is off.
+     */
+    fun detectSpeech(pcm16: ShortArray, sampleRate: Int): String {
+        ensureAvailable()
+        return nativeDetectSpeech(pcm16, sampleRate)
+    }
+
+    /**
+     * Starts a streaming session; the result envelope's `data` is `{"session_id": N}`.
+     * Sessions are dropped when the model is reloaded or unloaded.
+     */
+    fun streamStart(modelsRootDir: String, modelId: String, sampleRate: Int): String {
+        ensureAvailable()
+        return nativeStreamStart(modelsRootDir, modelId, sampleRate)
+    }
+
+    /**
+     * Appends a PCM chunk; the result envelope's `data` is the current partial result:
+     * `stable_text` only ever grows, while the rest of `text` may still change on the next
+     * push.
+     */
+    fun streamPush(sessionId: Long, pcm16: ShortArray): String {
+        ensureAvailable()
+        return nativeStreamPush(sessionId, pcm16)
+    }
+
+    /**
+     * Decodes the remaining audio, closes the session and returns the final result in the
+     * envelope's `data`.
+     */
+    fun streamFinish(sessionId: Long): String {
+        ensureAvailable()
+        return nativeStreamFinish(sessionId)
+    }
+
+    fun streamCancel(sessionId: Long) {
+        if (isAvailable()) {
+            nativeStreamCancel(sessionId)
+        }
+    }
+
+    /**
+     * The result envelope's `data` holds the metrics of the last transcription or warmup:
+     * audio duration and frame counts, time spent in every phase from the PCM copy to
+     * decoding, real-time factor, and how the model was loaded (`model_load`), including
+     * the execution provider that ran it. `{}` before the first call.
+     */
     fun getLastProfilingSummary(): String {
         ensureAvailable()
         return nativeGetLastProfilingSummary()
@@ -50,18 +290,70 @@ object GigaamNativeBridge {
     }
 
     private external fun nativeIsModelValid(modelsRootDir: String, modelId: String): Boolean
+    private external fun nativeVerifyModel(modelsRootDir: String, modelId: String): String
+    private external fun nativeListModels(modelsRootDir: String): String
     private external fun nativeTranscribe(
         modelsRootDir: String,
         modelId: String,
         pcm16: ShortArray,
         sampleRate: Int,
     ): String
+    private external fun nativeTranscribeResult(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: ShortArray,
+        sampleRate: Int,
+    ): String
+    private external fun nativeTranscribeDetailed(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: ShortArray,
+        sampleRate: Int,
+    ): String
+    private external fun nativeTranscribeBatch(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: Array<ShortArray>,
+        sampleRate: Int,
+    ): String
+    private external fun nativeTranscribeAlternatives(
+        modelsRootDir: String,
+        modelId: String,
+        pcm16: ShortArray,
+        sampleRate: Int,
+        maxAlternatives: Int,
+    ): String
     private external fun nativeWarmup(modelsRootDir: String, modelId: String): String
     private external fun nativeSetRuntimeOptions(
         modelId: String,
         speedProfile: String,
         acceleratorMode: String,
     ): String
+    private external fun nativeSetRuntimeOptionsJson(optionsJson: String): String
+    private external fun nativeSetDecodeOptions(
+        decodeStrategy: String,
+        beamWidth: Int,
+        lmAlpha: Float,
+        lmBeta: Float,
+    ): String
+    private external fun nativeSetHotwords(phrases: Array<String>, weights: FloatArray): String
+    private external fun nativeSetVadOptions(
+        modelsRootDir: String,
+        vadMode: String,
+        threshold: Float,
+        minSpeechMs: Int,
+        minSilenceMs: Int,
+        speechPadMs: Int,
+    ): String
+    private external fun nativeSetLongFormOptions(maxSegmentMs: Int, overlapMs: Int): String
+    private external fun nativeSetBatchOptions(maxBatchSize: Int, sortByLength: Boolean): String
+    private external fun nativeSetResampleQuality(quality: String): String
+    private external fun nativeSetModelCacheDir(cacheDir: String): String
+    private external fun nativeDetectSpeech(pcm16: ShortArray, sampleRate: Int): String
+    private external fun nativeStreamStart(modelsRootDir: String, modelId: String, sampleRate: Int): String
+    private external fun nativeStreamPush(sessionId: Long, pcm16: ShortArray): String
+    private external fun nativeStreamFinish(sessionId: Long): String
+    private external fun nativeStreamCancel(sessionId: Long)
     private external fun nativeGetLastProfilingSummary(): String
 
     private external fun nativeUnload()
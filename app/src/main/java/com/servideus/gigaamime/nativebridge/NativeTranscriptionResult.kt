@@ -0,0 +1,106 @@
+package com.servideus.gigaamime.nativebridge
+
+import org.json.JSONObject
+
+/** Error codes reported by the native bridge in `error.code`. */
+enum class NativeErrorCode(val id: String) {
+    INVALID_ARGUMENT("invalid_argument"),
+    UNSUPPORTED_MODEL("unsupported_model"),
+    MODEL_MISSING("model_missing"),
+    INVALID_MANIFEST("invalid_manifest"),
+    ORT_FAILURE("ort_failure"),
+    INVALID_AUDIO("invalid_audio"),
+    ENGINE_NOT_LOADED("engine_not_loaded"),
+    UNKNOWN_SESSION("unknown_session"),
+    PANIC("panic"),
+
+    /** The result has a newer [NativeTranscriptionResult.version] than this app reads. */
+    UNSUPPORTED_VERSION("unsupported_version"),
+    UNKNOWN("unknown"),
+    ;
+
+    companion object {
+        fun fromId(id: String): NativeErrorCode = entries.firstOrNull { it.id == id } ?: UNKNOWN
+    }
+}
+
+/**
+ * Parsed result envelope that every JSON call of [GigaamNativeBridge] answers with.
+ * [text] and [profileJson] are set by [GigaamNativeBridge.transcribeResult], where
+ * [profileJson] holds the same timings as [GigaamNativeBridge.getLastProfilingSummary].
+ * [dataJson] is the payload of the calls that return their own JSON object. Results of
+ * a newer [version] are reported as [NativeErrorCode.UNSUPPORTED_VERSION].
+ */
+data class NativeTranscriptionResult(
+    val version: Int,
+    val text: String,
+    val errorCode: NativeErrorCode?,
+    val errorMessage: String?,
+    val profileJson: String?,
+    val dataJson: String? = null,
+) {
+    val isSuccess: Boolean
+        get() = errorCode == null
+
+    companion object {
+        const val SUPPORTED_VERSION = 1
+
+        fun fromJson(json: String): NativeTranscriptionResult {
+            val root = JSONObject(json)
+            val version = root.optInt("version", 0)
+            val error = root.optJSONObject("error")
+            val (errorCode, errorMessage) = when {
+                version > SUPPORTED_VERSION -> NativeErrorCode.UNSUPPORTED_VERSION to
+                    "Native result version $version is newer than the supported $SUPPORTED_VERSION"
+                error != null -> NativeErrorCode.fromId(error.optString("code")) to
+                    error.optString("message")
+                // Anything but an explicit success is a failure, even without details.
+                root.optString("status") != "ok" -> NativeErrorCode.UNKNOWN to
+                    "Native result has no status: $json"
+                else -> null to null
+            }
+            return NativeTranscriptionResult(
+                version = version,
+                text = root.optString("text"),
+                errorCode = errorCode,
+                errorMessage = errorMessage,
+                profileJson = root.optJSONObject("profile")?.toString(),
+                dataJson = root.opt("data")?.takeIf { it != JSONObject.NULL }?.toString(),
+            )
+        }
+    }
+}
+
+/**
+ * Parsed result of the calls that configure, load or verify the native engine. [message]
+ * is the `ok: ...` summary of what was applied, which may end in a `warning: ...`.
+ */
+data class NativeCallResult(
+    val version: Int,
+    val message: String,
+    val errorCode: NativeErrorCode?,
+    val errorMessage: String?,
+) {
+    val isSuccess: Boolean
+        get() = errorCode == null
+
+    companion object {
+        fun fromJson(json: String): NativeCallResult {
+            val result = NativeTranscriptionResult.fromJson(json)
+            return NativeCallResult(
+                version = result.version,
+                message = result.text,
+                errorCode = result.errorCode,
+                errorMessage = result.errorMessage,
+            )
+        }
+
+        /** A call that threw before the native side could answer. */
+        fun failure(error: Throwable): NativeCallResult = NativeCallResult(
+            version = NativeTranscriptionResult.SUPPORTED_VERSION,
+            message = "",
+            errorCode = NativeErrorCode.UNKNOWN,
+            errorMessage = error.message,
+        )
+    }
+}
@@ -0,0 +1,201 @@
+use crate::timestamps::WordTiming;
+use serde::Serialize;
+
+/// A decoded hypothesis reduced to what the alternatives lists need.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HypothesisWords {
+    pub text: String,
+    pub words: Vec<WordTiming>,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptAlternative {
+    pub text: String,
+    /// Natural-log beam score, including LM and hotword contributions.
+    pub log_score: f32,
+    /// Share of the probability mass among the returned hypotheses.
+    pub probability: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordCandidate {
+    pub text: String,
+    /// Best beam score among the hypotheses that spell the span this way.
+    pub log_score: f32,
+    /// Summed probability of the hypotheses that spell the span this way.
+    pub probability: f32,
+}
+
+/// Distinct spellings of one word of the best hypothesis, best first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordAlternatives {
+    pub word_index: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub candidates: Vec<WordCandidate>,
+}
+
+pub fn transcript_alternatives(hypotheses: &[HypothesisWords]) -> Vec<TranscriptAlternative> {
+    let probabilities = normalized_probabilities(hypotheses);
+    hypotheses
+        .iter()
+        .zip(probabilities)
+        .map(|(hypothesis, probability)| TranscriptAlternative {
+            text: hypothesis.text.clone(),
+            log_score: hypothesis.score,
+            probability,
+        })
+        .collect()
+}
+
+/// For every word of the first (best) hypothesis, collects how each hypothesis spells
+/// that time span. A word of another hypothesis belongs to the span its midpoint
+/// falls into, so splits and merges show up as multi-word or partial spellings.
+pub fn word_alternatives(hypotheses: &[HypothesisWords]) -> Vec<WordAlternatives> {
+    let Some(best) = hypotheses.first() else {
+        return Vec::new();
+    };
+    let probabilities = normalized_probabilities(hypotheses);
+
+    best.words
+        .iter()
+        .enumerate()
+        .map(|(word_index, span)| {
+            let mut candidates = Vec::<WordCandidate>::new();
+            for (hypothesis, &probability) in hypotheses.iter().zip(&probabilities) {
+                let spelling = hypothesis
+                    .words
+                    .iter()
+                    .filter(|word| {
+                        let midpoint = (word.start_ms + word.end_ms) / 2;
+                        midpoint >= span.start_ms && midpoint < span.end_ms.max(span.start_ms + 1)
+                    })
+                    .map(|word| word.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if spelling.is_empty() {
+                    continue;
+                }
+                match candidates
+                    .iter_mut()
+                    .find(|candidate| candidate.text == spelling)
+                {
+                    Some(candidate) => {
+                        candidate.probability += probability;
+                        candidate.log_score = candidate.log_score.max(hypothesis.score);
+                    }
+                    None => candidates.push(WordCandidate {
+                        text: spelling,
+                        log_score: hypothesis.score,
+                        probability,
+                    }),
+                }
+            }
+            candidates.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+
+            WordAlternatives {
+                word_index,
+                start_ms: span.start_ms,
+                end_ms: span.end_ms,
+                candidates,
+            }
+        })
+        .collect()
+}
+
+fn normalized_probabilities(hypotheses: &[HypothesisWords]) -> Vec<f32> {
+    let max = hypotheses
+        .iter()
+        .map(|hypothesis| hypothesis.score)
+        .fold(f32::NEG_INFINITY, f32::max);
+    if !max.is_finite() {
+        return vec![0.0; hypotheses.len()];
+    }
+    let weights = hypotheses
+        .iter()
+        .map(|hypothesis| (hypothesis.score - max).exp())
+        .collect::<Vec<_>>();
+    let total = weights.iter().sum::<f32>();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, start_ms: u64, end_ms: u64) -> WordTiming {
+        WordTiming {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 1.0,
+            tokens: 0..1,
+        }
+    }
+
+    fn hypothesis(words: Vec<WordTiming>, probability: f32) -> HypothesisWords {
+        HypothesisWords {
+            text: words
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            words,
+            score: probability.ln(),
+        }
+    }
+
+    #[test]
+    fn transcript_probabilities_are_normalized_over_the_list() {
+        let hypotheses = [
+            hypothesis(vec![word("кот", 0, 200)], 0.3),
+            hypothesis(vec![word("код", 0, 200)], 0.1),
+        ];
+
+        let alternatives = transcript_alternatives(&hypotheses);
+        assert_eq!(alternatives[0].text, "кот");
+        assert!((alternatives[0].probability - 0.75).abs() < 1e-5);
+        assert!((alternatives[1].probability - 0.25).abs() < 1e-5);
+        assert!((alternatives[1].log_score - 0.1_f32.ln()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn word_spans_collect_distinct_spellings_from_the_beam() {
+        let hypotheses = [
+            hypothesis(vec![word("мой", 0, 200), word("кот", 240, 480)], 0.4),
+            hypothesis(vec![word("мой", 0, 200), word("код", 240, 480)], 0.3),
+            hypothesis(vec![word("мой", 0, 200), word("кот", 260, 480)], 0.2),
+            hypothesis(
+                vec![
+                    word("мо", 0, 120),
+                    word("и", 120, 200),
+                    word("кот", 240, 480),
+                ],
+                0.1,
+            ),
+        ];
+
+        let spans = word_alternatives(&hypotheses);
+        assert_eq!(spans.len(), 2);
+
+        let first = &spans[0].candidates;
+        assert_eq!(first[0].text, "мой");
+        assert!((first[0].probability - 0.9).abs() < 1e-5);
+        assert_eq!(first[1].text, "мо и");
+
+        let second = &spans[1];
+        assert_eq!(
+            (second.word_index, second.start_ms, second.end_ms),
+            (1, 240, 480)
+        );
+        let texts = second
+            .candidates
+            .iter()
+            .map(|candidate| candidate.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["кот", "код"]);
+        assert!((second.candidates[0].probability - 0.7).abs() < 1e-5);
+        assert!((second.candidates[0].log_score - 0.4_f32.ln()).abs() < 1e-6);
+    }
+}
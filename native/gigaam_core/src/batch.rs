@@ -0,0 +1,144 @@
+use anyhow::Result;
+use ndarray::{s, Array3, ArrayView3, Axis};
+
+/// Largest batch accepted, so that a bad host value cannot allocate a huge tensor.
+pub const MAX_BATCH_SIZE: usize = 64;
+
+/// How several utterances share encoder runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Utterances per ORT run, up to [`MAX_BATCH_SIZE`]; 0 and 1 run them one at a time.
+    pub max_batch_size: usize,
+    /// Groups utterances of similar length, so shorter ones carry less padding.
+    pub sort_by_length: bool,
+}
+
+impl BatchOptions {
+    /// Builds options from host-supplied values; a negative size keeps the default and
+    /// larger ones are capped at [`MAX_BATCH_SIZE`].
+    pub fn from_values(max_batch_size: i32, sort_by_length: bool) -> Self {
+        Self {
+            max_batch_size: usize::try_from(max_batch_size)
+                .unwrap_or(Self::default().max_batch_size)
+                .min(MAX_BATCH_SIZE),
+            sort_by_length,
+        }
+    }
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 1,
+            sort_by_length: true,
+        }
+    }
+}
+
+/// Splits items of the given lengths into batches of item indices. Sorted batches
+/// take the longest items first; otherwise items keep their order. Empty items are
+/// left out, since there is nothing to run.
+pub fn plan_batches(lengths: &[usize], options: &BatchOptions) -> Vec<Vec<usize>> {
+    let mut order = (0..lengths.len())
+        .filter(|&index| lengths[index] > 0)
+        .collect::<Vec<_>>();
+    if options.sort_by_length {
+        order.sort_by_key(|&index| std::cmp::Reverse(lengths[index]));
+    }
+    order
+        .chunks(options.max_batch_size.clamp(1, MAX_BATCH_SIZE))
+        .map(<[usize]>::to_vec)
+        .collect()
+}
+
+/// Stacks `[1, n_mels, frames]` features into one `[batch, n_mels, max_frames]`
+/// tensor filled up with `pad_value`, and returns it with the real frame count of
+/// each row.
+pub fn pad_features(
+    items: &[ArrayView3<'_, f32>],
+    pad_value: f32,
+) -> Result<(Array3<f32>, Vec<i64>)> {
+    let n_mels = items.first().map_or(0, |features| features.shape()[1]);
+    let max_frames = items
+        .iter()
+        .map(|features| features.shape()[2])
+        .max()
+        .unwrap_or(0);
+    let mut batch = Array3::from_elem((items.len(), n_mels, max_frames), pad_value);
+    let mut lengths = Vec::with_capacity(items.len());
+    for (row, features) in items.iter().enumerate() {
+        let (batch_size, mels, frames) = features.dim();
+        if batch_size != 1 || mels != n_mels {
+            anyhow::bail!(
+                "Cannot batch features of shape {:?} with {n_mels} mel bands",
+                features.shape()
+            );
+        }
+        batch
+            .slice_mut(s![row, .., ..frames])
+            .assign(&features.index_axis(Axis(0), 0));
+        lengths.push(frames as i64);
+    }
+    Ok((batch, lengths))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_group_similar_lengths_and_skip_empty_items() {
+        let lengths = [300, 0, 1_200, 310, 1_180, 40];
+        let sorted = BatchOptions {
+            max_batch_size: 2,
+            sort_by_length: true,
+        };
+        assert_eq!(
+            plan_batches(&lengths, &sorted),
+            vec![vec![2, 4], vec![3, 0], vec![5]]
+        );
+        let in_order = BatchOptions {
+            max_batch_size: 4,
+            sort_by_length: false,
+        };
+        assert_eq!(
+            plan_batches(&lengths, &in_order),
+            vec![vec![0, 2, 3, 4], vec![5]]
+        );
+        let zero = BatchOptions {
+            max_batch_size: 0,
+            ..in_order
+        };
+        assert_eq!(plan_batches(&lengths, &zero).len(), 5);
+        assert!(plan_batches(&[0, 0], &sorted).is_empty());
+
+        let huge = BatchOptions::from_values(i32::MAX, false);
+        assert_eq!(huge.max_batch_size, MAX_BATCH_SIZE);
+        assert_eq!(BatchOptions::from_values(-1, true), BatchOptions::default());
+        let unchecked = BatchOptions {
+            max_batch_size: usize::MAX,
+            ..in_order
+        };
+        let many = vec![1; MAX_BATCH_SIZE + 1];
+        assert_eq!(plan_batches(&many, &unchecked).len(), 2);
+    }
+
+    #[test]
+    fn padded_rows_keep_their_frames_and_lengths() -> Result<()> {
+        let short = Array3::from_shape_fn((1, 2, 3), |(_, mel, frame)| (mel * 10 + frame) as f32);
+        let long = Array3::from_elem((1, 2, 5), -1.0_f32);
+        let (batch, lengths) = pad_features(&[short.view(), long.view()], -20.0)?;
+        assert_eq!(batch.dim(), (2, 2, 5));
+        assert_eq!(lengths, vec![3, 5]);
+        assert_eq!(batch.slice(s![0, .., ..3]), short.index_axis(Axis(0), 0));
+        assert!(batch
+            .slice(s![0, .., 3..])
+            .iter()
+            .all(|&value| value == -20.0));
+        assert_eq!(batch.slice(s![1, .., ..]), long.index_axis(Axis(0), 0));
+
+        let other_mels = Array3::<f32>::zeros((1, 3, 2));
+        assert!(pad_features(&[short.view(), other_mels.view()], 0.0).is_err());
+        Ok(())
+    }
+}
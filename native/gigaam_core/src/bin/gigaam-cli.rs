@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match gigaam_core::cli::run(std::env::args().skip(1)) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("gigaam-cli: {error:#}");
+            ExitCode::from(2)
+        }
+    }
+}
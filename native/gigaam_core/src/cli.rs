@@ -0,0 +1,605 @@
+use crate::batch::BatchOptions;
+use crate::decoder::{DecodeOptions, DecodeStrategy};
+use crate::eval::{read_test_manifest, EvalReport, UtteranceReport};
+use crate::gigaam::{GigaamEngine, NativeTranscriptionReport, RuntimeOptions, TranscriptResult};
+use crate::longform::{LongFormOptions, MAX_SEGMENT_MS, MIN_SEGMENT_MS};
+use crate::manifest::ModelManifest;
+use crate::metrics::TranscriptionMetrics;
+use crate::model_cache::ModelCache;
+use crate::resample::{resample, ResampleQuality};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+const TARGET_SAMPLE_RATE: usize = 16_000;
+/// Files are read this many batches at a time, so that sorting by length has similar
+/// files to pair up without holding a whole test set in memory.
+const BATCHES_PER_GROUP: usize = 8;
+
+const USAGE: &str = "\
+Usage: gigaam-cli --model <dir> [options] <file.wav>...
+       gigaam-cli eval --model <dir> --manifest <set.jsonl> [--report <file>] [options]
+
+Transcribes WAV files with a GigaAM model directory. `eval` transcribes a test set
+instead, given as JSONL lines of {\"audio\": <wav>, \"text\": <reference>}, and writes
+a JSON report with WER, CER, word alignments and RTF.
+
+Options:
+  --model <dir>          Model directory (manifest.json or a known GigaAM layout)
+  --format <format>      plain (default), json or jsonl
+  --runtime <json>       Runtime options, as accepted by setRuntimeOptionsJson;
+                         defaults to the balanced CPU preset
+  --decode <strategy>    greedy (default) or beam
+  --beam-width <n>       Beam width for beam search
+  --max-segment-ms <ms>  Long-form chunk length, 1000 to 300000; 0 transcribes
+                         in one run
+  --batch-size <n>       Files or chunks per encoder run, at most 64; 1 (default)
+                         runs them one at a time
+  --model-cache <dir>    Directory for optimized models
+  --ort-lib <path>       libonnxruntime to load instead of ORT_DYLIB_PATH
+  --manifest <file>      Test set of `eval`
+  --report <file>        Where `eval` writes its report; stdout by default
+  -h, --help             Print this help
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Plain,
+    Json,
+    Jsonl,
+}
+
+impl OutputFormat {
+    fn from_id(value: &str) -> Option<Self> {
+        match value {
+            "plain" => Some(Self::Plain),
+            "json" => Some(Self::Json),
+            "jsonl" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Transcribe {
+        files: Vec<PathBuf>,
+    },
+    Eval {
+        manifest: PathBuf,
+        report: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct CliOptions {
+    command: Command,
+    model_dir: PathBuf,
+    format: OutputFormat,
+    runtime_options: RuntimeOptions,
+    decode_options: DecodeOptions,
+    long_form: LongFormOptions,
+    batch_options: BatchOptions,
+    model_cache: Option<PathBuf>,
+    ort_lib: Option<String>,
+}
+
+/// Entry point of the `gigaam-cli` binary; `args` excludes the program name. Files that
+/// fail are reported and skipped, and make the exit code non-zero.
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<ExitCode> {
+    let Some(options) = parse_args(args)? else {
+        print!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    };
+    if let Some(ort_lib) = &options.ort_lib {
+        ort::init_from(ort_lib)
+            .commit()
+            .with_context(|| format!("Failed to load ONNX Runtime from {ort_lib}"))?;
+    }
+
+    let manifest = ModelManifest::from_directory(&options.model_dir)?;
+    manifest.validate_files(&options.model_dir)?;
+    let model_cache = options.model_cache.as_ref().map(ModelCache::new);
+    let load_start = Instant::now();
+    let mut engine = GigaamEngine::new();
+    engine.load_model(
+        &options.model_dir,
+        &manifest,
+        &options.runtime_options,
+        model_cache.as_ref(),
+    )?;
+    engine.set_decode_options(options.decode_options);
+    engine.set_batch_options(options.batch_options);
+    let load_ms = load_start.elapsed().as_millis();
+
+    match &options.command {
+        Command::Transcribe { files } => transcribe_files(&mut engine, &options, files, load_ms),
+        Command::Eval { manifest, report } => {
+            evaluate(&mut engine, &options, manifest, report.as_deref())
+        }
+    }
+}
+
+fn transcribe_files(
+    engine: &mut GigaamEngine,
+    options: &CliOptions,
+    files: &[PathBuf],
+    mut load_ms: u128,
+) -> Result<ExitCode> {
+    let mut stdout = io::stdout().lock();
+    let mut failures = 0;
+    if options.format == OutputFormat::Json {
+        writeln!(stdout, "[")?;
+    }
+    let results = files.chunks(options.group_size()).flat_map(|group| {
+        let paths = group.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+        transcribe_group(engine, &paths, &options.long_form)
+    });
+    for (index, (file, result)) in files.iter().zip(results).enumerate() {
+        // The model load is charged to the first file, as it is to the first call on
+        // Android.
+        let result = result.map(|(report, mut metrics)| {
+            metrics.load_ms = std::mem::take(&mut load_ms);
+            metrics.wall_ms += metrics.load_ms;
+            (report, metrics)
+        });
+        let record = match &result {
+            Ok((report, metrics)) => {
+                if options.format == OutputFormat::Plain {
+                    writeln!(stdout, "{}", format_plain(file, report, metrics))?;
+                    continue;
+                }
+                FileRecord::transcribed(file, report, metrics)
+            }
+            Err(error) => {
+                failures += 1;
+                if options.format == OutputFormat::Plain {
+                    eprintln!("{}: error: {error:#}", file.display());
+                    continue;
+                }
+                FileRecord::failed(file, error)
+            }
+        };
+        serde_json::to_writer(&mut stdout, &record)?;
+        match options.format {
+            OutputFormat::Json if index + 1 < files.len() => writeln!(stdout, ",")?,
+            _ => writeln!(stdout)?,
+        }
+    }
+    if options.format == OutputFormat::Json {
+        writeln!(stdout, "]")?;
+    }
+
+    Ok(exit_code(failures))
+}
+
+/// Scores the transcripts of every test case in `manifest` against its reference.
+fn evaluate(
+    engine: &mut GigaamEngine,
+    options: &CliOptions,
+    manifest: &Path,
+    report_path: Option<&Path>,
+) -> Result<ExitCode> {
+    let cases = read_test_manifest(manifest)?;
+    let decode = &options.decode_options;
+    let mut report = EvalReport::new(
+        options.model_dir.display().to_string(),
+        options.runtime_options.cache_fragment(),
+        match decode.strategy {
+            DecodeStrategy::Greedy => decode.strategy.as_id().to_string(),
+            DecodeStrategy::BeamSearch => format!("beam={}", decode.beam_width),
+        },
+    );
+    let results = cases.chunks(options.group_size()).flat_map(|group| {
+        let paths = group
+            .iter()
+            .map(|case| case.audio.as_path())
+            .collect::<Vec<_>>();
+        transcribe_group(engine, &paths, &options.long_form)
+    });
+    for (case, result) in cases.iter().zip(results) {
+        let utterance = match result {
+            Ok((transcript, metrics)) => {
+                let mut utterance = UtteranceReport::score(&case.id, &case.text, &transcript.text);
+                utterance.set_timing(metrics.audio_ms, metrics.total_ms);
+                utterance
+            }
+            Err(error) => {
+                eprintln!("{}: error: {error:#}", case.audio.display());
+                UtteranceReport::failed(&case.id, &case.text, format!("{error:#}"))
+            }
+        };
+        report.push(utterance);
+    }
+
+    let json = report.to_json();
+    match report_path {
+        Some(path) => fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => println!("{json}"),
+    }
+    eprintln!("{}", report.summary());
+    Ok(exit_code(report.failed))
+}
+
+fn exit_code(failures: usize) -> ExitCode {
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Returns `None` when help was requested.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<CliOptions>> {
+    let mut args = args.into_iter().peekable();
+    let eval = args.next_if(|arg| arg == "eval").is_some();
+    let mut model_dir = None;
+    let mut files = Vec::new();
+    let mut manifest = None;
+    let mut report = None;
+    let mut format = OutputFormat::Plain;
+    let mut runtime_options = RuntimeOptions::from_ids("balanced", "cpu");
+    let mut decode_strategy = "greedy".to_string();
+    let mut beam_width = 0;
+    let mut long_form = LongFormOptions::default();
+    let mut batch_options = BatchOptions::default();
+    let mut model_cache = None;
+    let mut ort_lib = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("{arg} requires a value"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--model" => model_dir = Some(PathBuf::from(value()?)),
+            "--format" => {
+                let id = value()?;
+                format = OutputFormat::from_id(&id)
+                    .with_context(|| format!("Unknown output format: {id}"))?;
+            }
+            "--runtime" => runtime_options = RuntimeOptions::from_json(&value()?)?,
+            "--decode" => decode_strategy = value()?,
+            "--beam-width" => beam_width = parse_number(&arg, &value()?)?,
+            "--max-segment-ms" => {
+                long_form.max_segment_ms = parse_number(&arg, &value()?)?;
+                let ms = long_form.max_segment_ms;
+                if ms != 0 && !(MIN_SEGMENT_MS..=MAX_SEGMENT_MS).contains(&ms) {
+                    anyhow::bail!(
+                        "{arg} expects 0 or {MIN_SEGMENT_MS} to {MAX_SEGMENT_MS}, got {ms}"
+                    );
+                }
+            }
+            "--batch-size" => batch_options.max_batch_size = parse_number(&arg, &value()?)?,
+            "--model-cache" => model_cache = Some(PathBuf::from(value()?)),
+            "--ort-lib" => ort_lib = Some(value()?),
+            "--manifest" => manifest = Some(PathBuf::from(value()?)),
+            "--report" => report = Some(PathBuf::from(value()?)),
+            _ if arg.starts_with("--") => anyhow::bail!("Unknown option: {arg}"),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    let command = if eval {
+        if !files.is_empty() {
+            anyhow::bail!("eval reads its audio from --manifest, not from arguments");
+        }
+        Command::Eval {
+            manifest: manifest.context("eval requires --manifest")?,
+            report,
+        }
+    } else {
+        if manifest.is_some() || report.is_some() {
+            anyhow::bail!("--manifest and --report are options of eval");
+        }
+        if files.is_empty() {
+            anyhow::bail!("No input files");
+        }
+        Command::Transcribe { files }
+    };
+    let model_dir = model_dir.context("--model is required")?;
+    Ok(Some(CliOptions {
+        command,
+        model_dir,
+        format,
+        runtime_options,
+        decode_options: DecodeOptions::from_ids(&decode_strategy, beam_width),
+        long_form,
+        batch_options,
+        model_cache,
+        ort_lib,
+    }))
+}
+
+impl CliOptions {
+    fn group_size(&self) -> usize {
+        self.batch_options.max_batch_size.max(1) * BATCHES_PER_GROUP
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .ok()
+        .with_context(|| format!("{option} expects a non-negative integer, got {value}"))
+}
+
+/// A WAV file resampled to 16 kHz, with what reading it took.
+struct Audio {
+    samples: Vec<f32>,
+    pcm_to_f32_ms: u128,
+    resample_ms: u128,
+}
+
+fn read_audio(path: &Path) -> Result<Audio> {
+    let read_start = Instant::now();
+    let (mut samples, sample_rate) = read_wav(path)?;
+    let pcm_to_f32_ms = read_start.elapsed().as_millis();
+
+    let resample_start = Instant::now();
+    if sample_rate != TARGET_SAMPLE_RATE {
+        samples = resample(
+            &samples,
+            sample_rate,
+            TARGET_SAMPLE_RATE,
+            ResampleQuality::default(),
+        );
+    }
+    Ok(Audio {
+        samples,
+        pcm_to_f32_ms,
+        resample_ms: resample_start.elapsed().as_millis(),
+    })
+}
+
+/// Transcribes WAV files the way the app transcribes recordings: resampled to 16 kHz
+/// and split into long-form chunks when longer than `long_form` allows. The other
+/// files go through one `transcribe_batch` call and share its time equally.
+fn transcribe_group(
+    engine: &mut GigaamEngine,
+    paths: &[&Path],
+    long_form: &LongFormOptions,
+) -> Vec<Result<(NativeTranscriptionReport, TranscriptionMetrics)>> {
+    let audio = paths
+        .iter()
+        .map(|path| read_audio(path))
+        .collect::<Vec<_>>();
+    let max_segment_samples = long_form.max_segment_samples();
+    let is_long =
+        |audio: &Audio| max_segment_samples > 0 && audio.samples.len() > max_segment_samples;
+
+    let short = audio
+        .iter()
+        .flatten()
+        .filter(|audio| !is_long(audio))
+        .map(|audio| audio.samples.as_slice())
+        .collect::<Vec<_>>();
+    let batch_start = Instant::now();
+    // The error is kept as text, since every file of the batch reports it.
+    let mut batch = engine
+        .transcribe_batch(&short, long_form)
+        .map(Vec::into_iter)
+        .map_err(|error| format!("{error:#}"));
+    let batch_ms = batch_start.elapsed().as_millis() / short.len().max(1) as u128;
+
+    audio
+        .into_iter()
+        .map(|audio| {
+            let audio = audio?;
+            let (report, inference_ms) = if is_long(&audio) {
+                let inference_start = Instant::now();
+                let report = engine.transcribe_long_form(&audio.samples, None, long_form)?;
+                (report, inference_start.elapsed().as_millis())
+            } else {
+                match &mut batch {
+                    Ok(reports) => (reports.next().context("Missing batch result")?, batch_ms),
+                    Err(error) => anyhow::bail!("{error}"),
+                }
+            };
+
+            let model_load = engine.load_metrics().cloned().unwrap_or_default();
+            let mut metrics = TranscriptionMetrics::from_report(
+                &report,
+                audio.samples.len(),
+                TARGET_SAMPLE_RATE,
+                model_load,
+            );
+            metrics.pcm_to_f32_ms = audio.pcm_to_f32_ms;
+            metrics.resample_ms = audio.resample_ms;
+            metrics.wall_ms = audio.pcm_to_f32_ms + audio.resample_ms + inference_ms;
+            Ok((report, metrics))
+        })
+        .collect()
+}
+
+/// Reads a WAV file as mono samples in -1..1 and its sample rate.
+fn read_wav(path: &Path) -> Result<(Vec<f32>, usize)> {
+    let mut reader = hound::WavReader::open(path)
+        .with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+    let spec = reader.spec();
+    let channels = usize::from(spec.channels.max(1));
+
+    let interleaved = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let max_amplitude = (1_i64 << spec.bits_per_sample.saturating_sub(1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / max_amplitude))
+                .collect()
+        }
+    }
+    .with_context(|| format!("Failed to read WAV file: {}", path.display()))?;
+
+    let mono = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    Ok((mono, spec.sample_rate as usize))
+}
+
+fn format_plain(
+    path: &Path,
+    report: &NativeTranscriptionReport,
+    metrics: &TranscriptionMetrics,
+) -> String {
+    let mut lines = vec![format!("{}: {}", path.display(), report.text)];
+    for word in &report.words {
+        lines.push(format!(
+            "  {:>8.2} {:>8.2}  {} ({:.2})",
+            word.start_ms as f64 / 1000.0,
+            word.end_ms as f64 / 1000.0,
+            word.text,
+            word.confidence
+        ));
+    }
+    lines.push(format!(
+        "  audio {} ms, inference {} ms (RTF {:.3}), ort {} ms, load {} ms",
+        metrics.audio_ms,
+        metrics.total_ms,
+        metrics.real_time_factor,
+        metrics.ort_run_ms,
+        metrics.load_ms
+    ));
+    lines.join("\n")
+}
+
+/// One file of the `json` and `jsonl` output; a failed file has only an `error`.
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<TranscriptResult<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<&'a TranscriptionMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl<'a> FileRecord<'a> {
+    fn transcribed(
+        path: &Path,
+        report: &'a NativeTranscriptionReport,
+        metrics: &'a TranscriptionMetrics,
+    ) -> Self {
+        Self {
+            file: path.display().to_string(),
+            result: Some(report.result()),
+            metrics: Some(metrics),
+            error: None,
+        }
+    }
+
+    fn failed(path: &Path, error: &anyhow::Error) -> Self {
+        Self {
+            file: path.display().to_string(),
+            result: None,
+            metrics: None,
+            error: Some(format!("{error:#}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn arguments_select_model_files_and_options() -> Result<()> {
+        let options = parse_args(args(&[
+            "--model",
+            "models/v3_e2e_ctc",
+            "--format",
+            "jsonl",
+            "--runtime",
+            r#"{"accelerator_mode": "cpu", "intra_threads": 2}"#,
+            "--max-segment-ms",
+            "0",
+            "--batch-size",
+            "4",
+            "a.wav",
+            "b.wav",
+        ]))?
+        .expect("not a help request");
+        assert_eq!(options.model_dir, Path::new("models/v3_e2e_ctc"));
+        assert_eq!(
+            options.command,
+            Command::Transcribe {
+                files: vec![PathBuf::from("a.wav"), PathBuf::from("b.wav")]
+            }
+        );
+        assert_eq!(options.format, OutputFormat::Jsonl);
+        assert_eq!(options.runtime_options.intra_threads, 2);
+        assert_eq!(options.long_form.max_segment_samples(), 0);
+        assert_eq!(options.batch_options.max_batch_size, 4);
+        assert_eq!(options.group_size(), 4 * BATCHES_PER_GROUP);
+        assert_eq!(options.decode_options, DecodeOptions::default());
+
+        assert!(parse_args(args(&["--help"]))?.is_none());
+        assert!(parse_args(args(&["a.wav"])).is_err());
+        assert!(parse_args(args(&["--model", "m"])).is_err());
+        assert!(parse_args(args(&["--model", "m", "--format", "csv", "a.wav"])).is_err());
+        assert!(parse_args(args(&["--model", "m", "--threads", "2", "a.wav"])).is_err());
+        assert!(parse_args(args(&["--model", "m", "--max-segment-ms", "20", "a.wav"])).is_err());
+        assert!(parse_args(args(&["--model", "m", "a.wav", "--beam-width"])).is_err());
+
+        let eval = parse_args(args(&["eval", "--model", "m", "--manifest", "set.jsonl"]))?
+            .expect("not a help request");
+        assert_eq!(eval.batch_options, BatchOptions::default());
+        assert_eq!(
+            eval.command,
+            Command::Eval {
+                manifest: PathBuf::from("set.jsonl"),
+                report: None
+            }
+        );
+        assert!(parse_args(args(&["eval", "--model", "m"])).is_err());
+        assert!(parse_args(args(&["eval", "--model", "m", "--manifest", "s", "a.wav"])).is_err());
+        assert!(parse_args(args(&["--model", "m", "--manifest", "s", "a.wav"])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn wav_files_are_read_as_mono() -> Result<()> {
+        let dir = ScratchDir::new("cli-wav");
+        let path = dir.join("stereo.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec)?;
+        for sample in [16_384_i16, 0, -16_384, -16_384] {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+
+        let (samples, sample_rate) = read_wav(&path)?;
+        assert_eq!(sample_rate, 8_000);
+        assert_eq!(samples, [0.25, -0.5]);
+        Ok(())
+    }
+
+    #[test]
+    fn failed_files_are_recorded_with_only_their_error() -> Result<()> {
+        let error = anyhow::anyhow!("bad \"header\"").context("Failed to read a.wav");
+        let record = FileRecord::failed(Path::new("dir/a.wav"), &error);
+        assert_eq!(
+            serde_json::to_string(&record)?,
+            "{\"file\":\"dir/a.wav\",\"error\":\"Failed to read a.wav: bad \\\"header\\\"\"}"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::decoder::AlignedToken;
+use ndarray::{s, ArrayView1, ArrayView3};
+
+const MIN_POSTERIOR: f32 = 1e-12;
+
+/// How the model's `[1, T, V]` output should be turned into posteriors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreKind {
+    Logits,
+    LogProbs,
+}
+
+impl ScoreKind {
+    pub fn from_output_name(name: &str) -> Self {
+        match name {
+            "log_probs" => Self::LogProbs,
+            _ => Self::Logits,
+        }
+    }
+}
+
+/// Mean posterior of every token over the frames it spans.
+pub fn token_confidences(
+    logits: ArrayView3<'_, f32>,
+    tokens: &[AlignedToken],
+    kind: ScoreKind,
+) -> Vec<f32> {
+    let time_steps = logits.shape()[1];
+    tokens
+        .iter()
+        .map(|token| {
+            let end = token.end_frame.min(time_steps.saturating_sub(1));
+            if token.start_frame > end {
+                return 0.0;
+            }
+            let sum = (token.start_frame..=end)
+                .map(|frame_idx| posterior(logits.slice(s![0, frame_idx, ..]), token.id, kind))
+                .sum::<f32>();
+            sum / (end - token.start_frame + 1) as f32
+        })
+        .collect()
+}
+
+/// Geometric mean, so a single doubtful token pulls its word (or utterance) down.
+/// Returns 0 when there is nothing to aggregate.
+pub fn aggregate_confidence(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean_log = values
+        .iter()
+        .map(|value| value.max(MIN_POSTERIOR).ln())
+        .sum::<f32>()
+        / values.len() as f32;
+    mean_log.exp()
+}
+
+fn posterior(row: ArrayView1<'_, f32>, token_id: usize, kind: ScoreKind) -> f32 {
+    let Some(&score) = row.get(token_id) else {
+        return 0.0;
+    };
+    match kind {
+        ScoreKind::LogProbs => score.exp(),
+        ScoreKind::Logits => {
+            let max = row.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            if !max.is_finite() {
+                return 0.0;
+            }
+            let sum = row.iter().map(|&value| (value - max).exp()).sum::<f32>();
+            (score - max).exp() / sum
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use ndarray::Array3;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn logits_rows_are_softmax_normalized() -> Result<()> {
+        let uniform = [0.0, 0.0, 0.0];
+        let token_0_twice_as_likely = [2.0_f32.ln(), 0.0, 0.0];
+        let logits =
+            Array3::from_shape_vec((1, 2, 3), [uniform, token_0_twice_as_likely].concat())?;
+        let tokens = [
+            AlignedToken {
+                id: 0,
+                start_frame: 0,
+                end_frame: 0,
+            },
+            AlignedToken {
+                id: 0,
+                start_frame: 0,
+                end_frame: 1,
+            },
+        ];
+
+        let confidences = token_confidences(logits.view(), &tokens, ScoreKind::Logits);
+        assert_close(confidences[0], 1.0 / 3.0);
+        assert_close(confidences[1], (1.0 / 3.0 + 0.5) / 2.0);
+        Ok(())
+    }
+
+    #[test]
+    fn log_probs_are_used_directly() -> Result<()> {
+        let log_probs = Array3::from_shape_vec((1, 1, 2), vec![0.9_f32.ln(), 0.1_f32.ln()])?;
+        let tokens = [AlignedToken {
+            id: 1,
+            start_frame: 0,
+            end_frame: 0,
+        }];
+
+        let confidences = token_confidences(log_probs.view(), &tokens, ScoreKind::LogProbs);
+        assert_close(confidences[0], 0.1);
+        assert_eq!(
+            ScoreKind::from_output_name("log_probs"),
+            ScoreKind::LogProbs
+        );
+        assert_eq!(ScoreKind::from_output_name("logits"), ScoreKind::Logits);
+        Ok(())
+    }
+
+    #[test]
+    fn aggregation_is_a_geometric_mean() {
+        assert_close(aggregate_confidence(&[0.9, 0.4]), 0.6);
+        assert_close(aggregate_confidence(&[0.5]), 0.5);
+        assert_eq!(aggregate_confidence(&[]), 0.0);
+    }
+}
@@ -0,0 +1,342 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// The parts of a GigaAM model YAML config (`v3_e2e_ctc.yaml`) the native frontend
+/// needs. Other top-level sections (`head`, `decoding`, ...) are ignored. The
+/// preprocessor section is strict, since any key in it changes the features: every key
+/// is either applied, checked against what the frontend computes, or a known no-op.
+/// Keys a NeMo config leaves out take GigaAM's defaults, not NeMo's.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    pub model_name: Option<String>,
+    pub sample_rate: Option<usize>,
+    pub preprocessor: Option<PreprocessorSection>,
+    pub encoder: EncoderSection,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreprocessorSection {
+    /// Python class of the feature extractor; informational only.
+    #[serde(rename = "_target_", default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub sample_rate: Option<usize>,
+    /// Number of mel bands.
+    pub features: usize,
+    #[serde(default)]
+    pub win_length: Option<usize>,
+    #[serde(default)]
+    pub hop_length: Option<usize>,
+    #[serde(default)]
+    pub n_fft: Option<usize>,
+    #[serde(default)]
+    pub center: Option<bool>,
+    /// `reflect` or `constant` padding of a centered STFT.
+    #[serde(default)]
+    pub pad_mode: Option<String>,
+    /// `htk` or `slaney`.
+    #[serde(default)]
+    pub mel_scale: Option<String>,
+    /// `null` or `slaney`.
+    #[serde(default)]
+    pub mel_norm: Option<String>,
+    /// Lower edge of the mel filters in Hz (NeMo: `lowfreq`).
+    #[serde(default, alias = "lowfreq")]
+    pub f_min: Option<f64>,
+    /// Upper edge of the mel filters in Hz (NeMo: `highfreq`); the Nyquist frequency
+    /// when absent.
+    #[serde(default, alias = "highfreq")]
+    pub f_max: Option<f64>,
+    /// Preemphasis coefficient, as in NeMo configs.
+    #[serde(default)]
+    pub preemph: Option<f64>,
+    /// Standard deviation of the noise NeMo adds to the samples while training. Parsed so
+    /// that NeMo configs load, but never applied: a non-zero value is logged as ignored,
+    /// and the manifest's `preprocessing.dither` is what enables dithering at inference.
+    #[serde(default)]
+    pub dither: Option<f64>,
+    /// NeMo window length in seconds; `win_length` when that is absent.
+    #[serde(default)]
+    pub window_size: Option<f64>,
+    /// NeMo hop length in seconds; `hop_length` when that is absent.
+    #[serde(default)]
+    pub window_stride: Option<f64>,
+    /// NeMo analysis window; only `hann` is implemented.
+    #[serde(default)]
+    pub window: Option<String>,
+    /// NeMo feature normalization; only `NA` (none) matches this frontend.
+    #[serde(default)]
+    pub normalize: Option<String>,
+    /// NeMo switch between log and linear mel features; only `true` is supported.
+    #[serde(default)]
+    pub log: Option<bool>,
+    /// NeMo frame stacking factor; only 1 (no stacking) is supported.
+    #[serde(default)]
+    pub frame_splicing: Option<usize>,
+    /// NeMo padding of the feature length to a multiple of `pad_to`. A no-op here: the
+    /// padded frames are masked by the feature length anyway.
+    #[serde(default)]
+    pub pad_to: Option<usize>,
+    /// Value of the frames added for `pad_to`; a no-op like it.
+    #[serde(default)]
+    pub pad_value: Option<f64>,
+}
+
+/// Only `subsampling_factor` is read; the remaining encoder keys describe the network.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct EncoderSection {
+    pub subsampling_factor: Option<usize>,
+}
+
+impl ConfigFile {
+    /// Parses and validates a config. Errors name the offending key, e.g.
+    /// `preprocessor.hop_length: invalid type: string "abc", expected usize at line 6`.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config =
+            serde_yaml_ng::from_str::<Self>(content).context("Invalid GigaAM model config")?;
+        config.check().context("Invalid GigaAM model config")?;
+        Ok(config)
+    }
+
+    /// The preprocessor section, which `parse` guarantees is present.
+    pub fn preprocessor(&self) -> &PreprocessorSection {
+        self.preprocessor
+            .as_ref()
+            .expect("parsed configs have a preprocessor section")
+    }
+
+    pub fn sample_rate(&self) -> Option<usize> {
+        self.preprocessor().sample_rate.or(self.sample_rate)
+    }
+
+    /// `win_length`, or NeMo's `window_size` in samples.
+    pub fn win_length(&self) -> Option<usize> {
+        let preprocessor = self.preprocessor();
+        preprocessor
+            .win_length
+            .or_else(|| self.seconds_to_samples(preprocessor.window_size?))
+    }
+
+    /// `hop_length`, or NeMo's `window_stride` in samples.
+    pub fn hop_length(&self) -> Option<usize> {
+        let preprocessor = self.preprocessor();
+        preprocessor
+            .hop_length
+            .or_else(|| self.seconds_to_samples(preprocessor.window_stride?))
+    }
+
+    fn seconds_to_samples(&self, seconds: f64) -> Option<usize> {
+        Some((seconds * self.sample_rate()? as f64).round() as usize)
+    }
+
+    fn check(&self) -> Result<()> {
+        let Some(preprocessor) = &self.preprocessor else {
+            anyhow::bail!("preprocessor: missing section");
+        };
+        if let (Some(top), Some(nested)) = (self.sample_rate, preprocessor.sample_rate) {
+            if top != nested {
+                anyhow::bail!(
+                    "preprocessor.sample_rate: {nested} does not match sample_rate {top}"
+                );
+            }
+        }
+        let positive = [
+            ("sample_rate", preprocessor.sample_rate.or(self.sample_rate)),
+            ("preprocessor.features", Some(preprocessor.features)),
+            ("preprocessor.win_length", preprocessor.win_length),
+            ("preprocessor.hop_length", preprocessor.hop_length),
+            ("preprocessor.n_fft", preprocessor.n_fft),
+            (
+                "encoder.subsampling_factor",
+                self.encoder.subsampling_factor,
+            ),
+        ];
+        for (key, value) in positive {
+            if value == Some(0) {
+                anyhow::bail!("{key}: must be positive");
+            }
+        }
+        let durations = [
+            (
+                "preprocessor.window_size",
+                preprocessor.window_size,
+                ("preprocessor.win_length", preprocessor.win_length),
+            ),
+            (
+                "preprocessor.window_stride",
+                preprocessor.window_stride,
+                ("preprocessor.hop_length", preprocessor.hop_length),
+            ),
+        ];
+        for (key, seconds, (samples_key, samples)) in durations {
+            let Some(seconds) = seconds else {
+                continue;
+            };
+            let Some(converted) = self.seconds_to_samples(seconds) else {
+                anyhow::bail!("{key}: needs sample_rate to convert seconds to samples");
+            };
+            if !seconds.is_finite() || converted == 0 {
+                anyhow::bail!("{key}: must be at least one sample long");
+            }
+            if let Some(samples) = samples.filter(|&samples| samples != converted) {
+                anyhow::bail!(
+                    "{key}: {seconds} s is {converted} samples, but {samples_key} is {samples}"
+                );
+            }
+        }
+        let choices: [(&str, &Option<String>, &[&str]); 5] = [
+            (
+                "preprocessor.pad_mode",
+                &preprocessor.pad_mode,
+                &["reflect", "constant"],
+            ),
+            (
+                "preprocessor.mel_scale",
+                &preprocessor.mel_scale,
+                &["htk", "slaney"],
+            ),
+            ("preprocessor.mel_norm", &preprocessor.mel_norm, &["slaney"]),
+            ("preprocessor.window", &preprocessor.window, &["hann"]),
+            (
+                "preprocessor.normalize",
+                &preprocessor.normalize,
+                &["NA", "none"],
+            ),
+        ];
+        for (key, value, allowed) in choices {
+            if let Some(value) = value {
+                if !allowed
+                    .iter()
+                    .any(|choice| value.eq_ignore_ascii_case(choice))
+                {
+                    anyhow::bail!(
+                        "{key}: unsupported value {value:?}; expected one of {allowed:?}"
+                    );
+                }
+            }
+        }
+        if preprocessor.log == Some(false) {
+            anyhow::bail!("preprocessor.log: only log-mel features are supported");
+        }
+        if preprocessor
+            .frame_splicing
+            .is_some_and(|factor| factor != 1)
+        {
+            anyhow::bail!("preprocessor.frame_splicing: only 1 (no stacking) is supported");
+        }
+        // The ranges of f_min, f_max, preemph and dither are checked by the frontend,
+        // which also sees the values that come from the manifest.
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIPPED_CONFIG: &str = include_str!("../testdata/v3_e2e_ctc.yaml");
+
+    fn corrupt(from: &str, to: &str) -> String {
+        assert!(SHIPPED_CONFIG.contains(from), "fixture lacks {from:?}");
+        SHIPPED_CONFIG.replacen(from, to, 1)
+    }
+
+    fn error_message(content: &str) -> String {
+        format!("{:#}", ConfigFile::parse(content).unwrap_err())
+    }
+
+    #[test]
+    fn shipped_config_parses() {
+        let config = ConfigFile::parse(SHIPPED_CONFIG).unwrap();
+        assert_eq!(config.model_name.as_deref(), Some("v3_e2e_ctc"));
+        assert_eq!(config.sample_rate, Some(16_000));
+        assert_eq!(config.encoder.subsampling_factor, Some(4));
+        let preprocessor = config.preprocessor();
+        assert_eq!(preprocessor.features, 64);
+        assert_eq!(preprocessor.win_length, Some(320));
+        assert_eq!(preprocessor.hop_length, Some(160));
+        assert_eq!(preprocessor.n_fft, Some(320));
+        assert_eq!(preprocessor.center, Some(false));
+        assert_eq!(preprocessor.mel_scale.as_deref(), Some("htk"));
+
+        let quoted = corrupt("mel_scale: htk", "mel_scale: \"htk\"  # quoted");
+        let config = ConfigFile::parse(&quoted).unwrap();
+        assert_eq!(config.preprocessor().mel_scale.as_deref(), Some("htk"));
+    }
+
+    #[test]
+    fn corrupted_configs_report_the_key() {
+        let message = error_message(&corrupt("hop_length: 160", "hop_length: abc"));
+        assert!(message.contains("preprocessor.hop_length: invalid type: string \"abc\""));
+        let message = error_message(&corrupt("center: false", "center: maybe"));
+        assert!(message.contains("preprocessor.center: invalid type"));
+        let message = error_message(&corrupt("n_fft: 320", "nfft: 320"));
+        assert!(message.contains("preprocessor: unknown field `nfft`"));
+        let message = error_message(&corrupt("  features: 64\n", ""));
+        assert!(message.contains("preprocessor: missing field `features`"));
+        let message = error_message(&corrupt("  sample_rate: 16000", "  sample_rate: 8000"));
+        assert!(message.contains("preprocessor.sample_rate: 8000 does not match"));
+        let message = error_message(&corrupt("win_length: 320", "win_length: 0"));
+        assert!(message.contains("preprocessor.win_length: must be positive"));
+        let message = error_message(&corrupt("mel_norm: null", "mel_norm: area"));
+        assert!(message.contains("preprocessor.mel_norm: unsupported value \"area\""));
+        let message = error_message(&corrupt("center: false", "pad_mode: wrap"));
+        assert!(message.contains("preprocessor.pad_mode: unsupported value"));
+        let message = error_message(&SHIPPED_CONFIG.replace("preprocessor:", "frontend:"));
+        assert!(message.contains("preprocessor: missing section"));
+        assert!(ConfigFile::parse("preprocessor: [1, 2]").is_err());
+    }
+
+    #[test]
+    fn nemo_preprocessor_keys_are_applied_or_checked() {
+        let nemo = include_str!("../testdata/nemo_conformer_ctc.yaml");
+        let message = error_message(nemo);
+        assert!(message.contains("preprocessor.normalize: unsupported value \"per_feature\""));
+
+        let nemo = nemo.replace("normalize: per_feature", "normalize: NA");
+        let config = ConfigFile::parse(&nemo).unwrap();
+        assert_eq!(config.sample_rate(), Some(16_000));
+        assert_eq!(config.win_length(), Some(400));
+        assert_eq!(config.hop_length(), Some(160));
+        assert_eq!(config.encoder.subsampling_factor, Some(4));
+        let preprocessor = config.preprocessor();
+        assert_eq!(preprocessor.features, 80);
+        assert_eq!(preprocessor.dither, Some(1e-5));
+        assert_eq!(preprocessor.pad_to, Some(0));
+
+        let corrupt = |from: &str, to: &str| {
+            assert!(nemo.contains(from), "fixture lacks {from:?}");
+            nemo.replacen(from, to, 1)
+        };
+        let bounded = ConfigFile::parse(&corrupt(
+            "n_fft: 512",
+            "n_fft: 512\n  lowfreq: 20\n  highfreq: 7600",
+        ))
+        .unwrap();
+        assert_eq!(bounded.preprocessor().f_min, Some(20.0));
+        assert_eq!(bounded.preprocessor().f_max, Some(7600.0));
+
+        let message = error_message(&corrupt("window: hann", "window: hamming"));
+        assert!(message.contains("preprocessor.window: unsupported value \"hamming\""));
+        let message = error_message(&corrupt("log: true", "log: false"));
+        assert!(message.contains("preprocessor.log: only log-mel features"));
+        let message = error_message(&corrupt("frame_splicing: 1", "frame_splicing: 3"));
+        assert!(message.contains("preprocessor.frame_splicing: only 1"));
+        let message = error_message(&corrupt(
+            "window_size: 0.025",
+            "window_size: 0.025\n  win_length: 320",
+        ));
+        assert!(message
+            .contains("window_size: 0.025 s is 400 samples, but preprocessor.win_length is 320"));
+        let message = error_message(&corrupt("window_stride: 0.01", "window_stride: 0.0"));
+        assert!(message.contains("preprocessor.window_stride: must be at least one sample long"));
+        let message = error_message(&corrupt(
+            "  pad_to: 0\n",
+            "  pad_to: 0\n  stft_conv: false\n",
+        ));
+        assert!(message.contains("preprocessor: unknown field `stft_conv`"));
+    }
+}
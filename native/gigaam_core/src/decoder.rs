@@ -0,0 +1,807 @@
+use ndarray::{s, ArrayView1, ArrayView3};
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+const DEFAULT_BEAM_WIDTH: usize = 8;
+const DEFAULT_TOKEN_MIN_LOG_PROB: f32 = -10.0;
+const DEFAULT_BEAM_PRUNE_MARGIN: f32 = 12.0;
+const DEFAULT_LM_ALPHA: f32 = 0.5;
+const DEFAULT_LM_BETA: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeStrategy {
+    Greedy,
+    BeamSearch,
+}
+
+impl DecodeStrategy {
+    pub fn from_id(value: &str) -> Self {
+        match value {
+            "beam" => Self::BeamSearch,
+            _ => Self::Greedy,
+        }
+    }
+
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::Greedy => "greedy",
+            Self::BeamSearch => "beam",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeOptions {
+    pub strategy: DecodeStrategy,
+    /// Maximum number of prefixes kept after each frame.
+    pub beam_width: usize,
+    /// Non-blank tokens whose per-frame log-probability is below this value are not expanded.
+    pub token_min_log_prob: f32,
+    /// Prefixes scoring more than this many nats below the best prefix are dropped;
+    /// a negative margin falls back to the default.
+    pub beam_prune_margin: f32,
+    /// Weight of the n-gram language model log-probability during beam search.
+    pub lm_alpha: f32,
+    /// Word-insertion bonus added for every word scored by the language model.
+    pub lm_beta: f32,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            strategy: DecodeStrategy::Greedy,
+            beam_width: DEFAULT_BEAM_WIDTH,
+            token_min_log_prob: DEFAULT_TOKEN_MIN_LOG_PROB,
+            beam_prune_margin: DEFAULT_BEAM_PRUNE_MARGIN,
+            lm_alpha: DEFAULT_LM_ALPHA,
+            lm_beta: DEFAULT_LM_BETA,
+        }
+    }
+}
+
+impl DecodeOptions {
+    pub fn from_ids(strategy: &str, beam_width: usize) -> Self {
+        Self {
+            strategy: DecodeStrategy::from_id(strategy),
+            beam_width: if beam_width == 0 {
+                DEFAULT_BEAM_WIDTH
+            } else {
+                beam_width
+            },
+            ..Self::default()
+        }
+    }
+
+    pub fn with_lm_weights(self, lm_alpha: f32, lm_beta: f32) -> Self {
+        Self {
+            lm_alpha: if lm_alpha.is_finite() && lm_alpha >= 0.0 {
+                lm_alpha
+            } else {
+                DEFAULT_LM_ALPHA
+            },
+            lm_beta: if lm_beta.is_finite() {
+                lm_beta
+            } else {
+                DEFAULT_LM_BETA
+            },
+            ..self
+        }
+    }
+
+    pub fn decode(
+        &self,
+        logits: ArrayView3<'_, f32>,
+        encoded_len: usize,
+        blank_idx: usize,
+        scorers: &[&dyn DynPrefixScorer],
+    ) -> Vec<AlignedToken> {
+        match self.strategy {
+            DecodeStrategy::Greedy => ctc_greedy_decode(logits, encoded_len, blank_idx),
+            DecodeStrategy::BeamSearch => {
+                ctc_prefix_beam_search(logits, encoded_len, blank_idx, self, scorers)
+            }
+        }
+    }
+
+    /// Up to `max_hypotheses` best hypotheses, best first. Always runs beam search,
+    /// widening the beam when it is narrower than the requested list.
+    pub fn decode_nbest(
+        &self,
+        logits: ArrayView3<'_, f32>,
+        encoded_len: usize,
+        blank_idx: usize,
+        scorers: &[&dyn DynPrefixScorer],
+        max_hypotheses: usize,
+    ) -> Vec<Hypothesis> {
+        let options = Self {
+            strategy: DecodeStrategy::BeamSearch,
+            beam_width: self.beam_width.max(max_hypotheses),
+            ..*self
+        };
+        ctc_prefix_beam_search_nbest(
+            logits,
+            encoded_len,
+            blank_idx,
+            &options,
+            scorers,
+            max_hypotheses,
+        )
+    }
+}
+
+/// Emitted token with the encoder frames it spans (both ends inclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignedToken {
+    pub id: usize,
+    pub start_frame: usize,
+    pub end_frame: usize,
+}
+
+/// One complete beam search result. `score` is the natural-log acoustic score plus
+/// any fused scorer contributions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hypothesis {
+    pub tokens: Vec<AlignedToken>,
+    pub score: f32,
+}
+
+/// Extra score contributed by an external model (language model, hotwords) while a
+/// prefix grows. Scores are natural-log and already weighted.
+pub trait PrefixScorer {
+    /// What the scorer remembers about a prefix. It travels with the beam, so extending
+    /// a prefix does not require walking it again from the start.
+    type State: Clone + 'static;
+
+    /// State of the empty prefix.
+    fn initial_state(&self) -> Self::State;
+
+    /// Score added when `token` is appended to a prefix whose state is `state`, along
+    /// with the state of the extended prefix.
+    fn extension_score(&self, state: &Self::State, token: usize) -> (f32, Self::State);
+
+    /// Score added once per surviving prefix after the last frame, e.g. for an
+    /// unfinished trailing word.
+    fn final_score(&self, state: &Self::State) -> f32;
+}
+
+/// Object-safe form of [`PrefixScorer`], so that scorers with different states can be
+/// fused into one search. Each scorer only ever sees the states it made.
+pub trait DynPrefixScorer {
+    fn initial_state(&self) -> ScorerState;
+    fn extension_score(&self, state: &ScorerState, token: usize) -> (f32, ScorerState);
+    fn final_score(&self, state: &ScorerState) -> f32;
+}
+
+impl<S: PrefixScorer> DynPrefixScorer for S {
+    fn initial_state(&self) -> ScorerState {
+        ScorerState(Box::new(PrefixScorer::initial_state(self)))
+    }
+
+    fn extension_score(&self, state: &ScorerState, token: usize) -> (f32, ScorerState) {
+        let (score, next) = PrefixScorer::extension_score(self, state.get::<S::State>(), token);
+        (score, ScorerState(Box::new(next)))
+    }
+
+    fn final_score(&self, state: &ScorerState) -> f32 {
+        PrefixScorer::final_score(self, state.get::<S::State>())
+    }
+}
+
+/// One scorer's state for one prefix, type-erased.
+pub struct ScorerState(Box<dyn AnyState>);
+
+impl ScorerState {
+    fn get<T: 'static>(&self) -> &T {
+        self.0
+            .as_any()
+            .downcast_ref()
+            .expect("scorer states are only passed back to the scorer that made them")
+    }
+}
+
+impl std::fmt::Debug for ScorerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScorerState").finish_non_exhaustive()
+    }
+}
+
+impl Clone for ScorerState {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+trait AnyState {
+    fn as_any(&self) -> &dyn Any;
+    fn clone_box(&self) -> Box<dyn AnyState>;
+}
+
+impl<T: Clone + 'static> AnyState for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyState> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PrefixScore {
+    blank: f32,
+    non_blank: f32,
+    fusion: f32,
+}
+
+impl PrefixScore {
+    fn empty(fusion: f32) -> Self {
+        Self {
+            blank: f32::NEG_INFINITY,
+            non_blank: f32::NEG_INFINITY,
+            fusion,
+        }
+    }
+
+    fn acoustic(&self) -> f32 {
+        log_add(self.blank, self.non_blank)
+    }
+
+    fn total(&self) -> f32 {
+        self.acoustic() + self.fusion
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Beam {
+    score: PrefixScore,
+    // Frame at which each prefix token was first emitted, taken from the most
+    // probable path that reached this prefix.
+    token_frames: Vec<usize>,
+    token_frames_log_prob: f32,
+    // One entry per scorer, in the order the scorers were passed.
+    scorer_states: Vec<ScorerState>,
+}
+
+impl Beam {
+    fn empty(fusion: f32, scorer_states: Vec<ScorerState>) -> Self {
+        Self {
+            score: PrefixScore::empty(fusion),
+            token_frames: Vec::new(),
+            token_frames_log_prob: f32::NEG_INFINITY,
+            scorer_states,
+        }
+    }
+
+    fn add_path(
+        &mut self,
+        blank: bool,
+        log_prob: f32,
+        parent_frames: &[usize],
+        new_frame: Option<usize>,
+    ) {
+        if blank {
+            self.score.blank = log_add(self.score.blank, log_prob);
+        } else {
+            self.score.non_blank = log_add(self.score.non_blank, log_prob);
+        }
+        if log_prob > self.token_frames_log_prob {
+            self.token_frames.clear();
+            self.token_frames.extend_from_slice(parent_frames);
+            self.token_frames.extend(new_frame);
+            self.token_frames_log_prob = log_prob;
+        }
+    }
+}
+
+pub fn token_ids(tokens: &[AlignedToken]) -> Vec<usize> {
+    tokens.iter().map(|token| token.id).collect()
+}
+
+pub fn ctc_greedy_decode(
+    logits: ArrayView3<'_, f32>,
+    encoded_len: usize,
+    blank_idx: usize,
+) -> Vec<AlignedToken> {
+    let time_steps = logits.shape()[1];
+    let usable_steps = encoded_len.min(time_steps);
+
+    let mut tokens = Vec::<AlignedToken>::with_capacity(usable_steps);
+    let mut prev_token = blank_idx;
+
+    for frame_idx in 0..usable_steps {
+        let frame = logits.slice(s![0, frame_idx, ..]);
+        let best_idx = frame
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(idx, _)| idx)
+            .unwrap_or(blank_idx);
+
+        if best_idx != blank_idx {
+            if best_idx == prev_token {
+                if let Some(last) = tokens.last_mut() {
+                    last.end_frame = frame_idx;
+                }
+            } else {
+                tokens.push(AlignedToken {
+                    id: best_idx,
+                    start_frame: frame_idx,
+                    end_frame: frame_idx,
+                });
+            }
+        }
+        prev_token = best_idx;
+    }
+
+    tokens
+}
+
+/// CTC prefix beam search over `[1, T, V]` scores.
+///
+/// Each row is log-softmax normalized first, so both raw `logits` and `log_probs`
+/// outputs are accepted. `scorers` are fused into the ranking of every prefix.
+pub fn ctc_prefix_beam_search(
+    logits: ArrayView3<'_, f32>,
+    encoded_len: usize,
+    blank_idx: usize,
+    options: &DecodeOptions,
+    scorers: &[&dyn DynPrefixScorer],
+) -> Vec<AlignedToken> {
+    ctc_prefix_beam_search_nbest(logits, encoded_len, blank_idx, options, scorers, 1)
+        .into_iter()
+        .next()
+        .map(|hypothesis| hypothesis.tokens)
+        .unwrap_or_default()
+}
+
+/// Same search as [`ctc_prefix_beam_search`], returning up to `max_hypotheses`
+/// surviving prefixes ranked best first.
+pub fn ctc_prefix_beam_search_nbest(
+    logits: ArrayView3<'_, f32>,
+    encoded_len: usize,
+    blank_idx: usize,
+    options: &DecodeOptions,
+    scorers: &[&dyn DynPrefixScorer],
+    max_hypotheses: usize,
+) -> Vec<Hypothesis> {
+    let time_steps = logits.shape()[1];
+    let usable_steps = encoded_len.min(time_steps);
+    let beam_width = options.beam_width.max(1);
+    // A negative margin would prune every prefix, the best one included.
+    let prune_margin = if options.beam_prune_margin >= 0.0 {
+        options.beam_prune_margin
+    } else {
+        DEFAULT_BEAM_PRUNE_MARGIN
+    };
+
+    let initial_states = scorers
+        .iter()
+        .map(|scorer| scorer.initial_state())
+        .collect();
+    let mut initial = Beam::empty(0.0, initial_states);
+    initial.add_path(true, 0.0, &[], None);
+    let mut beams = vec![(Vec::<usize>::new(), initial)];
+    let mut log_probs = Vec::new();
+    let mut candidates = Vec::new();
+
+    for frame_idx in 0..usable_steps {
+        log_softmax_into(logits.slice(s![0, frame_idx, ..]), &mut log_probs);
+        collect_candidate_tokens(
+            &log_probs,
+            blank_idx,
+            options.token_min_log_prob,
+            &mut candidates,
+        );
+        let blank_log_prob = log_probs
+            .get(blank_idx)
+            .copied()
+            .unwrap_or(f32::NEG_INFINITY);
+
+        let mut next =
+            HashMap::<Vec<usize>, Beam>::with_capacity(beams.len() * (candidates.len() + 1));
+        for (prefix, beam) in &beams {
+            let score = beam.score;
+            let frames = beam.token_frames.as_slice();
+            let states = &beam.scorer_states;
+            next.entry(prefix.clone())
+                .or_insert_with(|| Beam::empty(score.fusion, states.clone()))
+                .add_path(true, score.acoustic() + blank_log_prob, frames, None);
+
+            for &token in &candidates {
+                let token_log_prob = log_probs[token];
+                let mut extended = prefix.clone();
+                extended.push(token);
+                let grow = next.entry(extended).or_insert_with(|| {
+                    let (fusion, extended_states) = extension_score(scorers, states, token);
+                    Beam::empty(score.fusion + fusion, extended_states)
+                });
+
+                if prefix.last() == Some(&token) {
+                    // A repeat without an intervening blank collapses into the same prefix;
+                    // only paths ending in blank may start a new copy of the token.
+                    grow.add_path(false, score.blank + token_log_prob, frames, Some(frame_idx));
+                    next.entry(prefix.clone())
+                        .or_insert_with(|| Beam::empty(score.fusion, states.clone()))
+                        .add_path(false, score.non_blank + token_log_prob, frames, None);
+                } else {
+                    grow.add_path(
+                        false,
+                        score.acoustic() + token_log_prob,
+                        frames,
+                        Some(frame_idx),
+                    );
+                }
+            }
+        }
+
+        beams = prune_beams(next, beam_width, prune_margin);
+    }
+
+    if !scorers.is_empty() {
+        for (_, beam) in &mut beams {
+            beam.score.fusion += scorers
+                .iter()
+                .zip(&beam.scorer_states)
+                .map(|(scorer, state)| scorer.final_score(state))
+                .sum::<f32>();
+        }
+        sort_beams(&mut beams);
+    }
+
+    beams
+        .into_iter()
+        .take(max_hypotheses.max(1))
+        .map(|(prefix, beam)| Hypothesis {
+            tokens: align_tokens(logits, usable_steps, blank_idx, &prefix, &beam.token_frames),
+            score: beam.score.total(),
+        })
+        .collect()
+}
+
+/// Pairs prefix tokens with their first frame and extends each token over the
+/// following frames where it still outscores blank, up to the next token.
+fn align_tokens(
+    logits: ArrayView3<'_, f32>,
+    usable_steps: usize,
+    blank_idx: usize,
+    prefix: &[usize],
+    token_frames: &[usize],
+) -> Vec<AlignedToken> {
+    prefix
+        .iter()
+        .zip(token_frames)
+        .enumerate()
+        .map(|(index, (&id, &start_frame))| {
+            let limit = token_frames
+                .get(index + 1)
+                .copied()
+                .unwrap_or(usable_steps)
+                .max(start_frame + 1);
+            let mut end_frame = start_frame;
+            while end_frame + 1 < limit {
+                let frame = logits.slice(s![0, end_frame + 1, ..]);
+                let blank = frame.get(blank_idx).copied().unwrap_or(f32::NEG_INFINITY);
+                if frame.get(id).is_none_or(|&score| score < blank) {
+                    break;
+                }
+                end_frame += 1;
+            }
+            AlignedToken {
+                id,
+                start_frame,
+                end_frame,
+            }
+        })
+        .collect()
+}
+
+fn extension_score(
+    scorers: &[&dyn DynPrefixScorer],
+    states: &[ScorerState],
+    token: usize,
+) -> (f32, Vec<ScorerState>) {
+    let mut total = 0.0;
+    let mut extended = Vec::with_capacity(scorers.len());
+    for (scorer, state) in scorers.iter().zip(states) {
+        let (score, state) = scorer.extension_score(state, token);
+        total += score;
+        extended.push(state);
+    }
+    (total, extended)
+}
+
+fn prune_beams(
+    candidates: HashMap<Vec<usize>, Beam>,
+    beam_width: usize,
+    prune_margin: f32,
+) -> Vec<(Vec<usize>, Beam)> {
+    let mut beams = candidates
+        .into_iter()
+        .filter(|(_, beam)| beam.score.total() > f32::NEG_INFINITY)
+        .collect::<Vec<_>>();
+    sort_beams(&mut beams);
+
+    if let Some(best) = beams.first().map(|(_, beam)| beam.score.total()) {
+        let floor = best - prune_margin;
+        beams.retain(|(_, beam)| beam.score.total() >= floor);
+    }
+    beams.truncate(beam_width);
+    beams
+}
+
+fn sort_beams(beams: &mut [(Vec<usize>, Beam)]) {
+    beams.sort_by(|(a_prefix, a_beam), (b_prefix, b_beam)| {
+        b_beam
+            .score
+            .total()
+            .partial_cmp(&a_beam.score.total())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a_prefix.cmp(b_prefix))
+    });
+}
+
+fn collect_candidate_tokens(
+    log_probs: &[f32],
+    blank_idx: usize,
+    token_min_log_prob: f32,
+    candidates: &mut Vec<usize>,
+) {
+    candidates.clear();
+    let mut best: Option<(usize, f32)> = None;
+    for (token, &log_prob) in log_probs.iter().enumerate() {
+        if token == blank_idx {
+            continue;
+        }
+        if log_prob >= token_min_log_prob {
+            candidates.push(token);
+        }
+        if best.is_none_or(|(_, best_log_prob)| log_prob > best_log_prob) {
+            best = Some((token, log_prob));
+        }
+    }
+    if candidates.is_empty() {
+        if let Some((token, log_prob)) = best {
+            if log_prob > f32::NEG_INFINITY {
+                candidates.push(token);
+            }
+        }
+    }
+}
+
+pub fn log_softmax_into(row: ArrayView1<'_, f32>, out: &mut Vec<f32>) {
+    out.clear();
+    let max = row.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if !max.is_finite() {
+        out.extend(row.iter().map(|_| f32::NEG_INFINITY));
+        return;
+    }
+    let log_sum = row
+        .iter()
+        .map(|&value| (value - max).exp())
+        .sum::<f32>()
+        .ln()
+        + max;
+    out.extend(row.iter().map(|&value| value - log_sum));
+}
+
+#[inline]
+fn log_add(a: f32, b: f32) -> f32 {
+    if a == f32::NEG_INFINITY {
+        return b;
+    }
+    if b == f32::NEG_INFINITY {
+        return a;
+    }
+    let (max, min) = if a > b { (a, b) } else { (b, a) };
+    max + (min - max).exp().ln_1p()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use ndarray::Array3;
+
+    fn beam_options(beam_width: usize) -> DecodeOptions {
+        DecodeOptions {
+            strategy: DecodeStrategy::BeamSearch,
+            beam_width,
+            ..DecodeOptions::default()
+        }
+    }
+
+    #[test]
+    fn prefix_beam_search_collapses_repeats() -> Result<()> {
+        let logits = Array3::from_shape_vec(
+            (1, 6, 4),
+            vec![
+                0.0, 5.0, 1.0, -1.0, // token 1
+                0.0, 4.0, 1.0, -1.0, // repeated token 1 (collapsed)
+                0.0, 1.0, 0.0, 3.0, // blank
+                0.0, 6.0, 0.0, -1.0, // token 1 again (kept because blank separated)
+                0.0, 1.0, 5.0, -1.0, // token 2
+                0.0, 1.0, 4.0, -1.0, // repeated token 2 (collapsed)
+            ],
+        )?;
+
+        let beam = ctc_prefix_beam_search(logits.view(), 6, 3, &beam_options(4), &[]);
+        assert_eq!(token_ids(&beam), vec![1, 1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn decoders_report_token_frame_spans() -> Result<()> {
+        let logits = Array3::from_shape_vec(
+            (1, 7, 3),
+            vec![
+                0.0, 0.0, 5.0, // blank
+                5.0, 0.0, 0.0, // token 0 starts
+                5.0, 0.0, 0.0, // token 0 continues
+                0.0, 0.0, 5.0, // blank
+                0.0, 5.0, 0.0, // token 1
+                0.0, 0.0, 5.0, // blank
+                0.0, 0.0, 5.0, // blank
+            ],
+        )?;
+        let expected = vec![
+            AlignedToken {
+                id: 0,
+                start_frame: 1,
+                end_frame: 2,
+            },
+            AlignedToken {
+                id: 1,
+                start_frame: 4,
+                end_frame: 4,
+            },
+        ];
+
+        assert_eq!(ctc_greedy_decode(logits.view(), 7, 2), expected);
+        assert_eq!(
+            ctc_prefix_beam_search(logits.view(), 7, 2, &beam_options(4), &[]),
+            expected
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn beam_search_merges_paths_that_greedy_discards() -> Result<()> {
+        // P(blank) = 0.6 and P(a) = 0.4 in both frames: greedy emits nothing, while the
+        // merged probability of "a" (0.64) beats the empty prefix (0.36).
+        let (blank, a) = (0.6_f32.ln(), 0.4_f32.ln());
+        let log_probs = Array3::from_shape_vec((1, 2, 2), vec![a, blank, a, blank])?;
+
+        assert!(ctc_greedy_decode(log_probs.view(), 2, 1).is_empty());
+        let beam = ctc_prefix_beam_search(log_probs.view(), 2, 1, &beam_options(4), &[]);
+        assert_eq!(token_ids(&beam), vec![0]);
+        Ok(())
+    }
+
+    #[test]
+    fn nbest_lists_distinct_hypotheses_best_first() -> Result<()> {
+        let (blank, a) = (0.6_f32.ln(), 0.4_f32.ln());
+        let log_probs = Array3::from_shape_vec((1, 2, 2), vec![a, blank, a, blank])?;
+
+        let hypotheses = DecodeOptions::default().decode_nbest(log_probs.view(), 2, 1, &[], 3);
+        let ids = hypotheses
+            .iter()
+            .map(|hypothesis| token_ids(&hypothesis.tokens))
+            .collect::<Vec<_>>();
+        // "aa" would need a blank between the two frames, so only two prefixes exist.
+        assert_eq!(ids, vec![vec![0], vec![]]);
+        assert!((hypotheses[0].score - 0.64_f32.ln()).abs() < 1e-5);
+        assert!((hypotheses[1].score - 0.36_f32.ln()).abs() < 1e-5);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_margin_drops_distant_prefixes_and_falls_back_when_negative() -> Result<()> {
+        // "a" and the empty prefix end 0.58 nats apart.
+        let (blank, a) = (0.6_f32.ln(), 0.4_f32.ln());
+        let log_probs = Array3::from_shape_vec((1, 2, 2), vec![a, blank, a, blank])?;
+        let surviving = |beam_prune_margin: f32| {
+            let options = DecodeOptions {
+                beam_prune_margin,
+                ..DecodeOptions::default()
+            };
+            options.decode_nbest(log_probs.view(), 2, 1, &[], 3).len()
+        };
+
+        assert_eq!(surviving(0.5), 1);
+        assert_eq!(surviving(0.6), 2);
+        assert_eq!(surviving(0.0), 1);
+        assert_eq!(surviving(-0.5), 2);
+        assert_eq!(surviving(f32::NAN), 2);
+        Ok(())
+    }
+
+    /// Charges one nat per token, counting them in its state.
+    struct LengthPenalty;
+
+    impl PrefixScorer for LengthPenalty {
+        type State = usize;
+
+        fn initial_state(&self) -> usize {
+            0
+        }
+
+        fn extension_score(&self, state: &usize, _token: usize) -> (f32, usize) {
+            (0.0, state + 1)
+        }
+
+        fn final_score(&self, state: &usize) -> f32 {
+            -(*state as f32)
+        }
+    }
+
+    /// Rewards a transcript ending in `token`.
+    struct FinalToken(usize);
+
+    impl PrefixScorer for FinalToken {
+        type State = Option<usize>;
+
+        fn initial_state(&self) -> Option<usize> {
+            None
+        }
+
+        fn extension_score(&self, _state: &Option<usize>, token: usize) -> (f32, Option<usize>) {
+            (0.0, Some(token))
+        }
+
+        fn final_score(&self, state: &Option<usize>) -> f32 {
+            if *state == Some(self.0) {
+                2.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    #[test]
+    fn scorers_with_different_states_are_fused() -> Result<()> {
+        let (blank, a) = (0.6_f32.ln(), 0.4_f32.ln());
+        let log_probs = Array3::from_shape_vec((1, 2, 2), vec![a, blank, a, blank])?;
+        let options = beam_options(4);
+
+        let penalized =
+            ctc_prefix_beam_search_nbest(log_probs.view(), 2, 1, &options, &[&LengthPenalty], 2);
+        assert!(penalized[0].tokens.is_empty());
+        assert!((penalized[1].score - (0.64_f32.ln() - 1.0)).abs() < 1e-5);
+
+        let scorers: [&dyn DynPrefixScorer; 2] = [&LengthPenalty, &FinalToken(0)];
+        let fused = ctc_prefix_beam_search_nbest(log_probs.view(), 2, 1, &options, &scorers, 2);
+        assert_eq!(token_ids(&fused[0].tokens), vec![0]);
+        assert!((fused[0].score - (0.64_f32.ln() + 1.0)).abs() < 1e-5);
+        Ok(())
+    }
+
+    #[test]
+    fn beam_search_respects_encoded_length() -> Result<()> {
+        let logits = Array3::from_shape_vec(
+            (1, 3, 3),
+            vec![
+                5.0, 0.0, 0.0, // token 0
+                0.0, 0.0, 5.0, // blank
+                0.0, 5.0, 0.0, // token 1, past the encoded length
+            ],
+        )?;
+
+        let beam = ctc_prefix_beam_search(logits.view(), 2, 2, &beam_options(2), &[]);
+        assert_eq!(token_ids(&beam), vec![0]);
+        assert!(ctc_prefix_beam_search(logits.view(), 0, 2, &beam_options(2), &[]).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn decode_options_parse_ids_with_greedy_fallback() {
+        assert_eq!(DecodeOptions::default().strategy, DecodeStrategy::Greedy);
+        let options = DecodeOptions::from_ids("beam", 0);
+        assert_eq!(options.strategy, DecodeStrategy::BeamSearch);
+        assert_eq!(options.beam_width, DEFAULT_BEAM_WIDTH);
+        assert_eq!(
+            DecodeOptions::from_ids("unknown", 3).strategy,
+            DecodeStrategy::Greedy
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use crate::metrics::TranscriptionMetrics;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Version of the structured result JSON; bumped whenever a field changes meaning.
+pub const RESULT_VERSION: u32 = 1;
+
+/// Machine-readable failure category reported to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A Java argument could not be decoded or is out of range.
+    InvalidArgument,
+    /// No `manifest.json` under the models root declares the model id, and it is not one
+    /// of the bundled GigaAM models either.
+    UnsupportedModel,
+    /// The model directory or one of its required files is missing.
+    ModelMissing,
+    /// The model manifest is malformed or a checksum does not match.
+    InvalidManifest,
+    /// ONNX Runtime failed to load the model or to run inference.
+    OrtFailure,
+    /// PCM samples could not be read or the sample rate is invalid.
+    InvalidAudio,
+    /// No model is loaded for a call that needs one.
+    EngineNotLoaded,
+    /// The streaming session id is unknown or was already finished.
+    UnknownSession,
+    /// Native code panicked; the engine is reloaded on the next call.
+    Panic,
+}
+
+impl ErrorCode {
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::InvalidArgument => "invalid_argument",
+            Self::UnsupportedModel => "unsupported_model",
+            Self::ModelMissing => "model_missing",
+            Self::InvalidManifest => "invalid_manifest",
+            Self::OrtFailure => "ort_failure",
+            Self::InvalidAudio => "invalid_audio",
+            Self::EngineNotLoaded => "engine_not_loaded",
+            Self::UnknownSession => "unknown_session",
+            Self::Panic => "panic",
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_id())
+    }
+}
+
+/// Error surfaced through the JNI bridge. Converts into its message, so helpers that
+/// still report plain strings can use `?` on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BridgeError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl BridgeError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Reports a failure of the engine. Errors the core tagged with a [`BridgeError`]
+    /// keep its code, a missing file is [`ErrorCode::ModelMissing`] and anything else is
+    /// `fallback`.
+    pub fn from_engine(error: &anyhow::Error, fallback: ErrorCode, context: &str) -> Self {
+        let missing_file = error.chain().any(|cause| {
+            cause
+                .downcast_ref::<io::Error>()
+                .is_some_and(|cause| cause.kind() == io::ErrorKind::NotFound)
+        });
+        let code = match error.downcast_ref::<BridgeError>() {
+            Some(tagged) => tagged.code,
+            None if missing_file => ErrorCode::ModelMissing,
+            None => fallback,
+        };
+        Self::new(code, format!("{context}: {error:#}"))
+    }
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+impl From<BridgeError> for String {
+    fn from(error: BridgeError) -> Self {
+        error.message
+    }
+}
+
+/// Runs `body` so that a panic inside it comes back as an [`ErrorCode::Panic`] error
+/// instead of unwinding across the JNI boundary, which would abort the host process.
+pub fn catch_panic<T, E: From<BridgeError>>(body: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic payload".to_string());
+            log::error!("GigaAM native panic: {message}");
+            Err(BridgeError::new(ErrorCode::Panic, format!("Native panic: {message}")).into())
+        }
+    }
+}
+
+/// Versioned object every JSON entry point of the bridge answers with. `error` is set
+/// exactly when `status` is `"error"`.
+#[derive(Serialize)]
+struct ResultEnvelope<'a, D> {
+    version: u32,
+    status: &'static str,
+    error: Option<&'a BridgeError>,
+    text: &'a str,
+    profile: Option<&'a TranscriptionMetrics>,
+    data: Option<D>,
+}
+
+impl<'a, D: Serialize> ResultEnvelope<'a, D> {
+    fn new(
+        result: Result<(&'a str, Option<&'a TranscriptionMetrics>, Option<D>), &'a BridgeError>,
+    ) -> Self {
+        let (status, error, (text, profile, data)) = match result {
+            Ok(body) => ("ok", None, body),
+            Err(error) => ("error", Some(error), ("", None, None)),
+        };
+        Self {
+            version: RESULT_VERSION,
+            status,
+            error,
+            text,
+            profile,
+            data,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("result envelopes contain only plain fields")
+    }
+}
+
+/// Builds the result of `nativeTranscribeResult` and of the calls that configure or load
+/// the engine. On success `profile` is the metrics also kept as the last profiling
+/// summary, or `None` for calls that do not transcribe.
+pub fn result_json(result: Result<(&str, Option<&TranscriptionMetrics>), &BridgeError>) -> String {
+    let result = result.map(|(text, profile)| (text, profile, None::<()>));
+    ResultEnvelope::new(result).to_json()
+}
+
+/// Builds the result of the calls that answer with their own object, which goes into
+/// `data`.
+pub fn data_json<D: Serialize>(result: Result<D, &BridgeError>) -> String {
+    let result = result.map(|data| ("", None, Some(data)));
+    ResultEnvelope::new(result).to_json()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Session {
+        session_id: i64,
+    }
+
+    #[test]
+    fn results_carry_version_status_and_error_code() {
+        let ok = result_json(Ok(("привет \"мир\"", None)));
+        assert_eq!(
+            ok,
+            "{\"version\":1,\"status\":\"ok\",\"error\":null,\"text\":\"привет \\\"мир\\\"\",\"profile\":null,\"data\":null}"
+        );
+        let metrics = TranscriptionMetrics {
+            total_ms: 12,
+            ..TranscriptionMetrics::default()
+        };
+        let profiled = result_json(Ok(("да", Some(&metrics))));
+        let profiled = serde_json::from_str::<serde_json::Value>(&profiled).unwrap();
+        assert_eq!(profiled["profile"]["total_ms"], 12);
+        assert_eq!(profiled["data"], serde_json::Value::Null);
+
+        let data = data_json(Ok(Session { session_id: 3 }));
+        assert_eq!(
+            data,
+            "{\"version\":1,\"status\":\"ok\",\"error\":null,\"text\":\"\",\"profile\":null,\"data\":{\"session_id\":3}}"
+        );
+
+        // Calls with and without a payload of their own fail in the same shape.
+        let error = BridgeError::new(ErrorCode::ModelMissing, "Required file not found: a.onnx");
+        let failed = "{\"version\":1,\"status\":\"error\",\"error\":{\"code\":\"model_missing\",\"message\":\"Required file not found: a.onnx\"},\"text\":\"\",\"profile\":null,\"data\":null}";
+        assert_eq!(result_json(Err(&error)), failed);
+        assert_eq!(data_json(Err::<Session, _>(&error)), failed);
+        assert_eq!(String::from(error), "Required file not found: a.onnx");
+    }
+
+    #[test]
+    fn engine_errors_keep_their_tagged_code() {
+        let tagged = anyhow::Error::new(BridgeError::new(ErrorCode::InvalidAudio, "NaN sample"))
+            .context("While decoding");
+        let error =
+            BridgeError::from_engine(&tagged, ErrorCode::OrtFailure, "Transcription failed");
+        assert_eq!(error.code, ErrorCode::InvalidAudio);
+        assert_eq!(
+            error.message,
+            "Transcription failed: While decoding: NaN sample"
+        );
+
+        let config = anyhow::anyhow!("unknown field `foo`").context(BridgeError::new(
+            ErrorCode::InvalidManifest,
+            "In GigaAM config file: v3_ctc.yaml",
+        ));
+        let error =
+            BridgeError::from_engine(&config, ErrorCode::OrtFailure, "Failed to load model");
+        assert_eq!(error.code, ErrorCode::InvalidManifest);
+
+        let missing = anyhow::Error::new(io::Error::from(io::ErrorKind::NotFound))
+            .context("Failed to read GigaAM vocab file: v3_vocab.txt");
+        let error =
+            BridgeError::from_engine(&missing, ErrorCode::OrtFailure, "Failed to load model");
+        assert_eq!(error.code, ErrorCode::ModelMissing);
+
+        let ort = anyhow::anyhow!("Failed to run inference");
+        let error = BridgeError::from_engine(&ort, ErrorCode::OrtFailure, "Transcription failed");
+        assert_eq!(error.code, ErrorCode::OrtFailure);
+    }
+
+    #[test]
+    fn panics_become_errors_and_results_pass_through() {
+        let caught = catch_panic(|| -> Result<(), BridgeError> { panic!("shape mismatch {}", 3) });
+        let error = caught.unwrap_err();
+        assert_eq!(error.code, ErrorCode::Panic);
+        assert_eq!(error.message, "Native panic: shape mismatch 3");
+
+        let as_string = catch_panic(|| -> Result<(), String> { panic!("boom") });
+        assert_eq!(as_string, Err("Native panic: boom".to_string()));
+
+        let passed = catch_panic(|| Err::<u8, String>("plain error".to_string()));
+        assert_eq!(passed, Err("plain error".to_string()));
+        assert_eq!(catch_panic(|| Ok::<_, BridgeError>(7)), Ok(7));
+    }
+}
@@ -0,0 +1,433 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One utterance of a test set: a WAV file and its reference transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// `id` from the manifest, or the audio path as written there.
+    pub id: String,
+    pub audio: PathBuf,
+    pub text: String,
+}
+
+/// Reads a JSONL test-set manifest with one `{"audio": ..., "text": ...}` object per
+/// line (NeMo's `audio_filepath` is accepted too). Relative audio paths are resolved
+/// against the manifest directory.
+pub fn read_test_manifest(path: &Path) -> Result<Vec<TestCase>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read test manifest {}", path.display()))?;
+    parse_test_manifest(&content, path.parent().unwrap_or(Path::new(".")))
+        .with_context(|| format!("In test manifest {}", path.display()))
+}
+
+fn parse_test_manifest(content: &str, base_dir: &Path) -> Result<Vec<TestCase>> {
+    #[derive(Deserialize)]
+    struct Line {
+        id: Option<String>,
+        #[serde(alias = "audio_filepath", alias = "wav")]
+        audio: String,
+        text: String,
+    }
+
+    let mut cases = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = serde_json::from_str::<Line>(line)
+            .with_context(|| format!("Invalid line {}", index + 1))?;
+        cases.push(TestCase {
+            id: line.id.unwrap_or_else(|| line.audio.clone()),
+            audio: base_dir.join(&line.audio),
+            text: line.text,
+        });
+    }
+    Ok(cases)
+}
+
+/// Lowercases, folds `ё` into `е` and turns everything but letters and digits into
+/// word breaks, so that only the words themselves are scored.
+pub fn normalize_text(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|ch| match ch {
+            'ё' => 'е',
+            ch if ch.is_alphanumeric() => ch,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EditOp {
+    #[serde(rename = "=")]
+    Equal,
+    #[serde(rename = "S")]
+    Substitution,
+    #[serde(rename = "I")]
+    Insertion,
+    #[serde(rename = "D")]
+    Deletion,
+}
+
+/// Edit counts of a hypothesis against a reference of `reference_len` units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ErrorCounts {
+    pub reference_len: usize,
+    pub substitutions: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl ErrorCounts {
+    pub fn errors(&self) -> usize {
+        self.substitutions + self.insertions + self.deletions
+    }
+
+    /// Errors per reference unit; a non-empty hypothesis of an empty reference scores 1.
+    pub fn rate(&self) -> f64 {
+        match (self.reference_len, self.errors()) {
+            (_, 0) => 0.0,
+            (0, _) => 1.0,
+            (len, errors) => round_rate(errors as f64 / len as f64),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.reference_len += other.reference_len;
+        self.substitutions += other.substitutions;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+    }
+
+    fn from_alignment<T>(alignment: &[(EditOp, Option<T>, Option<T>)]) -> Self {
+        let mut counts = Self::default();
+        for (op, reference, _) in alignment {
+            counts.reference_len += usize::from(reference.is_some());
+            match op {
+                EditOp::Equal => {}
+                EditOp::Substitution => counts.substitutions += 1,
+                EditOp::Insertion => counts.insertions += 1,
+                EditOp::Deletion => counts.deletions += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// Minimum edit-distance alignment of `hypothesis` to `reference`. Each step holds the
+/// reference and hypothesis unit it consumed; ties prefer matches and substitutions.
+pub fn align<T: PartialEq + Clone>(
+    reference: &[T],
+    hypothesis: &[T],
+) -> Vec<(EditOp, Option<T>, Option<T>)> {
+    let columns = hypothesis.len() + 1;
+    let mut cost = vec![0_usize; (reference.len() + 1) * columns];
+    for (j, cell) in cost.iter_mut().enumerate().take(columns) {
+        *cell = j;
+    }
+    for i in 1..=reference.len() {
+        cost[i * columns] = i;
+        for j in 1..columns {
+            let diagonal = cost[(i - 1) * columns + j - 1]
+                + usize::from(reference[i - 1] != hypothesis[j - 1]);
+            let deletion = cost[(i - 1) * columns + j] + 1;
+            let insertion = cost[i * columns + j - 1] + 1;
+            cost[i * columns + j] = diagonal.min(deletion).min(insertion);
+        }
+    }
+
+    let mut steps = Vec::with_capacity(reference.len().max(hypothesis.len()));
+    let (mut i, mut j) = (reference.len(), hypothesis.len());
+    while i > 0 || j > 0 {
+        let current = cost[i * columns + j];
+        if i > 0 && j > 0 {
+            let same = reference[i - 1] == hypothesis[j - 1];
+            if current == cost[(i - 1) * columns + j - 1] + usize::from(!same) {
+                let op = if same {
+                    EditOp::Equal
+                } else {
+                    EditOp::Substitution
+                };
+                steps.push((
+                    op,
+                    Some(reference[i - 1].clone()),
+                    Some(hypothesis[j - 1].clone()),
+                ));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && current == cost[(i - 1) * columns + j] + 1 {
+            steps.push((EditOp::Deletion, Some(reference[i - 1].clone()), None));
+            i -= 1;
+        } else {
+            steps.push((EditOp::Insertion, None, Some(hypothesis[j - 1].clone())));
+            j -= 1;
+        }
+    }
+    steps.reverse();
+    steps
+}
+
+/// One step of a word alignment; `ref` or `hyp` is left out for insertions and
+/// deletions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlignedWord {
+    pub op: EditOp,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(rename = "hyp", skip_serializing_if = "Option::is_none")]
+    pub hypothesis: Option<String>,
+}
+
+/// Scores of one utterance. Failed utterances carry `error` and are scored as if nothing
+/// was recognized, so the totals still cover the full set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UtteranceReport {
+    pub id: String,
+    pub reference: String,
+    pub hypothesis: String,
+    pub wer: f64,
+    pub cer: f64,
+    pub words: ErrorCounts,
+    pub chars: ErrorCounts,
+    pub alignment: Vec<AlignedWord>,
+    pub audio_ms: u64,
+    pub total_ms: u128,
+    pub real_time_factor: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl UtteranceReport {
+    /// Compares the normalized `hypothesis` with the normalized `reference`; both are
+    /// reported normalized.
+    pub fn score(id: &str, reference: &str, hypothesis: &str) -> Self {
+        let reference = normalize_text(reference);
+        let hypothesis = normalize_text(hypothesis);
+        let word_alignment = align(
+            &reference.split_whitespace().collect::<Vec<_>>(),
+            &hypothesis.split_whitespace().collect::<Vec<_>>(),
+        );
+        let chars = ErrorCounts::from_alignment(&align(
+            &reference.chars().collect::<Vec<_>>(),
+            &hypothesis.chars().collect::<Vec<_>>(),
+        ));
+        let words = ErrorCounts::from_alignment(&word_alignment);
+        Self {
+            id: id.to_string(),
+            wer: words.rate(),
+            cer: chars.rate(),
+            words,
+            chars,
+            alignment: word_alignment
+                .into_iter()
+                .map(|(op, reference, hypothesis)| AlignedWord {
+                    op,
+                    reference: reference.map(str::to_string),
+                    hypothesis: hypothesis.map(str::to_string),
+                })
+                .collect(),
+            reference,
+            hypothesis,
+            ..Self::default()
+        }
+    }
+
+    /// Scores an utterance that could not be transcribed: every reference word and
+    /// character counts as a deletion.
+    pub fn failed(id: &str, reference: &str, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::score(id, reference, "")
+        }
+    }
+
+    /// Records the inference time of `total_ms` over `audio_ms` of audio.
+    pub fn set_timing(&mut self, audio_ms: u64, total_ms: u128) {
+        self.audio_ms = audio_ms;
+        self.total_ms = total_ms;
+        self.real_time_factor = real_time_factor(audio_ms, total_ms);
+    }
+}
+
+/// Corpus-level scores of a test set. The JSON form is pretty-printed with one field
+/// per line, so reports of two runs can be compared with a plain text diff.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EvalReport {
+    pub model: String,
+    pub runtime_options: String,
+    pub decode_options: String,
+    pub utterances: usize,
+    pub failed: usize,
+    pub wer: f64,
+    pub cer: f64,
+    pub words: ErrorCounts,
+    pub chars: ErrorCounts,
+    pub audio_ms: u64,
+    pub total_ms: u128,
+    pub real_time_factor: f64,
+    pub results: Vec<UtteranceReport>,
+}
+
+impl EvalReport {
+    pub fn new(model: String, runtime_options: String, decode_options: String) -> Self {
+        Self {
+            model,
+            runtime_options,
+            decode_options,
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&mut self, utterance: UtteranceReport) {
+        self.utterances += 1;
+        self.words.add(&utterance.words);
+        self.chars.add(&utterance.chars);
+        self.wer = self.words.rate();
+        self.cer = self.chars.rate();
+        // Failed runs have no timing worth averaging.
+        if utterance.error.is_some() {
+            self.failed += 1;
+        } else {
+            self.audio_ms += utterance.audio_ms;
+            self.total_ms += utterance.total_ms;
+            self.real_time_factor = real_time_factor(self.audio_ms, self.total_ms);
+        }
+        self.results.push(utterance);
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} utterances ({} failed): WER {:.2}%, CER {:.2}%, RTF {:.3}",
+            self.utterances,
+            self.failed,
+            self.wer * 100.0,
+            self.cer * 100.0,
+            self.real_time_factor
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("eval reports contain only plain fields")
+    }
+}
+
+fn real_time_factor(audio_ms: u64, total_ms: u128) -> f64 {
+    if audio_ms == 0 {
+        0.0
+    } else {
+        round_rate(total_ms as f64 / audio_ms as f64)
+    }
+}
+
+/// Rates are rounded so that reports only differ where the results do.
+fn round_rate(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization_folds_case_yo_and_punctuation() {
+        assert_eq!(
+            normalize_text("  Ещё раз, Ёжик — кто-то «сказал»: 2 слова!"),
+            "еще раз ежик кто то сказал 2 слова"
+        );
+        assert_eq!(normalize_text("?!"), "");
+    }
+
+    #[test]
+    fn utterances_report_word_and_char_errors() {
+        let utterance = UtteranceReport::score("a", "Привет, большой мир!", "привет мир мир дом");
+        assert_eq!(
+            utterance.words,
+            ErrorCounts {
+                reference_len: 3,
+                substitutions: 1,
+                insertions: 1,
+                deletions: 0,
+            }
+        );
+        assert_eq!(utterance.wer, 0.6667);
+        let ops = utterance
+            .alignment
+            .iter()
+            .map(|word| word.op)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            [
+                EditOp::Equal,
+                EditOp::Substitution,
+                EditOp::Equal,
+                EditOp::Insertion
+            ]
+        );
+        assert_eq!(utterance.alignment[3].hypothesis.as_deref(), Some("дом"));
+        assert_eq!(utterance.alignment[3].reference, None);
+
+        let exact = UtteranceReport::score("b", "Ёлка", "елка");
+        assert_eq!((exact.wer, exact.cer), (0.0, 0.0));
+        let deleted = UtteranceReport::score("c", "да нет", "");
+        assert_eq!(deleted.words.deletions, 2);
+        assert_eq!(deleted.chars.deletions, 6);
+        assert_eq!(UtteranceReport::score("d", "", "шум").wer, 1.0);
+    }
+
+    #[test]
+    fn failed_utterances_count_as_deletions() {
+        let mut report = EvalReport::new(
+            "v3_e2e_ctc".to_string(),
+            "profile=balanced".to_string(),
+            "greedy".to_string(),
+        );
+        let mut first = UtteranceReport::score("a", "один два", "один три");
+        first.set_timing(2_000, 200);
+        let mut second = UtteranceReport::score("b", "три", "три");
+        second.set_timing(1_000, 100);
+        report.push(first);
+        report.push(second);
+        report.push(UtteranceReport::failed(
+            "c",
+            "четыре",
+            "no audio".to_string(),
+        ));
+
+        assert_eq!((report.utterances, report.failed), (3, 1));
+        assert_eq!(report.words.reference_len, 4);
+        assert_eq!(report.words.deletions, 1);
+        assert_eq!(report.chars.deletions, 6);
+        assert_eq!(report.wer, 0.5);
+        assert_eq!(report.real_time_factor, 0.1);
+        let json = serde_json::from_str::<serde_json::Value>(&report.to_json()).unwrap();
+        assert_eq!(json["results"][0]["alignment"][1]["op"], "S");
+        assert_eq!(json["results"][2]["error"], "no audio");
+        assert!(json["results"][0].get("error").is_none());
+    }
+
+    #[test]
+    fn manifest_lines_resolve_audio_against_the_manifest_directory() -> Result<()> {
+        let cases = parse_test_manifest(
+            "{\"audio\": \"clips/a.wav\", \"text\": \"да\"}\n\n\
+             {\"id\": \"b\", \"audio_filepath\": \"/data/b.wav\", \"text\": \"нет\", \"duration\": 1.5}\n",
+            Path::new("/sets/golden"),
+        )?;
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].id, "clips/a.wav");
+        assert_eq!(cases[0].audio, Path::new("/sets/golden/clips/a.wav"));
+        assert_eq!(cases[1].id, "b");
+        assert_eq!(cases[1].audio, Path::new("/data/b.wav"));
+        assert!(parse_test_manifest("{\"text\": \"да\"}", Path::new(".")).is_err());
+        Ok(())
+    }
+}
@@ -1,17 +1,39 @@
+use crate::alternatives::{
+    transcript_alternatives, word_alternatives, HypothesisWords, TranscriptAlternative,
+    WordAlternatives,
+};
+use crate::batch::{pad_features, plan_batches, BatchOptions};
+use crate::confidence::{aggregate_confidence, token_confidences, ScoreKind};
+use crate::config::ConfigFile;
+use crate::decoder::{token_ids, DecodeOptions, DecodeStrategy, DynPrefixScorer, Hypothesis};
+use crate::error::{BridgeError, ErrorCode};
+use crate::hotwords::{HotwordPhrase, HotwordTrie};
+use crate::lm::{LanguageModelScorer, NgramLanguageModel};
+use crate::longform::{plan_chunks, stitch_chunks, AudioChunk, LongFormOptions};
+use crate::manifest::{ModelArchitecture, ModelFile, ModelManifest, Preprocessing, VocabFormat};
+use crate::metrics::ModelLoadMetrics;
+use crate::model_cache::{has_external_data, map_model, CacheEntry, ModelCache};
+use crate::providers::{register_providers, ProviderSummary, CPU_PROVIDER};
+use crate::rnnt::RnntModel;
+use crate::signal::{PadMode, SignalCarry, SignalConditioner};
+use crate::spectrum::PowerSpectrum;
+use crate::streaming::{StreamingState, StreamingUpdate};
+use crate::timestamps::{encoder_frame_ms, token_timings, word_timings, TokenTiming, WordTiming};
+use crate::vad::{VadOptions, VoiceActivityDetector};
 use anyhow::{Context, Result};
-use ndarray::{s, Array1, Array3, ArrayView3, Ix3};
+use ndarray::{s, Array3, ArrayView1, ArrayView3, Ix3};
 use once_cell::sync::Lazy;
 use ort::execution_providers::cpu::CPUExecutionProvider;
 use ort::execution_providers::nnapi::NNAPIExecutionProvider;
 use ort::execution_providers::xnnpack::XNNPACKExecutionProvider;
-use ort::execution_providers::{ExecutionProvider, ExecutionProviderDispatch};
+use ort::execution_providers::ExecutionProvider;
 use ort::inputs;
-use ort::session::builder::GraphOptimizationLevel;
+use ort::session::builder::{GraphOptimizationLevel, SessionBuilder};
 use ort::session::Session;
 use ort::value::TensorRef;
 use regex::Regex;
-use rustfft::{num_complex::Complex32, Fft, FftPlanner};
-use std::cmp::Ordering;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::f32::consts::PI;
 use std::fs;
 use std::num::NonZeroUsize;
@@ -19,10 +41,6 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
-const MODEL_FILENAMES: [&str; 2] = ["v3_e2e_ctc.int8.onnx", "v3_e2e_ctc.onnx"];
-const VOCAB_FILENAME: &str = "v3_e2e_ctc_vocab.txt";
-const CONFIG_FILENAME: &str = "v3_e2e_ctc.yaml";
-
 const MEL_MIN_CLAMP: f32 = 1e-9;
 const MEL_MAX_CLAMP: f32 = 1e9;
 const XNNPACK_THREAD_COUNT: usize = 4;
@@ -30,7 +48,8 @@ const XNNPACK_THREAD_COUNT: usize = 4;
 static DECODE_SPACE_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\A\s|\s\B|(\s)\b").expect("valid decode spacing regex"));
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RuntimeSpeedProfile {
     Balanced,
     Fast,
@@ -67,9 +86,12 @@ impl RuntimeSpeedProfile {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RuntimeAcceleratorMode {
     Auto,
+    /// Like `Auto`, but loading fails when neither XNNPACK nor NNAPI registers.
+    Required,
     Cpu,
 }
 
@@ -77,6 +99,7 @@ impl RuntimeAcceleratorMode {
     pub fn from_id(value: &str) -> Self {
         match value {
             "cpu" => Self::Cpu,
+            "required" => Self::Required,
             _ => Self::Auto,
         }
     }
@@ -84,154 +107,372 @@ impl RuntimeAcceleratorMode {
     pub fn as_id(&self) -> &'static str {
         match self {
             Self::Auto => "auto",
+            Self::Required => "required",
             Self::Cpu => "cpu",
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An execution provider that can be listed in [`RuntimeOptions::providers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Xnnpack,
+    Nnapi,
+    Cpu,
+}
+
+impl ProviderKind {
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::Xnnpack => "xnnpack",
+            Self::Nnapi => "nnapi",
+            Self::Cpu => "cpu",
+        }
+    }
+}
+
+/// ONNX Runtime graph optimization level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptimizationLevel {
+    Disable,
+    Basic,
+    Extended,
+    All,
+}
+
+impl OptimizationLevel {
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Basic => "basic",
+            Self::Extended => "extended",
+            Self::All => "all",
+        }
+    }
+
+    fn ort_level(self) -> GraphOptimizationLevel {
+        match self {
+            Self::Disable => GraphOptimizationLevel::Disable,
+            Self::Basic => GraphOptimizationLevel::Level1,
+            Self::Extended => GraphOptimizationLevel::Level2,
+            Self::All => GraphOptimizationLevel::Level3,
+        }
+    }
+}
+
+/// Session settings. [`RuntimeOptions::preset`] derives them from a speed profile and
+/// accelerator mode; [`RuntimeOptions::from_json`] overrides single settings on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RuntimeOptions {
     pub speed_profile: RuntimeSpeedProfile,
     pub accelerator_mode: RuntimeAcceleratorMode,
+    pub intra_threads: usize,
+    pub inter_threads: usize,
+    pub parallel_execution: bool,
+    pub xnnpack_threads: usize,
+    /// Providers in priority order; always ends with CPU.
+    pub providers: Vec<ProviderKind>,
+    /// ONNX Runtime's CPU memory arena, which trades resident memory for fewer allocations.
+    pub memory_arena: bool,
+    /// Reuses the allocation plan of earlier runs with the same input shapes.
+    pub memory_pattern: bool,
+    pub optimization_level: OptimizationLevel,
 }
 
 impl Default for RuntimeOptions {
     fn default() -> Self {
-        Self {
-            speed_profile: RuntimeSpeedProfile::Balanced,
-            accelerator_mode: RuntimeAcceleratorMode::Auto,
-        }
+        Self::preset(RuntimeSpeedProfile::Balanced, RuntimeAcceleratorMode::Auto)
     }
 }
 
 impl RuntimeOptions {
     pub fn from_ids(speed_profile: &str, accelerator_mode: &str) -> Self {
+        Self::preset(
+            RuntimeSpeedProfile::from_id(speed_profile),
+            RuntimeAcceleratorMode::from_id(accelerator_mode),
+        )
+    }
+
+    /// Settings of a named profile. With accelerators ONNX Runtime itself gets a single
+    /// thread, since XNNPACK runs its own pool.
+    pub fn preset(
+        speed_profile: RuntimeSpeedProfile,
+        accelerator_mode: RuntimeAcceleratorMode,
+    ) -> Self {
+        let ((intra_threads, inter_threads, parallel_execution), providers) = match accelerator_mode
+        {
+            RuntimeAcceleratorMode::Auto | RuntimeAcceleratorMode::Required => (
+                (1, 1, false),
+                vec![
+                    ProviderKind::Xnnpack,
+                    ProviderKind::Nnapi,
+                    ProviderKind::Cpu,
+                ],
+            ),
+            RuntimeAcceleratorMode::Cpu => (speed_profile.cpu_threads(), vec![ProviderKind::Cpu]),
+        };
         Self {
-            speed_profile: RuntimeSpeedProfile::from_id(speed_profile),
-            accelerator_mode: RuntimeAcceleratorMode::from_id(accelerator_mode),
+            speed_profile,
+            accelerator_mode,
+            intra_threads,
+            inter_threads,
+            parallel_execution,
+            xnnpack_threads: XNNPACK_THREAD_COUNT,
+            providers,
+            memory_arena: false,
+            memory_pattern: true,
+            optimization_level: OptimizationLevel::All,
+        }
+    }
+
+    /// Parses options such as `{"speed_profile": "fast", "intra_threads": 2,
+    /// "providers": ["xnnpack", "cpu"]}`. `speed_profile` and `accelerator_mode` pick the
+    /// preset (`balanced` and `auto` when left out); every other key overrides it.
+    pub fn from_json(content: &str) -> Result<Self> {
+        #[derive(Default, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        struct Overrides {
+            speed_profile: Option<RuntimeSpeedProfile>,
+            accelerator_mode: Option<RuntimeAcceleratorMode>,
+            intra_threads: Option<usize>,
+            inter_threads: Option<usize>,
+            parallel_execution: Option<bool>,
+            xnnpack_threads: Option<usize>,
+            providers: Option<Vec<ProviderKind>>,
+            memory_arena: Option<bool>,
+            memory_pattern: Option<bool>,
+            optimization_level: Option<OptimizationLevel>,
+        }
+
+        let overrides =
+            serde_json::from_str::<Overrides>(content).context("Invalid runtime options")?;
+        for (key, value) in [
+            ("intra_threads", overrides.intra_threads),
+            ("inter_threads", overrides.inter_threads),
+            ("xnnpack_threads", overrides.xnnpack_threads),
+        ] {
+            if value == Some(0) {
+                anyhow::bail!("Invalid runtime options: {key}: must be positive");
+            }
+        }
+
+        let mut options = Self::preset(
+            overrides
+                .speed_profile
+                .unwrap_or(RuntimeSpeedProfile::Balanced),
+            overrides
+                .accelerator_mode
+                .unwrap_or(RuntimeAcceleratorMode::Auto),
+        );
+        options.intra_threads = overrides.intra_threads.unwrap_or(options.intra_threads);
+        options.inter_threads = overrides.inter_threads.unwrap_or(options.inter_threads);
+        options.parallel_execution = overrides
+            .parallel_execution
+            .unwrap_or(options.parallel_execution);
+        options.xnnpack_threads = overrides.xnnpack_threads.unwrap_or(options.xnnpack_threads);
+        if let Some(requested) = overrides.providers {
+            options.providers.clear();
+            for provider in requested {
+                if provider != ProviderKind::Cpu && !options.providers.contains(&provider) {
+                    options.providers.push(provider);
+                }
+            }
+            options.providers.push(ProviderKind::Cpu);
         }
+        options.memory_arena = overrides.memory_arena.unwrap_or(options.memory_arena);
+        options.memory_pattern = overrides.memory_pattern.unwrap_or(options.memory_pattern);
+        options.optimization_level = overrides
+            .optimization_level
+            .unwrap_or(options.optimization_level);
+        Ok(options)
     }
 
-    pub fn cache_fragment(self) -> String {
+    pub fn cache_fragment(&self) -> String {
         format!(
-            "profile={};accelerator={}",
+            "profile={};accelerator={};intra_threads={};inter_threads={};parallel={};xnnpack_threads={};providers={};arena={};mem_pattern={};opt={}",
             self.speed_profile.as_id(),
-            self.accelerator_mode.as_id()
+            self.accelerator_mode.as_id(),
+            self.intra_threads,
+            self.inter_threads,
+            self.parallel_execution,
+            self.xnnpack_threads,
+            self.providers
+                .iter()
+                .map(ProviderKind::as_id)
+                .collect::<Vec<_>>()
+                .join(","),
+            self.memory_arena,
+            self.memory_pattern,
+            self.optimization_level.as_id()
         )
     }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct NativeTranscriptionTimings {
+    pub vad_ms: u128,
     pub feature_extraction_ms: u128,
     pub ort_run_ms: u128,
     pub decode_ms: u128,
     pub total_ms: u128,
+    /// Frames produced by the frontend and by the encoder, summed over chunks.
+    pub feature_frames: usize,
+    pub encoder_frames: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct NativeTranscriptionReport {
     pub text: String,
+    /// Geometric mean of all token confidences; 0 for an empty transcript.
+    pub confidence: f32,
+    pub tokens: Vec<TokenTiming>,
+    pub words: Vec<WordTiming>,
+    /// N-best transcripts, best first; only filled when alternatives were requested.
+    pub alternatives: Vec<TranscriptAlternative>,
+    /// Competing spellings for every word of `text`; only filled with `alternatives`.
+    pub word_alternatives: Vec<WordAlternatives>,
     pub timings: NativeTranscriptionTimings,
-    pub provider_summary: String,
 }
 
 impl NativeTranscriptionReport {
-    pub fn to_json(&self) -> String {
-        let safe_provider = escape_json_string(&self.provider_summary);
-        format!(
-            "{{\"provider\":\"{safe_provider}\",\"feature_extraction_ms\":{},\"ort_run_ms\":{},\"decode_ms\":{},\"total_ms\":{}}}",
-            self.timings.feature_extraction_ms,
-            self.timings.ort_run_ms,
-            self.timings.decode_ms,
-            self.timings.total_ms
-        )
+    /// Moves every timestamp by `offset_ms`, for reports over a slice of a longer input.
+    pub fn shift_timestamps(&mut self, offset_ms: u64) {
+        for token in &mut self.tokens {
+            token.start_ms += offset_ms;
+            token.end_ms += offset_ms;
+        }
+        for word in &mut self.words {
+            word.start_ms += offset_ms;
+            word.end_ms += offset_ms;
+        }
+        for word in &mut self.word_alternatives {
+            word.start_ms += offset_ms;
+            word.end_ms += offset_ms;
+        }
+    }
+
+    /// Transcript with per-token and per-word start/end times in milliseconds.
+    pub fn result(&self) -> TranscriptResult<'_> {
+        TranscriptResult {
+            text: &self.text,
+            confidence: self.confidence,
+            words: &self.words,
+            tokens: &self.tokens,
+        }
+    }
+
+    /// N-best transcripts and per-word alternatives, for correction UIs.
+    pub fn alternatives_result(&self) -> AlternativesResult<'_> {
+        AlternativesResult {
+            text: &self.text,
+            confidence: self.confidence,
+            alternatives: &self.alternatives,
+            words: &self.word_alternatives,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+/// `data` of `transcribeDetailed`, and one element of the batch result.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TranscriptResult<'a> {
+    pub text: &'a str,
+    pub confidence: f32,
+    pub words: &'a [WordTiming],
+    pub tokens: &'a [TokenTiming],
+}
+
+/// `data` of `transcribeAlternatives`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AlternativesResult<'a> {
+    pub text: &'a str,
+    pub confidence: f32,
+    pub alternatives: &'a [TranscriptAlternative],
+    pub words: &'a [WordAlternatives],
+}
+
 struct SessionRuntimePlan {
-    providers: Vec<ExecutionProviderDispatch>,
-    provider_summary: String,
-    intra_threads: usize,
-    inter_threads: usize,
-    parallel_execution: bool,
+    /// Requested providers in priority order.
+    providers: Vec<Arc<dyn ExecutionProvider>>,
+    /// Mode and threading; registrations are filled in per session.
+    summary: ProviderSummary,
+    /// Whether a provider compiles graph partitions (NNAPI, XNNPACK); such sessions
+    /// cannot save their optimized model.
+    compiles_nodes: bool,
+    require_accelerator: bool,
+    memory_pattern: bool,
+    optimization_level: OptimizationLevel,
 }
 
 impl SessionRuntimePlan {
-    fn from_runtime_options(options: RuntimeOptions) -> Self {
-        match options.accelerator_mode {
-            RuntimeAcceleratorMode::Auto => {
-                let xnn_available = XNNPACKExecutionProvider::default()
-                    .is_available()
-                    .unwrap_or(false);
-                let nnapi_available = NNAPIExecutionProvider::default().is_available().unwrap_or(false);
-                let cpu_available = CPUExecutionProvider::default().is_available().unwrap_or(true);
-
-                let providers = vec![
-                    XNNPACKExecutionProvider::default()
-                        .with_intra_op_num_threads(
-                            NonZeroUsize::new(XNNPACK_THREAD_COUNT).expect("non-zero constant"),
-                        )
-                        .build(),
-                    NNAPIExecutionProvider::default()
-                        .with_disable_cpu(true)
-                        .with_fp16(options.speed_profile.nnapi_use_fp16())
-                        .build(),
-                    CPUExecutionProvider::default().build(),
-                ];
-
-                let provider_summary = format!(
-                    "mode=auto, profile={}, requested=[XNNPACK(available={},threads={}), NNAPI(available={},disable_cpu=true,fp16={}), CPU(available={})]",
-                    options.speed_profile.as_id(),
-                    xnn_available,
-                    XNNPACK_THREAD_COUNT,
-                    nnapi_available,
-                    options.speed_profile.nnapi_use_fp16(),
-                    cpu_available
-                );
-
-                Self {
-                    providers,
-                    provider_summary,
-                    intra_threads: 1,
-                    inter_threads: 1,
-                    parallel_execution: false,
+    fn from_runtime_options(options: &RuntimeOptions) -> Self {
+        let providers = options
+            .providers
+            .iter()
+            .map(|provider| -> Arc<dyn ExecutionProvider> {
+                match provider {
+                    ProviderKind::Xnnpack => Arc::new(
+                        XNNPACKExecutionProvider::default().with_intra_op_num_threads(
+                            NonZeroUsize::new(options.xnnpack_threads).unwrap_or(NonZeroUsize::MIN),
+                        ),
+                    ),
+                    ProviderKind::Nnapi => Arc::new(
+                        NNAPIExecutionProvider::default()
+                            .with_disable_cpu(true)
+                            .with_fp16(options.speed_profile.nnapi_use_fp16()),
+                    ),
+                    ProviderKind::Cpu => Arc::new(
+                        CPUExecutionProvider::default().with_arena_allocator(options.memory_arena),
+                    ),
                 }
-            }
-            RuntimeAcceleratorMode::Cpu => {
-                let (intra_threads, inter_threads, parallel_execution) =
-                    options.speed_profile.cpu_threads();
-                let cpu_available = CPUExecutionProvider::default().is_available().unwrap_or(true);
-                let provider_summary = format!(
-                    "mode=cpu, profile={}, requested=[CPU(available={})]",
-                    options.speed_profile.as_id(),
-                    cpu_available
-                );
+            })
+            .collect();
 
-                Self {
-                    providers: vec![CPUExecutionProvider::default().build()],
-                    provider_summary,
-                    intra_threads,
-                    inter_threads,
-                    parallel_execution,
-                }
-            }
+        Self {
+            providers,
+            summary: ProviderSummary {
+                mode: options.accelerator_mode.as_id(),
+                profile: options.speed_profile.as_id(),
+                intra_threads: options.intra_threads,
+                inter_threads: options.inter_threads,
+                parallel_execution: options.parallel_execution,
+                xnnpack_threads: options.xnnpack_threads,
+                memory_arena: options.memory_arena,
+                memory_pattern: options.memory_pattern,
+                optimization_level: options.optimization_level.as_id(),
+                ..ProviderSummary::default()
+            },
+            compiles_nodes: options
+                .providers
+                .iter()
+                .any(|provider| *provider != ProviderKind::Cpu),
+            require_accelerator: options.accelerator_mode == RuntimeAcceleratorMode::Required,
+            memory_pattern: options.memory_pattern,
+            optimization_level: options.optimization_level,
         }
     }
 }
 
-#[derive(Debug, Clone)]
-struct GigaamConfig {
+#[derive(Debug, Clone, PartialEq)]
+pub struct GigaamConfig {
     sample_rate: usize,
     n_mels: usize,
     win_length: usize,
     hop_length: usize,
     n_fft: usize,
     center: bool,
+    pad_mode: String,
     mel_scale: String,
+    mel_norm: Option<String>,
+    f_min: f64,
+    /// Upper edge of the mel filters; `None` is the Nyquist frequency.
+    f_max: Option<f64>,
+    preemph: f32,
+    dither: f32,
     subsampling_factor: usize,
-    model_name: String,
+    /// See [`Preprocessing::quantize_bf16`].
+    quantize_bf16: bool,
 }
 
 impl Default for GigaamConfig {
@@ -243,99 +484,238 @@ impl Default for GigaamConfig {
             hop_length: 160,
             n_fft: 320,
             center: false,
+            pad_mode: "reflect".to_string(),
             mel_scale: "htk".to_string(),
+            mel_norm: None,
+            f_min: 0.0,
+            f_max: None,
+            preemph: 0.0,
+            dither: 0.0,
             subsampling_factor: 4,
-            model_name: "v3_e2e_ctc".to_string(),
+            quantize_bf16: false,
         }
     }
 }
 
 impl GigaamConfig {
-    fn from_yaml(content: &str) -> Self {
-        let mut config = Self::default();
-
-        for raw_line in content.lines() {
-            let line = raw_line.split('#').next().unwrap_or("").trim();
-            if line.is_empty() {
-                continue;
-            }
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
 
-            if let Some(value) = line.strip_prefix("sample_rate:") {
-                if let Ok(parsed) = value.trim().parse::<usize>() {
-                    config.sample_rate = parsed;
-                }
-                continue;
-            }
+    /// Builds the frontend config from a model YAML config; keys it leaves out keep
+    /// the GigaAM v3 defaults.
+    fn from_yaml(content: &str) -> Result<Self> {
+        let file = ConfigFile::parse(content)?;
+        let preprocessor = file.preprocessor();
+        let defaults = Self::default();
+        if let Some(dither) = preprocessor.dither.filter(|dither| *dither != 0.0) {
+            log::warn!(
+                "GigaAM config dither {dither} ignored: NeMo dithers only while training, \
+                 set the manifest's preprocessing.dither to dither at inference"
+            );
+        }
+        Ok(Self {
+            sample_rate: file.sample_rate().unwrap_or(defaults.sample_rate),
+            n_mels: preprocessor.features,
+            win_length: file.win_length().unwrap_or(defaults.win_length),
+            hop_length: file.hop_length().unwrap_or(defaults.hop_length),
+            n_fft: preprocessor.n_fft.unwrap_or(defaults.n_fft),
+            center: preprocessor.center.unwrap_or(defaults.center),
+            pad_mode: preprocessor.pad_mode.clone().unwrap_or(defaults.pad_mode),
+            mel_scale: preprocessor.mel_scale.clone().unwrap_or(defaults.mel_scale),
+            mel_norm: preprocessor.mel_norm.clone(),
+            f_min: preprocessor.f_min.unwrap_or(defaults.f_min),
+            f_max: preprocessor.f_max,
+            preemph: preprocessor
+                .preemph
+                .map_or(defaults.preemph, |value| value as f32),
+            // Training-only, see the warning above; a manifest can still enable it.
+            dither: defaults.dither,
+            subsampling_factor: file
+                .encoder
+                .subsampling_factor
+                .unwrap_or(defaults.subsampling_factor),
+            quantize_bf16: file
+                .model_name
+                .as_deref()
+                .is_some_and(|name| name.contains("v3")),
+        })
+    }
 
-            if let Some(value) = line.strip_prefix("features:") {
-                if let Ok(parsed) = value.trim().parse::<usize>() {
-                    config.n_mels = parsed;
-                }
-                continue;
-            }
+    fn apply_manifest(&mut self, manifest: &ModelManifest) {
+        let Preprocessing {
+            n_mels,
+            win_length,
+            hop_length,
+            n_fft,
+            center,
+            pad_mode,
+            mel_scale,
+            mel_norm,
+            f_min,
+            f_max,
+            preemph,
+            dither,
+            subsampling_factor,
+            quantize_bf16,
+        } = &manifest.preprocessing;
+        self.sample_rate = manifest.sample_rate.unwrap_or(self.sample_rate);
+        self.n_mels = n_mels.unwrap_or(self.n_mels);
+        self.win_length = win_length.unwrap_or(self.win_length);
+        self.hop_length = hop_length.unwrap_or(self.hop_length);
+        self.n_fft = n_fft.unwrap_or(self.n_fft);
+        self.center = center.unwrap_or(self.center);
+        if let Some(pad_mode) = pad_mode {
+            self.pad_mode = pad_mode.clone();
+        }
+        if let Some(mel_scale) = mel_scale {
+            self.mel_scale = mel_scale.clone();
+        }
+        if mel_norm.is_some() {
+            self.mel_norm = mel_norm.clone();
+        }
+        self.f_min = f_min.unwrap_or(self.f_min);
+        if f_max.is_some() {
+            self.f_max = *f_max;
+        }
+        self.preemph = preemph.map_or(self.preemph, |value| value as f32);
+        self.dither = dither.map_or(self.dither, |value| value as f32);
+        self.subsampling_factor = subsampling_factor.unwrap_or(self.subsampling_factor);
+        // Left unset, GigaAM v3 checkpoints and models with their uncentered 320-point
+        // STFT are taken to store the window and filters in bfloat16.
+        self.quantize_bf16 =
+            quantize_bf16.unwrap_or(self.quantize_bf16 || (!self.center && self.n_fft == 320));
+    }
+}
 
-            if let Some(value) = line.strip_prefix("win_length:") {
-                if let Ok(parsed) = value.trim().parse::<usize>() {
-                    config.win_length = parsed;
-                }
-                continue;
-            }
+pub struct GigaamFrontend {
+    n_mels: usize,
+    win_length: usize,
+    hop_length: usize,
+    n_fft: usize,
+    /// Start of the window inside an `n_fft` frame; `torch.stft` centers it.
+    window_offset: usize,
+    conditioner: SignalConditioner,
+    hann_window: Vec<f32>,
+    mel_bands: Vec<MelBand>,
+    spectrum: PowerSpectrum,
+}
 
-            if let Some(value) = line.strip_prefix("hop_length:") {
-                if let Ok(parsed) = value.trim().parse::<usize>() {
-                    config.hop_length = parsed;
-                }
-                continue;
-            }
+/// Non-zero span of one triangular mel filter.
+#[derive(Debug, Clone, PartialEq)]
+struct MelBand {
+    start_bin: usize,
+    weights: Vec<f32>,
+}
 
-            if let Some(value) = line.strip_prefix("n_fft:") {
-                if let Ok(parsed) = value.trim().parse::<usize>() {
-                    config.n_fft = parsed;
-                }
-                continue;
-            }
+impl MelBand {
+    fn energy(&self, power_spectrum: &[f32]) -> f32 {
+        power_spectrum[self.start_bin..]
+            .iter()
+            .zip(&self.weights)
+            .map(|(power, weight)| power * weight)
+            .sum()
+    }
+}
 
-            if let Some(value) = line.strip_prefix("center:") {
-                if let Ok(parsed) = value.trim().parse::<bool>() {
-                    config.center = parsed;
-                }
-                continue;
-            }
+/// Frequency scale of the mel filters, as torchaudio's `mel_scale`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MelScale {
+    Htk,
+    /// Linear below 1 kHz and logarithmic above, as in librosa and the Auditory Toolbox.
+    Slaney,
+}
 
-            if let Some(value) = line.strip_prefix("mel_scale:") {
-                config.mel_scale = value.trim().to_string();
-                continue;
-            }
+impl MelScale {
+    fn from_id(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("htk") {
+            Some(Self::Htk)
+        } else if value.eq_ignore_ascii_case("slaney") {
+            Some(Self::Slaney)
+        } else {
+            None
+        }
+    }
 
-            if let Some(value) = line.strip_prefix("subsampling_factor:") {
-                if let Ok(parsed) = value.trim().parse::<usize>() {
-                    config.subsampling_factor = parsed;
-                }
-                continue;
-            }
+    fn hz_to_mel(self, hz: f32) -> f32 {
+        match self {
+            Self::Htk => 2595.0 * (1.0 + hz / 700.0).log10(),
+            Self::Slaney if hz < SLANEY_MIN_LOG_HZ => hz / SLANEY_HZ_PER_MEL,
+            Self::Slaney => SLANEY_MIN_LOG_MEL + (hz / SLANEY_MIN_LOG_HZ).ln() / slaney_log_step(),
+        }
+    }
 
-            if let Some(value) = line.strip_prefix("model_name:") {
-                config.model_name = value.trim().to_string();
+    fn mel_to_hz(self, mel: f32) -> f32 {
+        match self {
+            Self::Htk => 700.0 * (10_f32.powf(mel / 2595.0) - 1.0),
+            Self::Slaney if mel < SLANEY_MIN_LOG_MEL => mel * SLANEY_HZ_PER_MEL,
+            Self::Slaney => {
+                SLANEY_MIN_LOG_HZ * (slaney_log_step() * (mel - SLANEY_MIN_LOG_MEL)).exp()
             }
         }
-
-        config
     }
 }
 
-struct GigaamFrontend {
-    n_mels: usize,
-    win_length: usize,
-    hop_length: usize,
+const SLANEY_HZ_PER_MEL: f32 = 200.0 / 3.0;
+const SLANEY_MIN_LOG_HZ: f32 = 1_000.0;
+const SLANEY_MIN_LOG_MEL: f32 = SLANEY_MIN_LOG_HZ / SLANEY_HZ_PER_MEL;
+
+fn slaney_log_step() -> f32 {
+    6.4_f32.ln() / 27.0
+}
+
+/// Mel filterbank settings of a [`GigaamConfig`], validated.
+#[derive(Debug, Clone, PartialEq)]
+struct MelSpec {
+    sample_rate: usize,
     n_fft: usize,
-    center: bool,
-    hann_window: Vec<f32>,
-    mel_filterbank: Vec<f32>, // [n_freq_bins, n_mels]
-    fft: Arc<dyn Fft<f32>>,
+    n_mels: usize,
+    f_min: f64,
+    f_max: f64,
+    scale: MelScale,
+    /// Scales each filter to unit area (`mel_norm: slaney`).
+    slaney_norm: bool,
 }
 
-impl GigaamFrontend {
+impl MelSpec {
     fn from_config(config: &GigaamConfig) -> Result<Self> {
+        let scale = MelScale::from_id(&config.mel_scale).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unsupported GigaAM mel_scale '{}'; expected 'htk' or 'slaney'",
+                config.mel_scale
+            )
+        })?;
+        let slaney_norm = match config.mel_norm.as_deref() {
+            None => false,
+            Some(norm) if norm.eq_ignore_ascii_case("slaney") => true,
+            Some(norm) => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported GigaAM mel_norm '{norm}'; expected null or 'slaney'"
+                ))
+            }
+        };
+        let nyquist = config.sample_rate as f64 / 2.0;
+        let f_max = config.f_max.unwrap_or(nyquist);
+        if !(0.0 <= config.f_min && config.f_min < f_max && f_max <= nyquist) {
+            return Err(anyhow::anyhow!(
+                "Invalid GigaAM config: need 0 <= f_min ({}) < f_max ({f_max}) <= {nyquist} Hz",
+                config.f_min
+            ));
+        }
+        Ok(Self {
+            sample_rate: config.sample_rate,
+            n_fft: config.n_fft,
+            n_mels: config.n_mels,
+            f_min: config.f_min,
+            f_max,
+            scale,
+            slaney_norm,
+        })
+    }
+}
+
+impl GigaamFrontend {
+    pub fn from_config(config: &GigaamConfig) -> Result<Self> {
         if config.hop_length == 0 {
             return Err(anyhow::anyhow!("Invalid GigaAM config: hop_length must be > 0"));
         }
@@ -352,175 +732,260 @@ impl GigaamFrontend {
                 config.win_length
             ));
         }
-        if !config.mel_scale.eq_ignore_ascii_case("htk") {
-            return Err(anyhow::anyhow!(
-                "Unsupported GigaAM mel_scale '{}'; expected 'htk'",
-                config.mel_scale
-            ));
-        }
-        if config.center {
+        let pad_mode = PadMode::from_id(&config.pad_mode).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unsupported GigaAM pad_mode '{}'; expected 'reflect' or 'constant'",
+                config.pad_mode
+            )
+        })?;
+        if !(0.0..1.0).contains(&config.preemph) || !(0.0..f32::INFINITY).contains(&config.dither) {
             return Err(anyhow::anyhow!(
-                "Unsupported GigaAM config: center=true is not supported for this model"
+                "Invalid GigaAM config: need 0 <= preemph ({}) < 1 and dither ({}) >= 0",
+                config.preemph,
+                config.dither
             ));
         }
+        let mel_spec = MelSpec::from_config(config)?;
 
-        let quantize_bf16 = config.model_name.contains("v3") || (!config.center && config.n_fft == 320);
-        let hann_window = build_hann_window(config.win_length, quantize_bf16);
-        let mel_filterbank = build_mel_filterbank(
-            config.sample_rate,
-            config.n_fft,
-            config.n_mels,
-            quantize_bf16,
-        )?;
-
-        let mut planner = FftPlanner::<f32>::new();
-        let fft = planner.plan_fft_forward(config.n_fft);
+        let hann_window = build_hann_window(config.win_length, config.quantize_bf16);
+        let mel_bands = build_mel_filterbank(&mel_spec, config.quantize_bf16)?;
 
         Ok(Self {
             n_mels: config.n_mels,
             win_length: config.win_length,
             hop_length: config.hop_length,
             n_fft: config.n_fft,
-            center: config.center,
+            window_offset: (config.n_fft - config.win_length) / 2,
+            conditioner: SignalConditioner {
+                dither: config.dither,
+                preemph: config.preemph,
+                center_pad: if config.center { config.n_fft / 2 } else { 0 },
+                pad_mode,
+            },
             hann_window,
-            mel_filterbank,
-            fft,
+            mel_bands,
+            spectrum: PowerSpectrum::new(config.n_fft),
         })
     }
 
-    fn extract_features(&self, samples: &[f32]) -> Result<(Array3<f32>, i64)> {
-        if samples.is_empty() {
-            return Ok((Array3::zeros((1, self.n_mels, 0)), 0));
-        }
+    pub fn extract_features(&mut self, samples: &[f32]) -> Result<(Array3<f32>, i64)> {
+        let signal = self.conditioner.apply(samples);
+        self.extract_conditioned_features(&signal)
+    }
 
-        if self.center {
-            return Err(anyhow::anyhow!(
-                "Unsupported GigaAM preprocessor configuration: center=true"
-            ));
+    /// Features of audio that already went through [`Self::condition_stream`]; frames
+    /// start at its first sample.
+    pub fn extract_conditioned_features(&mut self, signal: &[f32]) -> Result<(Array3<f32>, i64)> {
+        let frame_count = self.frame_count(signal.len());
+        let mut features = vec![0.0_f32; self.n_mels * frame_count];
+
+        for (frame_idx, frame) in self.frames(signal).enumerate() {
+            let power_spectrum = self.spectrum.compute(frame, &self.hann_window);
+            for (mel_idx, band) in self.mel_bands.iter().enumerate() {
+                let clamped = band
+                    .energy(power_spectrum)
+                    .clamp(MEL_MIN_CLAMP, MEL_MAX_CLAMP);
+                features[mel_idx * frame_count + frame_idx] = clamped.ln();
+            }
         }
 
-        if samples.len() < self.win_length {
-            return Ok((Array3::zeros((1, self.n_mels, 0)), 0));
+        let features = Array3::from_shape_vec((1, self.n_mels, frame_count), features)?;
+        Ok((features, frame_count as i64))
+    }
+
+    /// Conditions the next chunk of a stream the way [`Self::extract_features`]
+    /// conditions a whole recording; `flush` ends the stream.
+    pub fn condition_stream(
+        &self,
+        carry: &mut SignalCarry,
+        samples: &[f32],
+        flush: bool,
+    ) -> Vec<f32> {
+        let mut signal = self.conditioner.push(carry, samples);
+        if flush {
+            signal.extend(self.conditioner.finish(carry));
         }
+        signal
+    }
 
-        let frame_count = ((samples.len() - self.win_length) / self.hop_length) + 1;
-        let n_freq_bins = (self.n_fft / 2) + 1;
+    pub fn hop_length(&self) -> usize {
+        self.hop_length
+    }
 
-        let mut features = vec![0.0_f32; self.n_mels * frame_count];
-        let mut fft_buffer = vec![Complex32::new(0.0, 0.0); self.n_fft];
-        let mut power_spectrum = vec![0.0_f32; n_freq_bins];
+    /// Feature value of digital silence. Batches are padded with it, as GigaAM's own
+    /// batching pads the waveforms with zeros.
+    pub fn silence_level(&self) -> f32 {
+        MEL_MIN_CLAMP.ln()
+    }
 
-        for frame_idx in 0..frame_count {
-            let start = frame_idx * self.hop_length;
+    /// Frames [`Self::extract_features`] returns for `sample_count` samples.
+    pub fn feature_frame_count(&self, sample_count: usize) -> usize {
+        self.frame_count(self.conditioner.conditioned_len(sample_count))
+    }
 
-            for i in 0..self.n_fft {
-                let sample = if i < self.win_length {
-                    samples[start + i] * self.hann_window[i]
-                } else {
-                    0.0
-                };
-                fft_buffer[i] = Complex32::new(sample, 0.0);
-            }
+    /// Samples per analysis frame: `n_fft`, with the window centered inside.
+    pub fn frame_length(&self) -> usize {
+        self.n_fft
+    }
 
-            self.fft.process(&mut fft_buffer);
+    /// Scale that turns the summed one-sided power spectrum of a frame back into the
+    /// mean squared amplitude of the windowed samples.
+    pub fn power_normalization(&self) -> f32 {
+        let window_energy = self.hann_window.iter().map(|w| w * w).sum::<f32>();
+        (self.n_fft as f32 * window_energy / 2.0).max(f32::MIN_POSITIVE)
+    }
 
-            for (bin_idx, power) in power_spectrum.iter_mut().enumerate() {
-                let complex = fft_buffer[bin_idx];
-                *power = complex.re.mul_add(complex.re, complex.im * complex.im);
-            }
+    fn frame_count(&self, sample_count: usize) -> usize {
+        if sample_count < self.n_fft {
+            0
+        } else {
+            ((sample_count - self.n_fft) / self.hop_length) + 1
+        }
+    }
 
-            for mel_idx in 0..self.n_mels {
-                let mut mel_energy = 0.0_f32;
-                for (bin_idx, &power) in power_spectrum.iter().enumerate() {
-                    mel_energy += power * self.mel_filterbank[bin_idx * self.n_mels + mel_idx];
-                }
-                let clamped = mel_energy.clamp(MEL_MIN_CLAMP, MEL_MAX_CLAMP);
-                features[mel_idx * frame_count + frame_idx] = clamped.ln();
-            }
+    /// The windowed span of every analysis frame of `signal`.
+    fn frames<'a>(&self, signal: &'a [f32]) -> impl Iterator<Item = &'a [f32]> {
+        let window = self.window_offset..self.window_offset + self.win_length;
+        signal
+            .windows(self.n_fft)
+            .step_by(self.hop_length)
+            .map(move |frame| &frame[window.clone()])
+    }
+
+    /// Calls `visit` with the power spectrum (`n_fft / 2 + 1` bins) of every analysis
+    /// frame, in order. Returns the number of frames.
+    pub fn visit_power_spectra(
+        &mut self,
+        samples: &[f32],
+        mut visit: impl FnMut(usize, &[f32]),
+    ) -> Result<usize> {
+        if samples.is_empty() {
+            return Ok(0);
         }
 
-        let features = Array3::from_shape_vec((1, self.n_mels, frame_count), features)?;
-        Ok((features, frame_count as i64))
+        let signal = self.conditioner.apply(samples);
+        for (frame_idx, frame) in self.frames(&signal).enumerate() {
+            visit(frame_idx, self.spectrum.compute(frame, &self.hann_window));
+        }
+
+        Ok(self.frame_count(signal.len()))
     }
 }
 
 struct GigaamModel {
     session: Session,
     frontend: GigaamFrontend,
-    vocab: Vec<String>,
-    blank_idx: usize,
-    subsampling_factor: usize,
+    decoder: CtcDecoder,
     features_input_name: String,
     feature_lengths_input_name: String,
     logits_output_name: String,
-    provider_summary: String,
+    load_metrics: ModelLoadMetrics,
+    /// The next run is the profiled first one.
+    node_profile_pending: bool,
 }
 
-impl GigaamModel {
-    fn new(model_dir: &Path, runtime_options: RuntimeOptions) -> Result<Self> {
-        let model_path = MODEL_FILENAMES
-            .iter()
-            .map(|filename| model_dir.join(filename))
-            .find(|path| path.exists())
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Missing GigaAM model file in {}. Expected one of: {}",
-                    model_dir.display(),
-                    MODEL_FILENAMES.join(", ")
-                )
-            })?;
-        let vocab_path = model_dir.join(VOCAB_FILENAME);
-        let config_path = model_dir.join(CONFIG_FILENAME);
+/// Everything that turns CTC logits into a transcript, apart from the session.
+struct CtcDecoder {
+    vocab: Vec<String>,
+    blank_idx: usize,
+    language_model: Option<NgramLanguageModel>,
+    hotwords: Option<HotwordTrie>,
+    subsampling_factor: usize,
+    frame_ms: f64,
+    score_kind: ScoreKind,
+}
 
-        if !vocab_path.exists() {
-            return Err(anyhow::anyhow!(
-                "Missing GigaAM vocab file: {}",
-                vocab_path.display()
-            ));
-        }
-        if !config_path.exists() {
-            return Err(anyhow::anyhow!(
-                "Missing GigaAM config file: {}",
-                config_path.display()
-            ));
-        }
+/// Vocabulary and frontend, loaded the same way for every model architecture.
+pub struct ModelAssets {
+    pub vocab: Vec<String>,
+    pub blank_idx: usize,
+    pub frontend: GigaamFrontend,
+    pub subsampling_factor: usize,
+    /// Duration of one encoder output frame.
+    pub frame_ms: f64,
+}
+
+impl ModelAssets {
+    pub fn load(model_dir: &Path, manifest: &ModelManifest) -> Result<Self> {
+        manifest.validate_files(model_dir)?;
+        let vocab_path = manifest.vocab_path(model_dir);
 
         let vocab_content = fs::read_to_string(&vocab_path).with_context(|| {
             format!("Failed to read GigaAM vocab file: {}", vocab_path.display())
         })?;
-        let (vocab, blank_idx) = parse_vocab_content(&vocab_content)?;
-
-        let config_content = fs::read_to_string(&config_path).with_context(|| {
-            format!("Failed to read GigaAM config file: {}", config_path.display())
+        let (vocab, blank_idx) = match manifest.vocab_format {
+            VocabFormat::TokenIdLines => parse_vocab_content(&vocab_content),
+        }
+        .with_context(|| {
+            invalid_manifest(format!("In GigaAM vocab file: {}", vocab_path.display()))
         })?;
-        let config = GigaamConfig::from_yaml(&config_content);
+
+        let mut config = match manifest.config_path(model_dir) {
+            Some(config_path) => {
+                let config_content = fs::read_to_string(&config_path).with_context(|| {
+                    format!("Failed to read GigaAM config file: {}", config_path.display())
+                })?;
+                GigaamConfig::from_yaml(&config_content).with_context(|| {
+                    invalid_manifest(format!("In GigaAM config file: {}", config_path.display()))
+                })?
+            }
+            None => GigaamConfig::default(),
+        };
+        config.apply_manifest(manifest);
+        log::info!(
+            "GigaAM model: id={}, architecture={}",
+            manifest.id,
+            manifest.architecture.as_id()
+        );
         if config.sample_rate != 16_000 {
-            return Err(anyhow::anyhow!(
+            return Err(invalid_manifest(format!(
                 "Unsupported GigaAM sample rate {} Hz; Handy currently provides 16000 Hz PCM input",
                 config.sample_rate
-            ));
+            ))
+            .into());
         }
-        let frontend = GigaamFrontend::from_config(&config)?;
-
-        let runtime_plan = SessionRuntimePlan::from_runtime_options(runtime_options);
-        let session = Session::builder()?
-            .with_optimization_level(GraphOptimizationLevel::Level3)?
-            .with_intra_threads(runtime_plan.intra_threads)?
-            .with_inter_threads(runtime_plan.inter_threads)?
-            .with_execution_providers(runtime_plan.providers)?
-            .with_parallel_execution(runtime_plan.parallel_execution)?
-            .commit_from_file(&model_path)
-            .with_context(|| {
-                format!("Failed to initialize ONNX Runtime session: {}", model_path.display())
-            })?;
+        let frontend = GigaamFrontend::from_config(&config)
+            .context(invalid_manifest("Unsupported GigaAM preprocessing"))?;
+        let subsampling_factor = config.subsampling_factor.max(1);
+        Ok(Self {
+            vocab,
+            blank_idx,
+            frontend,
+            subsampling_factor,
+            frame_ms: encoder_frame_ms(config.hop_length, subsampling_factor, config.sample_rate),
+        })
+    }
+}
 
-        log::info!(
-            "GigaAM runtime plan: {}, intra_threads={}, inter_threads={}, parallel_execution={}",
-            runtime_plan.provider_summary,
-            runtime_plan.intra_threads,
-            runtime_plan.inter_threads,
-            runtime_plan.parallel_execution
-        );
+impl GigaamModel {
+    fn new(
+        model_dir: &Path,
+        manifest: &ModelManifest,
+        runtime_options: &RuntimeOptions,
+        model_cache: Option<&ModelCache>,
+    ) -> Result<Self> {
+        let load_start = Instant::now();
+        let ModelAssets {
+            vocab,
+            blank_idx,
+            frontend,
+            subsampling_factor,
+            frame_ms,
+        } = ModelAssets::load(model_dir, manifest)?;
+        let language_model = NgramLanguageModel::load_optional(model_dir)
+            .context(invalid_manifest("Invalid GigaAM language model"))?;
+        if let Some(lm) = &language_model {
+            log::info!("GigaAM language model loaded: order={}", lm.order());
+        }
+        let assets_ms = load_start.elapsed().as_millis();
+
+        let (session, session_info) = build_model_session(
+            model_dir,
+            &manifest.model,
+            runtime_options,
+            model_cache,
+            true,
+        )?;
 
         for input in &session.inputs {
             log::info!(
@@ -576,17 +1041,38 @@ impl GigaamModel {
         Ok(Self {
             session,
             frontend,
-            vocab,
-            blank_idx,
-            subsampling_factor: config.subsampling_factor.max(1),
+            decoder: CtcDecoder {
+                vocab,
+                blank_idx,
+                language_model,
+                hotwords: None,
+                subsampling_factor,
+                frame_ms,
+                score_kind: ScoreKind::from_output_name(&logits_output_name),
+            },
             features_input_name,
             feature_lengths_input_name,
             logits_output_name,
-            provider_summary: runtime_plan.provider_summary,
+            node_profile_pending: session_info.profiling,
+            load_metrics: ModelLoadMetrics {
+                provider: session_info.providers,
+                assets_ms,
+                session_ms: session_info.create_ms,
+                total_ms: load_start.elapsed().as_millis(),
+            },
         })
     }
 
-    fn transcribe_samples(&mut self, samples: &[f32]) -> Result<NativeTranscriptionReport> {
+    fn set_hotwords(&mut self, phrases: &[HotwordPhrase]) {
+        self.decoder.set_hotwords(phrases);
+    }
+
+    fn transcribe_samples(
+        &mut self,
+        samples: &[f32],
+        decode_options: &DecodeOptions,
+        max_alternatives: usize,
+    ) -> Result<NativeTranscriptionReport> {
         let total_start = Instant::now();
 
         let feature_start = Instant::now();
@@ -595,30 +1081,107 @@ impl GigaamModel {
         if feature_length == 0 {
             return Ok(NativeTranscriptionReport {
                 text: String::new(),
+                confidence: 0.0,
+                tokens: Vec::new(),
+                words: Vec::new(),
+                alternatives: Vec::new(),
+                word_alternatives: Vec::new(),
                 timings: NativeTranscriptionTimings {
                     feature_extraction_ms,
-                    ort_run_ms: 0,
-                    decode_ms: 0,
                     total_ms: total_start.elapsed().as_millis(),
+                    ..NativeTranscriptionTimings::default()
                 },
-                provider_summary: self.provider_summary.clone(),
             });
         }
 
-        let feature_lengths = Array1::from_vec(vec![feature_length]);
-        let inputs = inputs![
-            self.features_input_name.as_str() => TensorRef::from_array_view(features.view())?,
-            self.feature_lengths_input_name.as_str() => TensorRef::from_array_view(feature_lengths.view())?,
-        ];
-
         let ort_start = Instant::now();
-        let outputs = self.session.run(inputs)?;
+        let logits = self.run_acoustic_model(features.view(), &[feature_length])?;
         let ort_run_ms = ort_start.elapsed().as_millis();
 
-        let decode_start = Instant::now();
-        let logits = outputs
-            .get(self.logits_output_name.as_str())
-            .ok_or_else(|| {
+        let mut report = self.decoder.report_from_logits(
+            logits.view(),
+            feature_length,
+            decode_options,
+            max_alternatives,
+        );
+        report.timings.feature_extraction_ms = feature_extraction_ms;
+        report.timings.ort_run_ms = ort_run_ms;
+        report.timings.total_ms = total_start.elapsed().as_millis();
+        Ok(report)
+    }
+
+    /// Transcribes several utterances, running the encoder over padded batches of up to
+    /// `batch_options.max_batch_size`. Reports come back in input order; each one's
+    /// `ort_run_ms` is its share of the batch run.
+    fn transcribe_batch(
+        &mut self,
+        utterances: &[&[f32]],
+        decode_options: &DecodeOptions,
+        batch_options: &BatchOptions,
+    ) -> Result<Vec<NativeTranscriptionReport>> {
+        // Features are extracted batch by batch, so a long recording split into many
+        // chunks never holds all of them at once.
+        let lengths = utterances
+            .iter()
+            .map(|samples| self.frontend.feature_frame_count(samples.len()))
+            .collect::<Vec<_>>();
+        let mut reports = (0..utterances.len())
+            .map(|_| {
+                report_from_tokens(
+                    Vec::new(),
+                    &self.decoder.vocab,
+                    NativeTranscriptionTimings::default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for batch in plan_batches(&lengths, batch_options) {
+            let mut features = Vec::with_capacity(batch.len());
+            let mut feature_extraction_ms = Vec::with_capacity(batch.len());
+            for &index in &batch {
+                let feature_start = Instant::now();
+                features.push(self.frontend.extract_features(utterances[index])?.0);
+                feature_extraction_ms.push(feature_start.elapsed().as_millis());
+            }
+            let views = features.iter().map(Array3::view).collect::<Vec<_>>();
+            let (padded, feature_lengths) = pad_features(&views, self.frontend.silence_level())?;
+            let ort_start = Instant::now();
+            let logits = self.run_acoustic_model(padded.view(), &feature_lengths)?;
+            let ort_run_ms = ort_start.elapsed().as_millis() / batch.len() as u128;
+
+            for (row, &index) in batch.iter().enumerate() {
+                let mut report = self.decoder.report_from_logits(
+                    logits.slice(s![row..row + 1, .., ..]),
+                    feature_lengths[row],
+                    decode_options,
+                    0,
+                );
+                let timings = &mut report.timings;
+                timings.feature_extraction_ms = feature_extraction_ms[row];
+                timings.ort_run_ms = ort_run_ms;
+                timings.total_ms = timings.feature_extraction_ms + ort_run_ms + timings.decode_ms;
+                reports[index] = report;
+            }
+        }
+        Ok(reports)
+    }
+
+    /// Runs the encoder over `[batch, n_mels, frames]` features with the real frame
+    /// count of every row, and returns `[batch, time, vocab]` logits.
+    fn run_acoustic_model(
+        &mut self,
+        features: ArrayView3<'_, f32>,
+        feature_lengths: &[i64],
+    ) -> Result<Array3<f32>> {
+        let feature_lengths = ArrayView1::from(feature_lengths);
+        let inputs = inputs![
+            self.features_input_name.as_str() => TensorRef::from_array_view(features)?,
+            self.feature_lengths_input_name.as_str() => TensorRef::from_array_view(feature_lengths.view())?,
+        ];
+
+        let outputs = self.session.run(inputs)?;
+        let logits = outputs
+            .get(self.logits_output_name.as_str())
+            .ok_or_else(|| {
                 anyhow::anyhow!(
                     "GigaAM output '{}' not found in inference outputs",
                     self.logits_output_name
@@ -627,29 +1190,359 @@ impl GigaamModel {
             .try_extract_array::<f32>()?
             .to_owned()
             .into_dimensionality::<Ix3>()?;
+        drop(outputs);
+        if self.node_profile_pending {
+            self.node_profile_pending = false;
+            self.load_metrics
+                .provider
+                .record_node_assignment(&mut self.session);
+        }
+        Ok(logits)
+    }
+
+    fn start_stream(&self) -> StreamingState {
+        StreamingState::new(
+            self.frontend.frame_length(),
+            self.frontend.hop_length,
+            self.frontend.n_mels,
+            self.decoder.subsampling_factor,
+        )
+    }
 
-        let encoded_len = ((feature_length - 1) / self.subsampling_factor as i64 + 1).max(0) as usize;
-        let token_ids = ctc_greedy_decode_ids(logits.view(), encoded_len, self.blank_idx);
-        let text = decode_token_ids_to_text(&token_ids, &self.vocab);
-        let decode_ms = decode_start.elapsed().as_millis();
+    /// Feeds `samples` into a streaming session and re-decodes the sliding window when
+    /// enough new audio has arrived. `flush` decodes the remainder and settles everything.
+    fn stream_samples(
+        &mut self,
+        state: &mut StreamingState,
+        samples: &[f32],
+        decode_options: &DecodeOptions,
+        flush: bool,
+    ) -> Result<StreamingUpdate> {
+        let signal = self
+            .frontend
+            .condition_stream(state.signal_mut(), samples, flush);
+        if let Some(ready) = state.carry_mut().push(&signal) {
+            let (features, _) = self.frontend.extract_conditioned_features(&ready)?;
+            state.append_features(features.view());
+        }
+
+        if let Some(window) = state.next_window(flush)? {
+            let logits =
+                self.run_acoustic_model(window.features.view(), &[window.feature_length])?;
+            let encoded_len = self.decoder.encoded_length(window.feature_length);
+            let beam_search = decode_options.strategy == DecodeStrategy::BeamSearch;
+            let tokens = self
+                .decoder
+                .with_scorers(decode_options, beam_search, |scorers| {
+                    decode_options.decode(
+                        logits.view(),
+                        encoded_len,
+                        self.decoder.blank_idx,
+                        scorers,
+                    )
+                });
+            state.apply_decode(&window, &tokens, flush);
+        }
+        if flush {
+            state.settle_remaining();
+        }
 
-        Ok(NativeTranscriptionReport {
-            text,
+        let mut ids = token_ids(state.committed());
+        let stable_text = decode_token_ids_to_text(&ids, &self.decoder.vocab);
+        ids.extend(token_ids(state.tentative()));
+        let feature_frame_ms = self.decoder.frame_ms / self.decoder.subsampling_factor as f64;
+        Ok(StreamingUpdate {
+            stable_text,
+            text: decode_token_ids_to_text(&ids, &self.decoder.vocab),
+            audio_ms: (state.total_frames() as f64 * feature_frame_ms).round() as u64,
+            is_final: flush,
+        })
+    }
+}
+
+impl CtcDecoder {
+    fn set_hotwords(&mut self, phrases: &[HotwordPhrase]) {
+        self.hotwords = if phrases.is_empty() {
+            None
+        } else {
+            let trie = HotwordTrie::build(phrases, &self.vocab);
+            log::info!(
+                "GigaAM hotwords compiled: {} of {} phrases",
+                trie.phrase_count(),
+                phrases.len()
+            );
+            Some(trie)
+        };
+    }
+
+    /// Decodes the logits of one utterance (`[1, time, vocab]`) into a report. Only the
+    /// decode and frame counts of its timings are filled.
+    fn report_from_logits(
+        &self,
+        logits: ArrayView3<'_, f32>,
+        feature_length: i64,
+        decode_options: &DecodeOptions,
+        max_alternatives: usize,
+    ) -> NativeTranscriptionReport {
+        let decode_start = Instant::now();
+        let encoded_len = self.encoded_length(feature_length);
+        let beam_search =
+            max_alternatives > 0 || decode_options.strategy == DecodeStrategy::BeamSearch;
+        let hypotheses = self.with_scorers(decode_options, beam_search, |scorers| {
+            if max_alternatives > 0 {
+                decode_options.decode_nbest(
+                    logits,
+                    encoded_len,
+                    self.blank_idx,
+                    scorers,
+                    max_alternatives,
+                )
+            } else {
+                vec![Hypothesis {
+                    tokens: decode_options.decode(logits, encoded_len, self.blank_idx, scorers),
+                    score: 0.0,
+                }]
+            }
+        });
+
+        let mut decoded = hypotheses
+            .into_iter()
+            .map(|hypothesis| {
+                let text = decode_token_ids_to_text(&token_ids(&hypothesis.tokens), &self.vocab);
+                let confidences = token_confidences(logits, &hypothesis.tokens, self.score_kind);
+                let tokens =
+                    token_timings(&hypothesis.tokens, &confidences, &self.vocab, self.frame_ms);
+                let words = word_timings(&tokens);
+                (
+                    HypothesisWords {
+                        text,
+                        words,
+                        score: hypothesis.score,
+                    },
+                    aggregate_confidence(&confidences),
+                    tokens,
+                )
+            })
+            .collect::<Vec<_>>();
+        let (alternatives, word_alternatives) = if max_alternatives > 0 {
+            let hypotheses = decoded
+                .iter()
+                .map(|(hypothesis, _, _)| hypothesis.clone())
+                .collect::<Vec<_>>();
+            (
+                transcript_alternatives(&hypotheses),
+                word_alternatives(&hypotheses),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let (best, confidence, tokens) = if decoded.is_empty() {
+            (
+                HypothesisWords {
+                    text: String::new(),
+                    words: Vec::new(),
+                    score: 0.0,
+                },
+                0.0,
+                Vec::new(),
+            )
+        } else {
+            decoded.swap_remove(0)
+        };
+        NativeTranscriptionReport {
+            text: best.text,
+            confidence,
+            tokens,
+            words: best.words,
+            alternatives,
+            word_alternatives,
             timings: NativeTranscriptionTimings {
-                feature_extraction_ms,
-                ort_run_ms,
-                decode_ms,
-                total_ms: total_start.elapsed().as_millis(),
+                decode_ms: decode_start.elapsed().as_millis(),
+                feature_frames: feature_length as usize,
+                encoder_frames: encoded_len,
+                ..NativeTranscriptionTimings::default()
             },
-            provider_summary: self.provider_summary.clone(),
+        }
+    }
+
+    fn encoded_length(&self, feature_length: i64) -> usize {
+        ((feature_length - 1) / self.subsampling_factor as i64 + 1).max(0) as usize
+    }
+
+    /// Runs `decode` with the LM and hotword scorers that apply to this strategy; both
+    /// are only used by beam search.
+    fn with_scorers<R>(
+        &self,
+        decode_options: &DecodeOptions,
+        beam_search: bool,
+        decode: impl FnOnce(&[&dyn DynPrefixScorer]) -> R,
+    ) -> R {
+        let lm_scorer = self
+            .language_model
+            .as_ref()
+            .filter(|_| beam_search)
+            .map(|lm| {
+                LanguageModelScorer::new(
+                    lm,
+                    &self.vocab,
+                    decode_options.lm_alpha,
+                    decode_options.lm_beta,
+                )
+            });
+        let mut scorers = Vec::<&dyn DynPrefixScorer>::with_capacity(2);
+        if let Some(scorer) = &lm_scorer {
+            scorers.push(scorer);
+        }
+        if let Some(trie) = self.hotwords.as_ref().filter(|_| beam_search) {
+            scorers.push(trie);
+        }
+        decode(&scorers)
+    }
+}
+
+/// Rebuilds text, words and confidence from already timed tokens.
+pub fn report_from_tokens(
+    tokens: Vec<TokenTiming>,
+    vocab: &[String],
+    timings: NativeTranscriptionTimings,
+) -> NativeTranscriptionReport {
+    let ids = tokens
+        .iter()
+        .map(|token| token.token_id)
+        .collect::<Vec<_>>();
+    let confidences = tokens
+        .iter()
+        .map(|token| token.confidence)
+        .collect::<Vec<_>>();
+    NativeTranscriptionReport {
+        text: decode_token_ids_to_text(&ids, vocab),
+        confidence: aggregate_confidence(&confidences),
+        words: word_timings(&tokens),
+        tokens,
+        alternatives: Vec::new(),
+        word_alternatives: Vec::new(),
+        timings,
+    }
+}
+
+/// The loaded acoustic model, picked by the manifest's `architecture`.
+enum AcousticModel {
+    Ctc(Box<GigaamModel>),
+    Rnnt(Box<RnntModel>),
+}
+
+impl AcousticModel {
+    fn load(
+        model_dir: &Path,
+        manifest: &ModelManifest,
+        runtime_options: &RuntimeOptions,
+        model_cache: Option<&ModelCache>,
+    ) -> Result<Self> {
+        Ok(match manifest.architecture {
+            ModelArchitecture::Ctc => Self::Ctc(Box::new(GigaamModel::new(
+                model_dir,
+                manifest,
+                runtime_options,
+                model_cache,
+            )?)),
+            ModelArchitecture::Rnnt => Self::Rnnt(Box::new(RnntModel::new(
+                model_dir,
+                manifest,
+                runtime_options,
+                model_cache,
+            )?)),
         })
     }
+
+    fn set_hotwords(&mut self, phrases: &[HotwordPhrase]) {
+        match self {
+            Self::Ctc(model) => model.set_hotwords(phrases),
+            // Hotwords bias CTC beam search only; transducer decoding is greedy.
+            Self::Rnnt(_) => {}
+        }
+    }
+
+    /// Transducers are decoded greedily, without hotwords or language model fusion.
+    fn decodes_greedily_only(&self) -> bool {
+        matches!(self, Self::Rnnt(_))
+    }
+
+    fn transcribe_samples(
+        &mut self,
+        samples: &[f32],
+        decode_options: &DecodeOptions,
+        max_alternatives: usize,
+    ) -> Result<NativeTranscriptionReport> {
+        match self {
+            Self::Ctc(model) => model.transcribe_samples(samples, decode_options, max_alternatives),
+            Self::Rnnt(_) if max_alternatives > 0 => Err(BridgeError::new(
+                ErrorCode::InvalidArgument,
+                "Alternatives are only available for CTC models",
+            )
+            .into()),
+            Self::Rnnt(model) => model.transcribe_samples(samples),
+        }
+    }
+
+    fn transcribe_batch(
+        &mut self,
+        utterances: &[&[f32]],
+        decode_options: &DecodeOptions,
+        batch_options: &BatchOptions,
+    ) -> Result<Vec<NativeTranscriptionReport>> {
+        match self {
+            Self::Ctc(model) => model.transcribe_batch(utterances, decode_options, batch_options),
+            Self::Rnnt(model) => model.transcribe_batch(utterances, batch_options),
+        }
+    }
+
+    fn report_from_tokens(
+        &self,
+        tokens: Vec<TokenTiming>,
+        timings: NativeTranscriptionTimings,
+    ) -> NativeTranscriptionReport {
+        let vocab = match self {
+            Self::Ctc(model) => model.decoder.vocab.as_slice(),
+            Self::Rnnt(model) => model.vocab(),
+        };
+        report_from_tokens(tokens, vocab, timings)
+    }
+
+    fn load_metrics(&self) -> &ModelLoadMetrics {
+        match self {
+            Self::Ctc(model) => &model.load_metrics,
+            Self::Rnnt(model) => model.load_metrics(),
+        }
+    }
+
+    fn start_stream(&self) -> Result<StreamingState> {
+        match self {
+            Self::Ctc(model) => Ok(model.start_stream()),
+            Self::Rnnt(_) => Err(streaming_unsupported()),
+        }
+    }
+
+    fn stream_samples(
+        &mut self,
+        state: &mut StreamingState,
+        samples: &[f32],
+        decode_options: &DecodeOptions,
+        flush: bool,
+    ) -> Result<StreamingUpdate> {
+        match self {
+            Self::Ctc(model) => model.stream_samples(state, samples, decode_options, flush),
+            Self::Rnnt(_) => Err(streaming_unsupported()),
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct GigaamEngine {
     loaded_model_path: Option<PathBuf>,
-    model: Option<GigaamModel>,
+    model: Option<AcousticModel>,
+    decode_options: DecodeOptions,
+    batch_options: BatchOptions,
+    hotwords: Vec<HotwordPhrase>,
 }
 
 impl GigaamEngine {
@@ -657,28 +1550,484 @@ impl GigaamEngine {
         Self::default()
     }
 
-    pub fn load_model(&mut self, model_path: &Path, runtime_options: RuntimeOptions) -> Result<()> {
-        let model = GigaamModel::new(model_path, runtime_options)?;
+    pub fn load_model(
+        &mut self,
+        model_path: &Path,
+        manifest: &ModelManifest,
+        runtime_options: &RuntimeOptions,
+        model_cache: Option<&ModelCache>,
+    ) -> Result<()> {
+        let mut model = AcousticModel::load(model_path, manifest, runtime_options, model_cache)?;
+        model.set_hotwords(&self.hotwords);
         self.model = Some(model);
         self.loaded_model_path = Some(model_path.to_path_buf());
         Ok(())
     }
 
+    /// How the loaded model was loaded; `None` before [`Self::load_model`].
+    pub fn load_metrics(&self) -> Option<&ModelLoadMetrics> {
+        self.model.as_ref().map(AcousticModel::load_metrics)
+    }
+
+    /// Whether the loaded model ignores beam search, hotwords and the language model.
+    pub fn decodes_greedily_only(&self) -> bool {
+        self.model
+            .as_ref()
+            .is_some_and(AcousticModel::decodes_greedily_only)
+    }
+
+    pub fn set_decode_options(&mut self, decode_options: DecodeOptions) {
+        self.decode_options = decode_options;
+    }
+
+    /// How [`Self::transcribe_batch`] and long-form chunks share encoder runs.
+    pub fn set_batch_options(&mut self, batch_options: BatchOptions) {
+        self.batch_options = batch_options;
+    }
+
+    /// Replaces the boost phrases used by beam search. They are kept across model reloads.
+    pub fn set_hotwords(&mut self, phrases: Vec<HotwordPhrase>) {
+        if let Some(model) = self.model.as_mut() {
+            model.set_hotwords(&phrases);
+        }
+        self.hotwords = phrases;
+    }
+
     pub fn unload_model(&mut self) {
         self.loaded_model_path = None;
         self.model = None;
     }
 
     pub fn transcribe_samples(&mut self, samples: &[f32]) -> Result<NativeTranscriptionReport> {
-        let model = self
-            .model
-            .as_mut()
-            .ok_or_else(|| anyhow::anyhow!("GigaAM model is not loaded"))?;
-        model.transcribe_samples(samples)
+        check_audio(samples)?;
+        let model = self.model.as_mut().ok_or_else(model_not_loaded)?;
+        model.transcribe_samples(samples, &self.decode_options, 0)
+    }
+
+    /// Transcribes several independent utterances, such as a backlog of recordings.
+    /// Features are padded to the longest utterance of each batch and every row is
+    /// decoded with its own length, so the transcripts match one-at-a-time runs.
+    /// Utterances longer than `long_form.max_segment_ms` are split and stitched as in
+    /// `transcribe_long_form`, with their chunks batched alongside the other rows.
+    pub fn transcribe_batch(
+        &mut self,
+        utterances: &[&[f32]],
+        long_form: &LongFormOptions,
+    ) -> Result<Vec<NativeTranscriptionReport>> {
+        for samples in utterances {
+            check_audio(samples)?;
+        }
+        let model = self.model.as_mut().ok_or_else(model_not_loaded)?;
+
+        let max_segment_samples = long_form.max_segment_samples();
+        let mut plans = Vec::with_capacity(utterances.len());
+        for samples in utterances {
+            let plan = if max_segment_samples > 0 && samples.len() > max_segment_samples {
+                // The energy detector only picks the cut points, as in `transcribe_long_form`.
+                let vad_start = Instant::now();
+                let segments =
+                    VoiceActivityDetector::energy(VadOptions::default())?.detect(samples)?;
+                let chunks = plan_chunks(0..samples.len(), &segments, long_form);
+                (chunks, Some(vad_start.elapsed().as_millis()))
+            } else {
+                let chunk = AudioChunk {
+                    start_sample: 0,
+                    end_sample: samples.len(),
+                };
+                (vec![chunk], None)
+            };
+            plans.push(plan);
+        }
+        let chunk_samples = utterances
+            .iter()
+            .zip(&plans)
+            .flat_map(|(samples, (chunks, _))| {
+                chunks
+                    .iter()
+                    .map(|chunk| &samples[chunk.start_sample..chunk.end_sample])
+            })
+            .collect::<Vec<_>>();
+        let mut reports = model
+            .transcribe_batch(&chunk_samples, &self.decode_options, &self.batch_options)?
+            .into_iter();
+
+        let mut results = Vec::with_capacity(utterances.len());
+        for (chunks, vad_ms) in plans {
+            let chunk_reports = reports.by_ref().take(chunks.len()).collect::<Vec<_>>();
+            let report = match vad_ms {
+                Some(vad_ms) => {
+                    let chunk_total_ms = chunk_reports
+                        .iter()
+                        .map(|report| report.timings.total_ms)
+                        .sum::<u128>();
+                    let mut report = stitch_chunk_reports(model, &chunks, chunk_reports, vad_ms);
+                    report.timings.total_ms = vad_ms + chunk_total_ms;
+                    report
+                }
+                None => chunk_reports
+                    .into_iter()
+                    .next()
+                    .context("Missing batch result")?,
+            };
+            results.push(report);
+        }
+        Ok(results)
+    }
+
+    /// Like `transcribe_samples`, but always runs beam search and also reports up to
+    /// `max_alternatives` hypotheses with per-word alternatives.
+    pub fn transcribe_samples_with_alternatives(
+        &mut self,
+        samples: &[f32],
+        max_alternatives: usize,
+    ) -> Result<NativeTranscriptionReport> {
+        check_audio(samples)?;
+        let model = self.model.as_mut().ok_or_else(model_not_loaded)?;
+        model.transcribe_samples(samples, &self.decode_options, max_alternatives.max(1))
+    }
+
+    /// Transcribes only the detected speech: audio before the first and after the last
+    /// segment is skipped, and inference is not run at all when there is no speech.
+    /// Timestamps stay relative to the start of `samples`.
+    pub fn transcribe_speech(
+        &mut self,
+        samples: &[f32],
+        vad: &mut VoiceActivityDetector,
+        max_alternatives: usize,
+    ) -> Result<NativeTranscriptionReport> {
+        check_audio(samples)?;
+        let vad_start = Instant::now();
+        let segments = vad.detect(samples)?;
+        let vad_ms = vad_start.elapsed().as_millis();
+
+        let (speech, offset_ms) = match (segments.first(), segments.last()) {
+            (Some(first), Some(last)) => {
+                (&samples[first.start_sample..last.end_sample], first.start_ms)
+            }
+            _ => (&samples[..0], 0),
+        };
+        let mut report = if max_alternatives > 0 {
+            self.transcribe_samples_with_alternatives(speech, max_alternatives)?
+        } else {
+            self.transcribe_samples(speech)?
+        };
+        report.shift_timestamps(offset_ms);
+        report.timings.vad_ms = vad_ms;
+        report.timings.total_ms += vad_ms;
+        Ok(report)
+    }
+
+    /// Transcribes audio of any length by running the encoder over chunks of at most
+    /// `options.max_segment_ms`, cut at pauses where possible, and stitching the chunk
+    /// transcripts. With a `vad`, audio outside the detected speech is skipped as in
+    /// `transcribe_speech`; without one the energy detector only picks the cut points.
+    pub fn transcribe_long_form(
+        &mut self,
+        samples: &[f32],
+        vad: Option<&mut VoiceActivityDetector>,
+        options: &LongFormOptions,
+    ) -> Result<NativeTranscriptionReport> {
+        check_audio(samples)?;
+        let total_start = Instant::now();
+        let model = self.model.as_mut().ok_or_else(model_not_loaded)?;
+
+        let vad_start = Instant::now();
+        let (segments, range) = match vad {
+            Some(vad) => {
+                let segments = vad.detect(samples)?;
+                let range = match (segments.first(), segments.last()) {
+                    (Some(first), Some(last)) => first.start_sample..last.end_sample,
+                    _ => 0..0,
+                };
+                (segments, range)
+            }
+            None => {
+                let segments = VoiceActivityDetector::energy(VadOptions::default())?.detect(samples)?;
+                (segments, 0..samples.len())
+            }
+        };
+        let vad_ms = vad_start.elapsed().as_millis();
+
+        let chunks = plan_chunks(range, &segments, options);
+        let chunk_samples = chunks
+            .iter()
+            .map(|chunk| &samples[chunk.start_sample..chunk.end_sample])
+            .collect::<Vec<_>>();
+        let reports =
+            model.transcribe_batch(&chunk_samples, &self.decode_options, &self.batch_options)?;
+        let mut report = stitch_chunk_reports(model, &chunks, reports, vad_ms);
+        report.timings.total_ms = total_start.elapsed().as_millis();
+        Ok(report)
+    }
+
+    /// Starts a streaming session for the loaded model. The state is only valid for
+    /// this model; drop it when the model is reloaded.
+    pub fn start_stream(&self) -> Result<StreamingState> {
+        let model = self.model.as_ref().ok_or_else(model_not_loaded)?;
+        model.start_stream()
+    }
+
+    pub fn push_stream(
+        &mut self,
+        state: &mut StreamingState,
+        samples: &[f32],
+    ) -> Result<StreamingUpdate> {
+        check_audio(samples)?;
+        let model = self.model.as_mut().ok_or_else(model_not_loaded)?;
+        model.stream_samples(state, samples, &self.decode_options, false)
+    }
+
+    pub fn finish_stream(&mut self, state: &mut StreamingState) -> Result<StreamingUpdate> {
+        let model = self.model.as_mut().ok_or_else(model_not_loaded)?;
+        model.stream_samples(state, &[], &self.decode_options, true)
     }
 }
 
-fn parse_vocab_content(content: &str) -> Result<(Vec<String>, usize)> {
+/// Joins the reports of one recording's chunks into a single report with timestamps
+/// from the start of the recording; the caller fills in `total_ms`.
+fn stitch_chunk_reports(
+    model: &AcousticModel,
+    chunks: &[AudioChunk],
+    reports: Vec<NativeTranscriptionReport>,
+    vad_ms: u128,
+) -> NativeTranscriptionReport {
+    let mut timings = NativeTranscriptionTimings {
+        vad_ms,
+        ..NativeTranscriptionTimings::default()
+    };
+    let mut transcripts = Vec::with_capacity(chunks.len());
+    for (chunk, mut report) in chunks.iter().zip(reports) {
+        report.shift_timestamps(chunk.start_ms());
+        timings.feature_extraction_ms += report.timings.feature_extraction_ms;
+        timings.ort_run_ms += report.timings.ort_run_ms;
+        timings.decode_ms += report.timings.decode_ms;
+        timings.feature_frames += report.timings.feature_frames;
+        timings.encoder_frames += report.timings.encoder_frames;
+        transcripts.push(report.tokens);
+    }
+    log::info!("GigaAM long-form transcription: {} chunks", chunks.len());
+
+    let tokens = stitch_chunks(chunks, &transcripts);
+    model.report_from_tokens(tokens, timings)
+}
+
+fn model_not_loaded() -> anyhow::Error {
+    BridgeError::new(ErrorCode::EngineNotLoaded, "GigaAM model is not loaded").into()
+}
+
+fn streaming_unsupported() -> anyhow::Error {
+    BridgeError::new(
+        ErrorCode::InvalidArgument,
+        "Streaming is only available for CTC models",
+    )
+    .into()
+}
+
+fn invalid_manifest(message: impl Into<String>) -> BridgeError {
+    BridgeError::new(ErrorCode::InvalidManifest, message)
+}
+
+/// Rejects samples the frontend cannot turn into features.
+fn check_audio(samples: &[f32]) -> Result<()> {
+    match samples.iter().position(|sample| !sample.is_finite()) {
+        Some(index) => Err(BridgeError::new(
+            ErrorCode::InvalidAudio,
+            format!("Audio sample {index} is not a finite number"),
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// How [`build_ort_session`] created a session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionInfo {
+    pub providers: ProviderSummary,
+    pub create_ms: u128,
+    /// The first run is profiled; see [`ProviderSummary::record_node_assignment`].
+    pub profiling: bool,
+}
+
+/// Opens `file` of `model_dir` with [`build_ort_session`], through `model_cache` when set.
+/// With `profile_nodes` the first run is profiled into the model cache directory, the
+/// one writable location the host hands over, to find out which provider runs each
+/// node; without a model cache the run is not profiled.
+pub fn build_model_session(
+    model_dir: &Path,
+    file: &ModelFile,
+    runtime_options: &RuntimeOptions,
+    model_cache: Option<&ModelCache>,
+    profile_nodes: bool,
+) -> Result<(Session, SessionInfo)> {
+    let model_path = model_dir.join(&file.path);
+    let cache_entry = model_cache
+        .map(|cache| cache.entry(&model_path, file.sha256.as_deref(), runtime_options))
+        .transpose()?;
+    let profile_dir = model_cache.filter(|_| profile_nodes).map(ModelCache::dir);
+    let profile_prefix = match profile_dir.map(|dir| (dir, fs::create_dir_all(dir))) {
+        Some((dir, Ok(()))) => Some(dir.join("gigaam-node-profile")),
+        Some((_, Err(error))) => {
+            log::warn!("GigaAM node profiling disabled: {error}");
+            None
+        }
+        None => None,
+    };
+    build_ort_session(
+        &model_path,
+        runtime_options,
+        cache_entry.as_ref(),
+        profile_prefix.as_deref(),
+    )
+}
+
+/// Creates an ONNX Runtime session with the providers and threading of `runtime_options`.
+/// Returns the session and how it was created. With a `cache_entry` the optimized graph
+/// is saved on the first load and reused by later ones; with a `profile_prefix` the
+/// first run is profiled to a file starting with it.
+pub fn build_ort_session(
+    model_path: &Path,
+    runtime_options: &RuntimeOptions,
+    cache_entry: Option<&CacheEntry>,
+    profile_prefix: Option<&Path>,
+) -> Result<(Session, SessionInfo)> {
+    let create_start = Instant::now();
+    let runtime_plan = SessionRuntimePlan::from_runtime_options(runtime_options);
+    let providers = RefCell::new(runtime_plan.summary.clone());
+    let builder = |level: OptimizationLevel| -> Result<SessionBuilder> {
+        let mut builder = Session::builder()?
+            .with_optimization_level(level.ort_level())?
+            .with_intra_threads(runtime_plan.summary.intra_threads)?
+            .with_inter_threads(runtime_plan.summary.inter_threads)?;
+        let mut summary = runtime_plan.summary.clone();
+        summary.set_registrations(register_providers(&mut builder, &runtime_plan.providers));
+        if runtime_plan.require_accelerator && !summary.has_accelerator() {
+            let failures = summary
+                .requested
+                .iter()
+                .filter(|registration| registration.name != CPU_PROVIDER)
+                .map(|registration| {
+                    format!(
+                        "{}: {}",
+                        registration.name,
+                        registration.error.as_deref().unwrap_or("not registered")
+                    )
+                })
+                .collect::<Vec<_>>();
+            anyhow::bail!(
+                "Hardware acceleration is required, but no accelerator registered ({})",
+                failures.join("; ")
+            );
+        }
+        *providers.borrow_mut() = summary;
+        let builder = builder
+            .with_parallel_execution(runtime_plan.summary.parallel_execution)?
+            .with_memory_pattern(runtime_plan.memory_pattern)?;
+        Ok(match profile_prefix {
+            Some(prefix) => builder.with_profiling(prefix)?,
+            None => builder,
+        })
+    };
+    // Without optimizations there is nothing worth caching.
+    let cache_entry =
+        cache_entry.filter(|_| runtime_plan.optimization_level != OptimizationLevel::Disable);
+    let (session, cache_status) = match cache_entry {
+        Some(entry) => build_cached_session(model_path, entry, &runtime_plan, builder)?,
+        None => (
+            commit_mapped(builder(runtime_plan.optimization_level)?, model_path)?,
+            "off",
+        ),
+    };
+
+    let mut providers = providers.into_inner();
+    providers.optimized_model = cache_status;
+    log::info!(
+        "GigaAM session {}: {}",
+        model_path.display(),
+        providers.to_json()
+    );
+    Ok((
+        session,
+        SessionInfo {
+            providers,
+            create_ms: create_start.elapsed().as_millis(),
+            profiling: profile_prefix.is_some(),
+        },
+    ))
+}
+
+/// Loads through the optimized model cache and reports `reused`, `saved` or `failed`.
+/// CPU sessions save their fully optimized graph and reload it without optimizing. Graphs
+/// of compiling providers cannot be saved, so for them a CPU session stores the
+/// provider-independent basic optimizations and the real session finishes the rest.
+fn build_cached_session(
+    model_path: &Path,
+    entry: &CacheEntry,
+    runtime_plan: &SessionRuntimePlan,
+    builder: impl Fn(OptimizationLevel) -> Result<SessionBuilder>,
+) -> Result<(Session, &'static str)> {
+    let reuse_level = if runtime_plan.compiles_nodes {
+        runtime_plan.optimization_level
+    } else {
+        OptimizationLevel::Disable
+    };
+    if entry.exists() {
+        match commit_mapped(builder(reuse_level)?, entry.path()) {
+            Ok(session) => return Ok((session, "reused")),
+            Err(error) => {
+                log::warn!("GigaAM optimized model unusable, rebuilding: {error:#}");
+                entry.discard();
+            }
+        }
+    }
+
+    let saved = entry.staging_path().and_then(|staging_path| {
+        if runtime_plan.compiles_nodes {
+            let cpu_builder = Session::builder()?
+                .with_optimization_level(GraphOptimizationLevel::Level1)?
+                .with_execution_providers([CPUExecutionProvider::default().build()])?
+                .with_optimized_model_path(&staging_path)?;
+            drop(commit_mapped(cpu_builder, model_path)?);
+            entry.publish()?;
+            commit_mapped(builder(reuse_level)?, entry.path())
+        } else {
+            let session = commit_mapped(
+                builder(runtime_plan.optimization_level)?
+                    .with_optimized_model_path(&staging_path)?,
+                model_path,
+            )?;
+            entry.publish()?;
+            Ok(session)
+        }
+    });
+    match saved {
+        Ok(session) => Ok((session, "saved")),
+        Err(error) => {
+            log::warn!("GigaAM optimized model not saved: {error:#}");
+            entry.discard();
+            let session = commit_mapped(builder(runtime_plan.optimization_level)?, model_path)?;
+            Ok((session, "failed"))
+        }
+    }
+}
+
+/// Commits `builder` from a memory map of `model_path`. Models whose weights live in
+/// external data files need the path to resolve them and are committed from the file.
+fn commit_mapped(builder: SessionBuilder, model_path: &Path) -> Result<Session> {
+    let session = if has_external_data(model_path) {
+        builder.commit_from_file(model_path)
+    } else {
+        let bytes = map_model(model_path)?;
+        builder.commit_from_memory(&bytes)
+    };
+    session.with_context(|| {
+        format!(
+            "Failed to initialize ONNX Runtime session: {}",
+            model_path.display()
+        )
+    })
+}
+
+pub fn parse_vocab_content(content: &str) -> Result<(Vec<String>, usize)> {
     let mut entries = Vec::<(usize, String)>::new();
     let mut max_id = 0_usize;
     let mut blank_idx = None;
@@ -715,36 +2064,7 @@ fn parse_vocab_content(content: &str) -> Result<(Vec<String>, usize)> {
     Ok((vocab, blank_idx))
 }
 
-fn ctc_greedy_decode_ids(
-    logits: ArrayView3<'_, f32>,
-    encoded_len: usize,
-    blank_idx: usize,
-) -> Vec<usize> {
-    let time_steps = logits.shape()[1];
-    let usable_steps = encoded_len.min(time_steps);
-
-    let mut token_ids = Vec::with_capacity(usable_steps);
-    let mut prev_token = blank_idx;
-
-    for frame_idx in 0..usable_steps {
-        let frame = logits.slice(s![0, frame_idx, ..]);
-        let best_idx = frame
-            .iter()
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
-            .map(|(idx, _)| idx)
-            .unwrap_or(blank_idx);
-
-        if best_idx != blank_idx && best_idx != prev_token {
-            token_ids.push(best_idx);
-        }
-        prev_token = best_idx;
-    }
-
-    token_ids
-}
-
-fn decode_token_ids_to_text(token_ids: &[usize], vocab: &[String]) -> String {
+pub fn decode_token_ids_to_text(token_ids: &[usize], vocab: &[String]) -> String {
     let concatenated = token_ids
         .iter()
         .filter_map(|&id| vocab.get(id))
@@ -781,29 +2101,34 @@ fn build_hann_window(win_length: usize, quantize_bf16: bool) -> Vec<f32> {
         .collect()
 }
 
-fn build_mel_filterbank(
-    sample_rate: usize,
-    n_fft: usize,
-    n_mels: usize,
-    quantize_bf16: bool,
-) -> Result<Vec<f32>> {
+/// Triangular mel filters, one per mel band, as torchaudio's `melscale_fbanks`.
+fn build_mel_filterbank(spec: &MelSpec, quantize_bf16: bool) -> Result<Vec<MelBand>> {
+    let MelSpec {
+        sample_rate,
+        n_fft,
+        n_mels,
+        f_min,
+        f_max,
+        scale,
+        slaney_norm,
+    } = *spec;
     let n_freq_bins = n_fft / 2 + 1;
-    let f_min = 0.0_f32;
-    let f_max = (sample_rate as f32) / 2.0;
 
-    let mel_min = hz_to_mel_htk(f_min);
-    let mel_max = hz_to_mel_htk(f_max);
+    let mel_min = scale.hz_to_mel(f_min as f32);
+    let mel_max = scale.hz_to_mel(f_max as f32);
 
     let mel_points: Vec<f32> = (0..(n_mels + 2))
         .map(|i| mel_min + (mel_max - mel_min) * (i as f32 / (n_mels + 1) as f32))
         .collect();
-    let hz_points: Vec<f32> = mel_points.into_iter().map(mel_to_hz_htk).collect();
+    let hz_points: Vec<f32> = mel_points
+        .into_iter()
+        .map(|mel| scale.mel_to_hz(mel))
+        .collect();
     let fft_freqs: Vec<f32> = (0..n_freq_bins)
         .map(|bin| bin as f32 * sample_rate as f32 / n_fft as f32)
         .collect();
 
-    let mut filterbank = vec![0.0_f32; n_freq_bins * n_mels];
-
+    let mut bands = Vec::with_capacity(n_mels);
     for mel_idx in 0..n_mels {
         let left = hz_points[mel_idx];
         let center = hz_points[mel_idx + 1];
@@ -816,36 +2141,42 @@ fn build_mel_filterbank(
             ));
         }
 
-        for (bin_idx, &freq) in fft_freqs.iter().enumerate() {
-            let weight = if freq >= left && freq <= center {
-                (freq - left) / (center - left)
-            } else if freq > center && freq <= right {
-                (right - freq) / (right - center)
-            } else {
-                0.0
-            };
-
-            if weight > 0.0 {
-                filterbank[bin_idx * n_mels + mel_idx] = if quantize_bf16 {
+        let area_scale = if slaney_norm {
+            2.0 / (right - left)
+        } else {
+            1.0
+        };
+        let weights = fft_freqs
+            .iter()
+            .map(|&freq| {
+                let triangle = if freq >= left && freq <= center {
+                    (freq - left) / (center - left)
+                } else if freq > center && freq <= right {
+                    (right - freq) / (right - center)
+                } else {
+                    0.0
+                };
+                let weight = triangle * area_scale;
+                if quantize_bf16 {
                     quantize_to_bf16(weight)
                 } else {
                     weight
-                };
-            }
-        }
+                }
+            })
+            .collect::<Vec<_>>();
+        // The triangle is contiguous; a band narrower than a bin may have no weights.
+        let start_bin = weights.iter().position(|&weight| weight > 0.0).unwrap_or(0);
+        let end_bin = weights
+            .iter()
+            .rposition(|&weight| weight > 0.0)
+            .map_or(0, |bin| bin + 1);
+        bands.push(MelBand {
+            start_bin,
+            weights: weights[start_bin..end_bin.max(start_bin)].to_vec(),
+        });
     }
 
-    Ok(filterbank)
-}
-
-#[inline]
-fn hz_to_mel_htk(hz: f32) -> f32 {
-    2595.0 * (1.0 + hz / 700.0).log10()
-}
-
-#[inline]
-fn mel_to_hz_htk(mel: f32) -> f32 {
-    700.0 * (10_f32.powf(mel / 2595.0) - 1.0)
+    Ok(bands)
 }
 
 #[inline]
@@ -853,13 +2184,10 @@ fn quantize_to_bf16(value: f32) -> f32 {
     f32::from_bits(value.to_bits() & 0xFFFF_0000)
 }
 
-fn escape_json_string(value: &str) -> String {
-    value.replace('\\', "\\\\").replace('\"', "\\\"")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decoder::ctc_greedy_decode;
 
     #[test]
     fn vocab_loader_parses_blank_and_word_boundary_tokens() -> Result<()> {
@@ -872,6 +2200,14 @@ mod tests {
         Ok(())
     }
 
+    fn ctc_greedy_decode_ids(
+        logits: ArrayView3<'_, f32>,
+        encoded_len: usize,
+        blank_idx: usize,
+    ) -> Vec<usize> {
+        token_ids(&ctc_greedy_decode(logits, encoded_len, blank_idx))
+    }
+
     #[test]
     fn ctc_decoder_collapses_repeats_and_removes_blank() -> Result<()> {
         let logits = Array3::from_shape_vec(
@@ -891,6 +2227,798 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn detailed_results_name_token_ids_and_leave_out_word_ranges() -> Result<()> {
+        let (vocab, _) = parse_vocab_content("<unk> 0\n\u{2581}да 1\n<blk> 2\n")?;
+        let token = TokenTiming {
+            token_id: 1,
+            text: vocab[1].clone(),
+            start_ms: 40,
+            end_ms: 80,
+            confidence: 0.5,
+        };
+        let report = report_from_tokens(vec![token], &vocab, NativeTranscriptionTimings::default());
+        let json = serde_json::to_value(report.result())?;
+        assert_eq!(json["text"], "да");
+        assert_eq!(json["tokens"][0]["id"], 1);
+        assert_eq!(json["tokens"][0]["start_ms"], 40);
+        assert_eq!(
+            json["words"][0],
+            serde_json::json!({"text": "да", "start_ms": 40, "end_ms": 80, "confidence": 0.5})
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn padded_batch_rows_decode_like_single_utterances() -> Result<()> {
+        let (vocab, blank_idx) =
+            parse_vocab_content("<unk> 0\n\u{2581}a 1\nb 2\n\u{2581}c 3\n<blk> 4\n")?;
+        let decoder = CtcDecoder {
+            vocab,
+            blank_idx,
+            language_model: None,
+            hotwords: None,
+            subsampling_factor: 4,
+            frame_ms: 40.0,
+            score_kind: ScoreKind::LogProbs,
+        };
+        let log_probs = |peaks: &[usize]| {
+            Array3::from_shape_fn((1, peaks.len(), 5), |(_, frame, token)| {
+                if token == peaks[frame] {
+                    -0.1
+                } else {
+                    -4.0
+                }
+            })
+        };
+        // 12 and 24 feature frames give 3 and 6 encoder frames. The padded tail of the
+        // short row holds confident tokens, as an encoder may emit over padding.
+        let short = log_probs(&[1, 4, 2]);
+        let long = log_probs(&[3, 3, 4, 1, 2, 4]);
+        let mut batch = Array3::zeros((2, 6, 5));
+        batch
+            .slice_mut(s![0..1, .., ..])
+            .assign(&log_probs(&[1, 4, 2, 3, 3, 1]));
+        batch.slice_mut(s![1..2, .., ..]).assign(&long);
+
+        for strategy in ["greedy", "beam"] {
+            let options = DecodeOptions::from_ids(strategy, 4);
+            for (row, (single, feature_length)) in
+                [(&short, 12), (&long, 24)].into_iter().enumerate()
+            {
+                let expected =
+                    decoder.report_from_logits(single.view(), feature_length, &options, 0);
+                let batched = decoder.report_from_logits(
+                    batch.slice(s![row..row + 1, .., ..]),
+                    feature_length,
+                    &options,
+                    0,
+                );
+                assert_eq!(batched.text, expected.text, "{strategy} row {row}");
+                assert_eq!(batched.tokens, expected.tokens, "{strategy} row {row}");
+                assert_eq!(batched.words, expected.words, "{strategy} row {row}");
+                assert_eq!(
+                    batched.confidence, expected.confidence,
+                    "{strategy} row {row}"
+                );
+                assert_eq!(batched.timings.encoder_frames, single.dim().1);
+            }
+        }
+        assert_eq!(
+            decoder
+                .report_from_logits(short.view(), 12, &DecodeOptions::default(), 0)
+                .text,
+            "ab"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn runtime_options_json_overrides_the_preset() -> Result<()> {
+        let options = RuntimeOptions::from_json(
+            r#"{"speed_profile": "fast", "accelerator_mode": "cpu", "intra_threads": 2,
+                "providers": ["nnapi", "cpu", "nnapi"], "optimization_level": "basic"}"#,
+        )?;
+        assert_eq!(options.speed_profile, RuntimeSpeedProfile::Fast);
+        assert_eq!(options.intra_threads, 2);
+        assert_eq!(options.inter_threads, 1);
+        assert_eq!(options.providers, [ProviderKind::Nnapi, ProviderKind::Cpu]);
+        assert_eq!(options.optimization_level, OptimizationLevel::Basic);
+        assert!(options.memory_pattern);
+        assert_ne!(
+            options.cache_fragment(),
+            RuntimeOptions::from_ids("fast", "cpu").cache_fragment()
+        );
+
+        assert_eq!(RuntimeOptions::from_json("{}")?, RuntimeOptions::default());
+        let cpu_only = RuntimeOptions::from_json(r#"{"providers": []}"#)?;
+        assert_eq!(cpu_only.providers, [ProviderKind::Cpu]);
+
+        for invalid in [
+            r#"{"intra_threads": 0}"#,
+            r#"{"threads": 2}"#,
+            r#"{"providers": ["cuda"]}"#,
+            r#"{"optimization_level": "max"}"#,
+        ] {
+            assert!(RuntimeOptions::from_json(invalid).is_err(), "{invalid}");
+        }
+        Ok(())
+    }
+
+    /// Golden references of `testdata/frontend`. They are not checked in: they must come
+    /// from `generate_references.py` run against torchaudio and a GigaAM v3 checkpoint,
+    /// and the tests reject a file without that provenance.
+    #[derive(Deserialize)]
+    struct FrontendReference {
+        config: ReferenceConfig,
+        provenance: ReferenceProvenance,
+        window: Vec<f32>,
+        /// Non-zero span of every mel band: first FFT bin and its weights.
+        filterbank: Vec<(usize, Vec<f32>)>,
+        /// Log-mel features per fixture, `[n_mels][frames]`.
+        features: std::collections::BTreeMap<String, Vec<Vec<f32>>>,
+    }
+
+    #[derive(Deserialize)]
+    struct ReferenceConfig {
+        model_name: String,
+        sample_rate: usize,
+        n_mels: usize,
+        win_length: usize,
+        hop_length: usize,
+        n_fft: usize,
+        quantize_bf16: bool,
+        #[serde(default)]
+        center: bool,
+        pad_mode: Option<String>,
+        mel_scale: Option<String>,
+        mel_norm: Option<String>,
+        f_min: Option<f64>,
+        f_max: Option<f64>,
+        preemph: Option<f32>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct ReferenceProvenance {
+        torch: String,
+        torchaudio: String,
+        /// Set for the quantized v3 reference, whose buffers come from the checkpoint.
+        checkpoint: Option<String>,
+        checkpoint_sha256: Option<String>,
+    }
+
+    impl ReferenceConfig {
+        fn frontend_config(&self) -> GigaamConfig {
+            let defaults = GigaamConfig::default();
+            GigaamConfig {
+                sample_rate: self.sample_rate,
+                n_mels: self.n_mels,
+                win_length: self.win_length,
+                hop_length: self.hop_length,
+                n_fft: self.n_fft,
+                center: self.center,
+                pad_mode: self.pad_mode.clone().unwrap_or(defaults.pad_mode),
+                mel_scale: self.mel_scale.clone().unwrap_or(defaults.mel_scale),
+                mel_norm: self.mel_norm.clone(),
+                f_min: self.f_min.unwrap_or(defaults.f_min),
+                f_max: self.f_max,
+                preemph: self.preemph.unwrap_or(defaults.preemph),
+                quantize_bf16: self.quantize_bf16,
+                ..defaults
+            }
+        }
+    }
+
+    /// The frontends `generate_references.py` covers: the shipped quantized v3 one, an
+    /// unquantized one, the centered GigaAM v2 one and a NeMo-style one with every
+    /// optional key set.
+    fn reference_configs() -> [(&'static str, GigaamConfig); 4] {
+        let v2 = GigaamConfig {
+            win_length: 400,
+            n_fft: 400,
+            ..GigaamConfig::default()
+        };
+        [
+            (
+                "v3_e2e_ctc",
+                GigaamConfig {
+                    quantize_bf16: true,
+                    ..GigaamConfig::default()
+                },
+            ),
+            ("unquantized", v2.clone()),
+            ("v2_centered", GigaamConfig { center: true, ..v2 }),
+            (
+                "nemo_slaney",
+                GigaamConfig {
+                    n_mels: 80,
+                    win_length: 400,
+                    n_fft: 512,
+                    center: true,
+                    pad_mode: "constant".to_string(),
+                    mel_scale: "slaney".to_string(),
+                    mel_norm: Some("slaney".to_string()),
+                    f_min: 20.0,
+                    f_max: Some(7_600.0),
+                    preemph: 0.97,
+                    ..GigaamConfig::default()
+                },
+            ),
+        ]
+    }
+
+    fn frontend_references() -> Vec<FrontendReference> {
+        reference_configs()
+            .into_iter()
+            .map(|(name, config)| {
+                let path = frontend_testdata().join(format!("{name}.json"));
+                let content = fs::read_to_string(&path).unwrap_or_else(|error| {
+                    panic!(
+                        "{}: {error}; run testdata/frontend/generate_references.py",
+                        path.display()
+                    )
+                });
+                let reference: FrontendReference = serde_json::from_str(&content).unwrap();
+                let provenance = &reference.provenance;
+                assert!(
+                    !provenance.torch.is_empty() && !provenance.torchaudio.is_empty(),
+                    "{name}: no torch/torchaudio versions"
+                );
+                if config.quantize_bf16 {
+                    assert!(
+                        provenance.checkpoint.is_some()
+                            && provenance
+                                .checkpoint_sha256
+                                .as_ref()
+                                .is_some_and(|sha| sha.len() == 64),
+                        "{name}: no checkpoint name and SHA-256"
+                    );
+                }
+                assert_eq!(reference.config.frontend_config(), config, "{name}: config");
+                reference
+            })
+            .collect()
+    }
+
+    fn frontend_testdata() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/frontend")
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32, what: &str) {
+        assert_eq!(actual.len(), expected.len(), "{what}: length");
+        for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+            assert!(
+                (actual - expected).abs() <= tolerance,
+                "{what}[{index}]: {actual} differs from the reference {expected}"
+            );
+        }
+    }
+
+    // Known deviations from torch. torch builds a periodic Hann window, rounds the v3
+    // buffers to bfloat16 to nearest even and computes the mel filter edges in f64; the
+    // frontend keeps its symmetric window, bfloat16 truncation and f32 edges until the
+    // checkpoint buffers and a WER/CER run show which the models need. The helpers
+    // below rebuild torch's buffers, so the references check the rest of the frontend.
+
+    fn torch_round_to_bf16(value: f32) -> f32 {
+        let bits = value.to_bits();
+        let rounded = bits.wrapping_add(0x7FFF + ((bits >> 16) & 1));
+        f32::from_bits(rounded & 0xFFFF_0000)
+    }
+
+    fn torch_hann_window(win_length: usize, quantize_bf16: bool) -> Vec<f32> {
+        (0..win_length)
+            .map(|n| {
+                let value = 0.5 - 0.5 * (2.0 * PI * n as f32 / win_length as f32).cos();
+                if quantize_bf16 {
+                    torch_round_to_bf16(value)
+                } else {
+                    value
+                }
+            })
+            .collect()
+    }
+
+    fn torch_hz_to_mel(scale: MelScale, hz: f64) -> f64 {
+        match scale {
+            MelScale::Htk => 2595.0 * (1.0 + hz / 700.0).log10(),
+            MelScale::Slaney if hz < 1_000.0 => hz * 3.0 / 200.0,
+            MelScale::Slaney => 15.0 + (hz / 1_000.0).ln() * 27.0 / 6.4_f64.ln(),
+        }
+    }
+
+    fn torch_mel_to_hz(scale: MelScale, mel: f64) -> f64 {
+        match scale {
+            MelScale::Htk => 700.0 * (10_f64.powf(mel / 2595.0) - 1.0),
+            MelScale::Slaney if mel < 15.0 => mel * 200.0 / 3.0,
+            MelScale::Slaney => 1_000.0 * ((mel - 15.0) * 6.4_f64.ln() / 27.0).exp(),
+        }
+    }
+
+    /// `[n_mels][n_freq_bins]` filter weights, dense.
+    fn torch_mel_filterbank(spec: &MelSpec, quantize_bf16: bool) -> Vec<Vec<f32>> {
+        let mel_min = torch_hz_to_mel(spec.scale, spec.f_min);
+        let mel_max = torch_hz_to_mel(spec.scale, spec.f_max);
+        let hz_points = (0..spec.n_mels + 2)
+            .map(|i| {
+                let mel = mel_min + (mel_max - mel_min) * (i as f64 / (spec.n_mels + 1) as f64);
+                torch_mel_to_hz(spec.scale, mel)
+            })
+            .collect::<Vec<_>>();
+        (0..spec.n_mels)
+            .map(|mel_idx| {
+                let [left, center, right] = [0, 1, 2].map(|offset| hz_points[mel_idx + offset]);
+                let area_scale = if spec.slaney_norm {
+                    2.0 / (right - left)
+                } else {
+                    1.0
+                };
+                (0..spec.n_fft / 2 + 1)
+                    .map(|bin| {
+                        let freq = bin as f64 * spec.sample_rate as f64 / spec.n_fft as f64;
+                        let triangle = ((freq - left) / (center - left))
+                            .min((right - freq) / (right - center))
+                            .max(0.0);
+                        let weight = (triangle * area_scale) as f32;
+                        if quantize_bf16 {
+                            torch_round_to_bf16(weight)
+                        } else {
+                            weight
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn dense_bands(bands: &[MelBand], n_freq_bins: usize) -> Vec<Vec<f32>> {
+        bands
+            .iter()
+            .map(|band| {
+                let mut weights = vec![0.0_f32; n_freq_bins];
+                weights[band.start_bin..band.start_bin + band.weights.len()]
+                    .copy_from_slice(&band.weights);
+                weights
+            })
+            .collect()
+    }
+
+    fn sparse_bands(filterbank: Vec<Vec<f32>>) -> Vec<MelBand> {
+        filterbank
+            .into_iter()
+            .map(|weights| {
+                let start_bin = weights.iter().position(|&weight| weight > 0.0).unwrap_or(0);
+                let end_bin = weights
+                    .iter()
+                    .rposition(|&weight| weight > 0.0)
+                    .map_or(start_bin, |bin| bin + 1);
+                MelBand {
+                    start_bin,
+                    weights: weights[start_bin..end_bin].to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    /// Largest window difference the known deviations allow: a symmetric window is off
+    /// the periodic one by under `pi / (win_length - 1)`, and bfloat16 truncation and
+    /// rounding by one bfloat16 step below 1.
+    fn window_deviation_bound(win_length: usize) -> f32 {
+        PI / (win_length as f32 - 1.0) + 2.0_f32.powi(-8)
+    }
+
+    /// Largest filter weight difference the known deviations allow: f32 edges within
+    /// 1e-5, plus two bfloat16 steps of the weight when quantized.
+    fn weight_deviation_bound(weight: f32, quantize_bf16: bool) -> f32 {
+        let bf16_steps = if quantize_bf16 {
+            2.0 * 2.0_f32.powi(-7) * weight.abs()
+        } else {
+            0.0
+        };
+        1e-5 + bf16_steps
+    }
+
+    #[test]
+    fn frontend_known_deviations_from_torch_stay_bounded() -> Result<()> {
+        for value in [1.0_f32, 0.3, 0.999_99, 1.0e-3, 0.5 + 2.0_f32.powi(-9)] {
+            let truncated = quantize_to_bf16(value);
+            let rounded = torch_round_to_bf16(value);
+            assert!(truncated <= value && value - truncated < value * 2.0_f32.powi(-7));
+            assert!((rounded - value).abs() <= value * 2.0_f32.powi(-8));
+        }
+
+        for (name, config) in reference_configs() {
+            let frontend = GigaamFrontend::from_config(&config)?;
+            let torch_window = torch_hann_window(config.win_length, config.quantize_bf16);
+            assert_ne!(frontend.hann_window, torch_window, "{name}: window");
+            let bound = window_deviation_bound(config.win_length);
+            for (native, torch) in frontend.hann_window.iter().zip(&torch_window) {
+                assert!(
+                    (native - torch).abs() <= bound,
+                    "{name}: {native} vs {torch}"
+                );
+            }
+
+            let spec = MelSpec::from_config(&config)?;
+            let n_freq_bins = config.n_fft / 2 + 1;
+            let native = dense_bands(&frontend.mel_bands, n_freq_bins);
+            let torch = torch_mel_filterbank(&spec, config.quantize_bf16);
+            for (mel_idx, (native, torch)) in native.iter().zip(&torch).enumerate() {
+                for (bin, (native, torch)) in native.iter().zip(torch).enumerate() {
+                    assert!(
+                        (native - torch).abs()
+                            <= weight_deviation_bound(*torch, config.quantize_bf16),
+                        "{name} band {mel_idx} bin {bin}: {native} vs {torch}"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "Requires references from testdata/frontend/generate_references.py"]
+    fn hann_window_matches_reference() {
+        for reference in frontend_references() {
+            let config = &reference.config;
+            let torch_window = torch_hann_window(config.win_length, config.quantize_bf16);
+            assert_close(&torch_window, &reference.window, 1e-6, &config.model_name);
+
+            // Known deviation: the native window is symmetric and truncated.
+            let window = build_hann_window(config.win_length, config.quantize_bf16);
+            let bound = window_deviation_bound(config.win_length);
+            assert_close(&window, &reference.window, bound, &config.model_name);
+            let frontend = GigaamFrontend::from_config(&config.frontend_config()).unwrap();
+            assert_eq!(
+                frontend.hann_window, window,
+                "{}: quantization",
+                config.model_name
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "Requires references from testdata/frontend/generate_references.py"]
+    fn mel_filterbank_matches_reference() {
+        for reference in frontend_references() {
+            let config = &reference.config;
+            let spec = MelSpec::from_config(&config.frontend_config()).unwrap();
+            let torch = sparse_bands(torch_mel_filterbank(&spec, config.quantize_bf16));
+            assert_eq!(torch.len(), reference.filterbank.len());
+            let n_freq_bins = config.n_fft / 2 + 1;
+            let mut expected = Vec::with_capacity(reference.filterbank.len());
+            for (mel_idx, (band, (first_bin, weights))) in
+                torch.iter().zip(&reference.filterbank).enumerate()
+            {
+                let what = format!("{} band {mel_idx}", config.model_name);
+                assert_eq!(band.start_bin, *first_bin, "{what}: first bin");
+                assert_close(&band.weights, weights, 1e-6, &what);
+                expected.push(MelBand {
+                    start_bin: *first_bin,
+                    weights: weights.clone(),
+                });
+            }
+
+            // Known deviation: f32 filter edges, and truncation when quantized.
+            let native = build_mel_filterbank(&spec, config.quantize_bf16).unwrap();
+            let native = dense_bands(&native, n_freq_bins);
+            let expected = dense_bands(&expected, n_freq_bins);
+            for (mel_idx, (native, expected)) in native.iter().zip(&expected).enumerate() {
+                for (bin, (native, expected)) in native.iter().zip(expected).enumerate() {
+                    assert!(
+                        (native - expected).abs()
+                            <= weight_deviation_bound(*expected, config.quantize_bf16),
+                        "{} band {mel_idx} bin {bin}: {native} vs {expected}",
+                        config.model_name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "Requires references from testdata/frontend/generate_references.py"]
+    fn extract_features_matches_reference_log_mels() -> Result<()> {
+        for reference in frontend_references() {
+            let config = &reference.config;
+            // The rest of the pipeline is checked with torch's buffers; the known
+            // deviations are covered by the window and filterbank tests.
+            let mut frontend = GigaamFrontend::from_config(&config.frontend_config())?;
+            let spec = MelSpec::from_config(&config.frontend_config())?;
+            frontend.hann_window = torch_hann_window(config.win_length, config.quantize_bf16);
+            frontend.mel_bands = sparse_bands(torch_mel_filterbank(&spec, config.quantize_bf16));
+            for (fixture, expected) in &reference.features {
+                let samples = read_wav_mono_f32(&frontend_testdata().join(fixture))?;
+                let (features, frame_count) = frontend.extract_features(&samples)?;
+                assert_eq!(features.dim(), (1, config.n_mels, expected[0].len()));
+                assert_eq!(frame_count as usize, expected[0].len());
+                for (mel_idx, expected) in expected.iter().enumerate() {
+                    let what = format!("{} {fixture} band {mel_idx}", config.model_name);
+                    let actual = features.slice(ndarray::s![0, mel_idx, ..]).to_vec();
+                    // f32 FFT rounding stays below 2e-3 even in the quietest bands, which
+                    // preemphasis pushes under e^-15.
+                    assert_close(&actual, expected, 2e-3, &what);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Log-mel features the way the frontend computed them before the fast path: a full
+    /// complex FFT per frame and a dense filterbank multiply. The window sits inside the
+    /// `n_fft` frame as `torch.stft` places it.
+    fn dense_complex_fft_features(frontend: &GigaamFrontend, samples: &[f32]) -> Vec<f32> {
+        let n_freq_bins = frontend.n_fft / 2 + 1;
+        let mut filterbank = vec![0.0_f32; n_freq_bins * frontend.n_mels];
+        for (mel_idx, band) in frontend.mel_bands.iter().enumerate() {
+            for (offset, weight) in band.weights.iter().enumerate() {
+                filterbank[(band.start_bin + offset) * frontend.n_mels + mel_idx] = *weight;
+            }
+        }
+        let fft = rustfft::FftPlanner::<f32>::new().plan_fft_forward(frontend.n_fft);
+
+        let frame_count = frontend.frame_count(samples.len());
+        let mut features = vec![0.0_f32; frontend.n_mels * frame_count];
+        let mut fft_buffer = vec![rustfft::num_complex::Complex32::default(); frontend.n_fft];
+        let mut power_spectrum = vec![0.0_f32; n_freq_bins];
+        for frame_idx in 0..frame_count {
+            let start = frame_idx * frontend.hop_length;
+            for (i, value) in fft_buffer.iter_mut().enumerate() {
+                let sample = match i.checked_sub(frontend.window_offset) {
+                    Some(n) if n < frontend.win_length => {
+                        samples[start + i] * frontend.hann_window[n]
+                    }
+                    _ => 0.0,
+                };
+                *value = rustfft::num_complex::Complex32::new(sample, 0.0);
+            }
+            fft.process(&mut fft_buffer);
+            for (power, complex) in power_spectrum.iter_mut().zip(&fft_buffer) {
+                *power = complex.re.mul_add(complex.re, complex.im * complex.im);
+            }
+            for mel_idx in 0..frontend.n_mels {
+                let mut mel_energy = 0.0_f32;
+                for (bin_idx, &power) in power_spectrum.iter().enumerate() {
+                    mel_energy += power * filterbank[bin_idx * frontend.n_mels + mel_idx];
+                }
+                let clamped = mel_energy.clamp(MEL_MIN_CLAMP, MEL_MAX_CLAMP);
+                features[mel_idx * frame_count + frame_idx] = clamped.ln();
+            }
+        }
+        features
+    }
+
+    /// Tones over noise from a fixed-seed generator.
+    fn synthetic_speech(seconds: usize) -> Vec<f32> {
+        let mut state = 12_345_u32;
+        (0..seconds * 16_000)
+            .map(|i| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+                let t = i as f32 / 16_000.0;
+                let pitch = 120.0 + 40.0 * (2.0 * PI * 0.5 * t).sin();
+                0.3 * (2.0 * PI * pitch * t).sin()
+                    + 0.1 * (2.0 * PI * 2_300.0 * t).sin()
+                    + 0.05 * noise
+            })
+            .collect()
+    }
+
+    fn frontend_configs() -> [(&'static str, GigaamConfig); 3] {
+        let odd_fft = GigaamConfig {
+            win_length: 399,
+            n_fft: 401,
+            ..GigaamConfig::default()
+        };
+        [
+            ("default", GigaamConfig::default()),
+            ("quantized", reference_configs()[0].1.clone()),
+            ("odd", odd_fft),
+        ]
+    }
+
+    #[test]
+    fn fast_feature_path_matches_dense_complex_fft() -> Result<()> {
+        let samples = synthetic_speech(2);
+        for (name, config) in frontend_configs() {
+            let mut frontend = GigaamFrontend::from_config(&config)?;
+            let expected = dense_complex_fft_features(&frontend, &samples);
+            // The second call runs on the buffers left by the first.
+            for _ in 0..2 {
+                let (features, _) = frontend.extract_features(&samples)?;
+                let actual = features.iter().copied().collect::<Vec<_>>();
+                // Only the FFT differs; its rounding moves log-mels by about 1e-5, and by
+                // up to 3e-4 in the quietest bands.
+                assert_close(&actual, &expected, 5e-4, name);
+            }
+            let (short, frame_count) = frontend.extract_features(&samples[..100])?;
+            assert_eq!((short.dim(), frame_count), ((1, config.n_mels, 0), 0));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn optional_yaml_keys_configure_the_frontend() -> Result<()> {
+        let yaml = include_str!("../testdata/v3_e2e_ctc.yaml")
+            .replace("  n_fft: 320\n", "  n_fft: 512\n")
+            .replace("mel_scale: htk", "mel_scale: slaney")
+            .replace("mel_norm: null", "mel_norm: slaney")
+            .replace(
+                "center: false",
+                "center: true\n  pad_mode: constant\n  f_min: 20\n  f_max: 7600\n  preemph: 0.97\n  dither: 1.0e-5",
+            );
+        let config = GigaamConfig::from_yaml(&yaml)?;
+        let frontend = GigaamFrontend::from_config(&config)?;
+        assert_eq!(frontend.window_offset, 96);
+        // The YAML dither is NeMo's training noise and is ignored with a warning.
+        assert_eq!(
+            frontend.conditioner,
+            SignalConditioner {
+                dither: 0.0,
+                preemph: 0.97,
+                center_pad: 256,
+                pad_mode: PadMode::Constant,
+            }
+        );
+        let spec = MelSpec::from_config(&config)?;
+        assert_eq!((spec.f_min, spec.f_max), (20.0, 7600.0));
+        assert_eq!((spec.scale, spec.slaney_norm), (MelScale::Slaney, true));
+
+        let above_nyquist = GigaamConfig {
+            f_max: Some(9_000.0),
+            ..config
+        };
+        let error = GigaamFrontend::from_config(&above_nyquist).err().unwrap();
+        assert!(
+            error.to_string().contains("f_max (9000) <= 8000 Hz"),
+            "{error}"
+        );
+        let unknown_scale = GigaamConfig {
+            mel_scale: "bark".to_string(),
+            ..GigaamConfig::default()
+        };
+        assert!(GigaamFrontend::from_config(&unknown_scale).is_err());
+
+        let out_of_range = [
+            GigaamConfig {
+                preemph: 1.5,
+                ..GigaamConfig::default()
+            },
+            GigaamConfig {
+                dither: -0.1,
+                ..GigaamConfig::default()
+            },
+            GigaamConfig {
+                f_min: 4_000.0,
+                f_max: Some(2_000.0),
+                ..GigaamConfig::default()
+            },
+        ];
+        for config in out_of_range {
+            assert!(GigaamFrontend::from_config(&config).is_err(), "{config:?}");
+        }
+
+        // Seconds-based NeMo window keys become samples.
+        let nemo = include_str!("../testdata/nemo_conformer_ctc.yaml")
+            .replace("normalize: per_feature", "normalize: NA");
+        let config = GigaamConfig::from_yaml(&nemo)?;
+        assert_eq!((config.win_length, config.hop_length), (400, 160));
+        let frontend = GigaamFrontend::from_config(&config)?;
+        assert_eq!((frontend.n_mels, frontend.window_offset), (80, 56));
+
+        // Manifest preprocessing overrides the YAML config and is checked the same way.
+        let mut manifest = ModelManifest::builtin("gigaam-v3-e2e-ctc").unwrap();
+        manifest.preprocessing = Preprocessing {
+            center: Some(true),
+            pad_mode: Some("constant".to_string()),
+            f_min: Some(20.0),
+            preemph: Some(0.97),
+            ..Preprocessing::default()
+        };
+        let mut config = GigaamConfig::default();
+        config.apply_manifest(&manifest);
+        let frontend = GigaamFrontend::from_config(&config)?;
+        assert_eq!(frontend.conditioner.pad_mode, PadMode::Constant);
+        assert_eq!(frontend.conditioner.preemph, 0.97);
+        assert_eq!(MelSpec::from_config(&config)?.f_min, 20.0);
+        // Without `quantize_bf16` only v3 configs and the uncentered 320-point STFT of the
+        // v3 checkpoints quantize.
+        assert!(!config.quantize_bf16);
+        manifest.preprocessing.quantize_bf16 = Some(true);
+        config.apply_manifest(&manifest);
+        assert!(config.quantize_bf16);
+        let mut unstated = ModelManifest::builtin("gigaam-v3-e2e-ctc").unwrap();
+        unstated.preprocessing = Preprocessing::default();
+        let mut uncentered = GigaamConfig::default();
+        uncentered.apply_manifest(&unstated);
+        assert!(uncentered.quantize_bf16);
+        let v3 = GigaamConfig::from_yaml(include_str!("../testdata/v3_e2e_ctc.yaml"))?;
+        assert!(v3.quantize_bf16);
+        let mut unquantized = v3.clone();
+        manifest.preprocessing = Preprocessing {
+            quantize_bf16: Some(false),
+            ..Preprocessing::default()
+        };
+        unquantized.apply_manifest(&manifest);
+        assert!(!unquantized.quantize_bf16);
+        manifest.preprocessing.quantize_bf16 = None;
+        manifest.preprocessing.dither = Some(-1.0);
+        config.apply_manifest(&manifest);
+        assert!(GigaamFrontend::from_config(&config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn streamed_features_match_the_whole_recording() -> Result<()> {
+        let samples = synthetic_speech(1);
+        let [_, _, (_, centered), (_, nemo)] = reference_configs();
+        let dithered = GigaamConfig {
+            dither: 1e-4,
+            ..nemo
+        };
+        for (name, config) in [
+            ("default", GigaamConfig::default()),
+            ("centered", centered),
+            ("dithered", dithered),
+        ] {
+            let mut frontend = GigaamFrontend::from_config(&config)?;
+            let (whole, frame_count) = frontend.extract_features(&samples)?;
+            assert_eq!(
+                frontend.feature_frame_count(samples.len()) as i64,
+                frame_count
+            );
+
+            let mut signal_carry = SignalCarry::default();
+            let mut sample_carry =
+                crate::streaming::SampleCarry::new(frontend.frame_length(), frontend.hop_length());
+            let mut frames = Vec::new();
+            let mut chunks = samples.chunks(1_234).peekable();
+            while let Some(chunk) = chunks.next() {
+                let flush = chunks.peek().is_none();
+                let signal = frontend.condition_stream(&mut signal_carry, chunk, flush);
+                if let Some(ready) = sample_carry.push(&signal) {
+                    let (features, _) = frontend.extract_conditioned_features(&ready)?;
+                    frames.push(features);
+                }
+            }
+            let views = frames
+                .iter()
+                .map(|features| features.view())
+                .collect::<Vec<_>>();
+            let streamed = ndarray::concatenate(ndarray::Axis(2), &views)?;
+            assert_eq!(streamed.dim().2 as i64, frame_count, "{name}");
+            assert_eq!(streamed, whole, "{name}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "Benchmark; run with cargo test --release -- --ignored --nocapture"]
+    fn benchmark_feature_extraction_on_30s() -> Result<()> {
+        let samples = synthetic_speech(30);
+        for (name, config) in frontend_configs() {
+            let mut frontend = GigaamFrontend::from_config(&config)?;
+            let runs = 10;
+            let dense_start = Instant::now();
+            for _ in 0..runs {
+                std::hint::black_box(dense_complex_fft_features(&frontend, &samples));
+            }
+            let dense = dense_start.elapsed() / runs;
+            let fast_start = Instant::now();
+            for _ in 0..runs {
+                std::hint::black_box(frontend.extract_features(&samples)?);
+            }
+            let fast = fast_start.elapsed() / runs;
+            println!(
+                "{name} (n_fft {}): dense complex FFT {dense:?}, real FFT + sparse mel {fast:?}, {:.1}x",
+                config.n_fft,
+                dense.as_secs_f64() / fast.as_secs_f64()
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     #[ignore = "Requires local model files and a WAV fixture; set GIGAAM_TEST_MODEL_DIR and GIGAAM_TEST_WAV_PATH"]
     fn integration_transcribes_wav_fixture() -> Result<()> {
@@ -901,7 +3029,9 @@ mod tests {
         let expected_substring = std::env::var("GIGAAM_TEST_EXPECT_CONTAINS").unwrap_or_default();
 
         let mut engine = GigaamEngine::new();
-        engine.load_model(Path::new(&model_dir), RuntimeOptions::default())?;
+        let model_dir = Path::new(&model_dir);
+        let manifest = ModelManifest::from_directory(model_dir)?;
+        engine.load_model(model_dir, &manifest, &RuntimeOptions::default(), None)?;
 
         let samples = read_wav_mono_f32(Path::new(&wav_path))?;
         let report = engine.transcribe_samples(&samples)?;
@@ -0,0 +1,382 @@
+use crate::decoder::PrefixScorer;
+use std::collections::HashMap;
+
+const DEFAULT_HOTWORD_WEIGHT: f32 = 1.5;
+
+/// A boost phrase as supplied by the host application.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotwordPhrase {
+    pub text: String,
+    /// Bonus in nats added for every token of the phrase.
+    pub weight: f32,
+}
+
+impl HotwordPhrase {
+    pub fn new(text: &str, weight: f32) -> Self {
+        Self {
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+            weight: if weight.is_finite() && weight > 0.0 {
+                weight
+            } else {
+                DEFAULT_HOTWORD_WEIGHT
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<usize, usize>,
+    // Largest weight of any phrase passing through this node.
+    weight: f32,
+    // Weight of the phrase ending here, if any.
+    terminal_weight: Option<f32>,
+}
+
+/// Open phrase match of one prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HotwordState {
+    /// Trie node the match has reached.
+    node: usize,
+    /// Length in tokens of the match; 0 when no phrase is open.
+    depth: usize,
+    /// Bonus of the longest phrase the match has already completed, for when a longer
+    /// one fails further on.
+    completed: f32,
+}
+
+/// Token-level prefix trie over the model vocabulary, used to bias beam search
+/// towards the configured phrases.
+#[derive(Debug)]
+pub struct HotwordTrie {
+    nodes: Vec<TrieNode>,
+    word_start: Vec<bool>,
+    // Tokens that end the word before them: word-initial ones and punctuation.
+    word_break: Vec<bool>,
+    phrase_count: usize,
+}
+
+impl HotwordTrie {
+    /// Tokenizes every phrase with a longest-match over `vocab`. Phrases that cannot be
+    /// spelled with the vocabulary are skipped.
+    pub fn build(phrases: &[HotwordPhrase], vocab: &[String]) -> Self {
+        let word_start = vocab
+            .iter()
+            .map(|token| token.starts_with(' '))
+            .collect::<Vec<_>>();
+        let word_break = vocab
+            .iter()
+            .zip(&word_start)
+            .map(|(token, &starts_word)| starts_word || !token.chars().any(char::is_alphanumeric))
+            .collect();
+        let mut trie = Self {
+            nodes: vec![TrieNode::default()],
+            word_start,
+            word_break,
+            phrase_count: 0,
+        };
+        let tokenizer = VocabTokenizer::new(vocab);
+
+        for phrase in phrases {
+            if phrase.text.is_empty() {
+                continue;
+            }
+            let Some(token_ids) = tokenizer.tokenize(&format!(" {}", phrase.text)) else {
+                log::warn!(
+                    "GigaAM hotword '{}' cannot be spelled with the vocabulary",
+                    phrase.text
+                );
+                continue;
+            };
+            trie.insert(&token_ids, phrase.weight);
+        }
+        trie
+    }
+
+    pub fn phrase_count(&self) -> usize {
+        self.phrase_count
+    }
+
+    fn insert(&mut self, token_ids: &[usize], weight: f32) {
+        let mut node = 0;
+        for &token in token_ids {
+            node = match self.nodes[node].children.get(&token) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(token, child);
+                    child
+                }
+            };
+            self.nodes[node].weight = self.nodes[node].weight.max(weight);
+        }
+        let terminal = &mut self.nodes[node].terminal_weight;
+        *terminal = Some(terminal.map_or(weight, |existing| existing.max(weight)));
+        self.phrase_count += 1;
+    }
+
+    fn starts_word(&self, token: usize) -> bool {
+        self.word_start.get(token).copied().unwrap_or(false)
+    }
+
+    fn breaks_word(&self, token: usize) -> bool {
+        self.word_break.get(token).copied().unwrap_or(false)
+    }
+
+    /// Bonus of the phrase ending at the open match, if any, given that `token` follows.
+    /// A phrase only counts when the next token ends its word, so "Ivan" rewards
+    /// "Ivan," but not "Ivanov".
+    fn completed_by(&self, state: &HotwordState, token: usize) -> Option<f32> {
+        let weight = self.nodes[state.node].terminal_weight?;
+        self.breaks_word(token)
+            .then_some(weight * state.depth as f32)
+    }
+
+    /// Advances the open match in `state` by one token. Returns the bonus of a phrase
+    /// completed by this token together with the new state.
+    fn step(&self, state: &HotwordState, token: usize) -> (f32, HotwordState) {
+        let mut completed = 0.0;
+        if state.depth > 0 {
+            // The deepest phrase passed on the way is credited if a longer one fails.
+            let passed = self.completed_by(state, token).unwrap_or(state.completed);
+            if let Some(&child) = self.nodes[state.node].children.get(&token) {
+                return (
+                    0.0,
+                    HotwordState {
+                        node: child,
+                        depth: state.depth + 1,
+                        completed: passed,
+                    },
+                );
+            }
+            completed = passed;
+        }
+        let next = match self.nodes[0].children.get(&token) {
+            Some(&child) if self.starts_word(token) => HotwordState {
+                node: child,
+                depth: 1,
+                completed: 0.0,
+            },
+            _ => HotwordState::default(),
+        };
+        (completed, next)
+    }
+
+    /// Bonus provisionally granted to the open match of `state`.
+    fn provisional(&self, state: &HotwordState) -> f32 {
+        self.nodes[state.node].weight * state.depth as f32
+    }
+}
+
+impl PrefixScorer for HotwordTrie {
+    type State = HotwordState;
+
+    fn initial_state(&self) -> HotwordState {
+        HotwordState::default()
+    }
+
+    fn extension_score(&self, state: &HotwordState, token: usize) -> (f32, HotwordState) {
+        if self.phrase_count == 0 {
+            return (0.0, HotwordState::default());
+        }
+        let (completed, next) = self.step(state, token);
+        (
+            completed + self.provisional(&next) - self.provisional(state),
+            next,
+        )
+    }
+
+    fn final_score(&self, state: &HotwordState) -> f32 {
+        if self.phrase_count == 0 {
+            return 0.0;
+        }
+        // Settle the open match: a finished phrase, or the deepest one passed on the
+        // way, keeps its bonus; anything else gives back what it was provisionally
+        // granted.
+        let earned = self.nodes[state.node]
+            .terminal_weight
+            .map_or(state.completed, |weight| weight * state.depth as f32);
+        earned - self.provisional(state)
+    }
+}
+
+struct VocabTokenizer<'a> {
+    token_ids: HashMap<&'a str, usize>,
+    max_token_chars: usize,
+}
+
+impl<'a> VocabTokenizer<'a> {
+    fn new(vocab: &'a [String]) -> Self {
+        let token_ids = vocab
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| {
+                let special = token.starts_with('<') && token.ends_with('>');
+                !token.is_empty() && !special
+            })
+            .map(|(id, token)| (token.as_str(), id))
+            .collect::<HashMap<_, _>>();
+        let max_token_chars = token_ids
+            .keys()
+            .map(|token| token.chars().count())
+            .max()
+            .unwrap_or(0);
+        Self {
+            token_ids,
+            max_token_chars,
+        }
+    }
+
+    fn tokenize(&self, text: &str) -> Option<Vec<usize>> {
+        let chars = text.char_indices().map(|(idx, _)| idx).collect::<Vec<_>>();
+        let mut token_ids = Vec::new();
+        let mut pos = 0;
+        while pos < chars.len() {
+            let start = chars[pos];
+            let longest = (1..=self.max_token_chars.min(chars.len() - pos))
+                .rev()
+                .find_map(|len| {
+                    let end = chars.get(pos + len).copied().unwrap_or(text.len());
+                    self.token_ids.get(&text[start..end]).map(|&id| (id, len))
+                })?;
+            token_ids.push(longest.0);
+            pos += longest.1;
+        }
+        Some(token_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{ctc_prefix_beam_search, token_ids, DecodeOptions, DecodeStrategy};
+    use anyhow::Result;
+    use ndarray::Array3;
+
+    fn test_vocab() -> Vec<String> {
+        [
+            " iv", "an", " ivan", "ov", " pe", "tr", "<blk>", " ida", ",", ".",
+        ]
+        .iter()
+        .map(|token| token.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn phrases_are_tokenized_with_longest_vocab_match() {
+        let vocab = test_vocab();
+        let tokenizer = VocabTokenizer::new(&vocab);
+
+        assert_eq!(tokenizer.tokenize(" ivan petr"), Some(vec![2, 4, 5]));
+        assert_eq!(tokenizer.tokenize(" ivanov"), Some(vec![2, 3]));
+        assert_eq!(tokenizer.tokenize(" zed"), None);
+    }
+
+    #[test]
+    fn bonus_is_kept_only_for_completed_phrases() {
+        let vocab = test_vocab();
+        let trie = HotwordTrie::build(&[HotwordPhrase::new("ivan  petr", 2.0)], &vocab);
+        assert_eq!(trie.phrase_count(), 1);
+
+        assert_eq!(decoded_bonus(&trie, &[2, 4, 5]), 6.0);
+        assert_eq!(running_bonus(&trie, &[2, 4]), 4.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 4]), 0.0);
+
+        // "ivan" followed by a non-word-initial token is a different word.
+        let trie = HotwordTrie::build(&[HotwordPhrase::new("ivan", 2.0)], &vocab);
+        assert_eq!(decoded_bonus(&trie, &[2, 3]), 0.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 7]), 2.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 7, 2]), 4.0);
+    }
+
+    #[test]
+    fn punctuation_ends_a_phrase() {
+        let vocab = test_vocab();
+        let trie = HotwordTrie::build(&[HotwordPhrase::new("ivan", 2.0)], &vocab);
+        // "ivan," keeps its bonus, as does "ivan." before the next word.
+        assert_eq!(running_bonus(&trie, &[2, 8]), 2.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 8]), 2.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 9, 2]), 4.0);
+        // Punctuation does not start a phrase.
+        assert_eq!(decoded_bonus(&trie, &[8, 2]), 2.0);
+    }
+
+    #[test]
+    fn shorter_phrase_keeps_its_bonus_when_a_longer_one_fails() {
+        let vocab = test_vocab();
+        let trie = HotwordTrie::build(
+            &[
+                HotwordPhrase::new("ivan", 2.0),
+                HotwordPhrase::new("ivan petr", 1.0),
+            ],
+            &vocab,
+        );
+        // "ivan pe" is not "ivan petr", but it still contains "ivan".
+        assert_eq!(decoded_bonus(&trie, &[2, 4, 7]), 2.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 4]), 2.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 4, 5]), 3.0);
+        assert_eq!(decoded_bonus(&trie, &[2, 4, 5, 8]), 3.0);
+
+        // "ivanov" passes the end of "ivan" mid-word and earns nothing.
+        let trie = HotwordTrie::build(
+            &[
+                HotwordPhrase::new("ivan", 2.0),
+                HotwordPhrase::new("ivanov petr", 1.0),
+            ],
+            &vocab,
+        );
+        assert_eq!(decoded_bonus(&trie, &[2, 3, 7]), 0.0);
+    }
+
+    fn walk(trie: &HotwordTrie, tokens: &[usize]) -> (f32, HotwordState) {
+        let mut state = trie.initial_state();
+        let mut bonus = 0.0;
+        for &token in tokens {
+            let (score, next) = trie.extension_score(&state, token);
+            bonus += score;
+            state = next;
+        }
+        (bonus, state)
+    }
+
+    fn running_bonus(trie: &HotwordTrie, tokens: &[usize]) -> f32 {
+        walk(trie, tokens).0
+    }
+
+    fn decoded_bonus(trie: &HotwordTrie, tokens: &[usize]) -> f32 {
+        let (bonus, state) = walk(trie, tokens);
+        bonus + trie.final_score(&state)
+    }
+
+    #[test]
+    fn beam_search_favors_boosted_phrase() -> Result<()> {
+        let vocab = test_vocab();
+        // " ida" is acoustically slightly ahead of " ivan".
+        let mut frame = vec![-5.0_f32; vocab.len()];
+        frame[2] = 1.0;
+        frame[7] = 1.3;
+        let logits = Array3::from_shape_vec((1, 1, vocab.len()), frame)?;
+        let options = DecodeOptions {
+            strategy: DecodeStrategy::BeamSearch,
+            ..DecodeOptions::default()
+        };
+
+        assert_eq!(
+            token_ids(&ctc_prefix_beam_search(logits.view(), 1, 6, &options, &[])),
+            vec![7]
+        );
+        let trie = HotwordTrie::build(&[HotwordPhrase::new("ivan", 1.0)], &vocab);
+        assert_eq!(
+            token_ids(&ctc_prefix_beam_search(
+                logits.view(),
+                1,
+                6,
+                &options,
+                &[&trie]
+            )),
+            vec![2]
+        );
+        Ok(())
+    }
+}
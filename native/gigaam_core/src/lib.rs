@@ -1,27 +1,79 @@
+mod alternatives;
+mod batch;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod confidence;
+mod config;
+mod decoder;
+mod error;
+#[cfg(feature = "cli")]
+pub mod eval;
 mod gigaam;
+mod hotwords;
+mod lm;
+mod longform;
+mod manifest;
+mod metrics;
+mod model_cache;
+mod providers;
+mod resample;
+mod rnnt;
+mod signal;
+mod spectrum;
+mod streaming;
+#[cfg(test)]
+mod test_support;
+mod timestamps;
+mod vad;
 
-use crate::gigaam::{GigaamEngine, RuntimeOptions};
-use jni::objects::{JClass, JShortArray, JString};
-use jni::sys::{jboolean, jint, jstring, JNI_FALSE, JNI_TRUE};
+use crate::batch::BatchOptions;
+use crate::decoder::{DecodeOptions, DecodeStrategy};
+use crate::error::{catch_panic, data_json, result_json, BridgeError, ErrorCode};
+use crate::gigaam::{GigaamEngine, NativeTranscriptionReport, RuntimeOptions};
+use crate::hotwords::HotwordPhrase;
+use crate::longform::LongFormOptions;
+use crate::manifest::{discover, ModelManifest};
+use crate::metrics::TranscriptionMetrics;
+use crate::model_cache::ModelCache;
+use crate::resample::{resample, ResampleQuality, Resampler};
+use crate::streaming::{SessionUpdate, StreamingState};
+use crate::vad::{
+    SpeechDetection, VadMode, VadOptions, VoiceActivityDetector, VAD_SAMPLE_RATE,
+};
+use jni::objects::{JClass, JFloatArray, JObjectArray, JShortArray, JString};
+use jni::sys::{jboolean, jfloat, jint, jlong, jstring, JNI_FALSE, JNI_TRUE};
 use jni::JNIEnv;
 use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::ptr;
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
 use std::time::Instant;
 
-const MODEL_INT8_ID: &str = "gigaam-v3-e2e-ctc-int8";
-const MODEL_FULL_ID: &str = "gigaam-v3-e2e-ctc";
 const TARGET_SAMPLE_RATE: usize = 16_000;
-const VOCAB_FILE: &str = "v3_e2e_ctc_vocab.txt";
-const CONFIG_FILE: &str = "v3_e2e_ctc.yaml";
+const MAX_ALTERNATIVES: jint = 32;
 
 #[derive(Default)]
 struct EngineCache {
     model_key: Option<String>,
     engine: Option<GigaamEngine>,
     runtime_options: RuntimeOptions,
-    last_profile_summary: String,
+    decode_options: DecodeOptions,
+    hotwords: Vec<HotwordPhrase>,
+    vad: Option<VoiceActivityDetector>,
+    long_form: LongFormOptions,
+    batch_options: BatchOptions,
+    resample_quality: ResampleQuality,
+    model_cache: Option<ModelCache>,
+    streams: HashMap<jlong, StreamingSession>,
+    next_stream_id: jlong,
+    last_metrics: Option<TranscriptionMetrics>,
+}
+
+struct StreamingSession {
+    resampler: Resampler,
+    state: StreamingState,
 }
 
 static ENGINE_CACHE: Lazy<Mutex<EngineCache>> = Lazy::new(|| Mutex::new(EngineCache::default()));
@@ -33,10 +85,39 @@ pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBri
     models_root_dir: JString,
     model_id: JString,
 ) -> jboolean {
-    let result = validate_model_from_jni_inputs(&mut env, models_root_dir, model_id).is_ok();
+    let result = catch_panic(|| {
+        validate_model_from_jni_inputs(&mut env, models_root_dir, model_id)
+    })
+    .is_ok();
     if result { JNI_TRUE } else { JNI_FALSE }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeVerifyModel(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    model_id: JString,
+) -> jstring {
+    let result = catch_panic(|| {
+        verify_model_from_jni_inputs(&mut env, models_root_dir, model_id).map(|()| "ok".to_string())
+    });
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeListModels(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+) -> jstring {
+    let result = catch_panic(|| {
+        let models_root = jstring_to_rust(&mut env, models_root_dir)?;
+        Ok::<_, BridgeError>(list_models(Path::new(&models_root)))
+    });
+    data_string(&mut env, result)
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeTranscribe(
     mut env: JNIEnv,
@@ -46,19 +127,124 @@ pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBri
     pcm16: JShortArray,
     sample_rate: jint,
 ) -> jstring {
-    let result = transcribe_from_jni_inputs(
-        &mut env,
-        models_root_dir,
-        model_id,
-        pcm16,
-        sample_rate,
-    );
+    let result = catch_panic(|| {
+        transcribe_from_jni_inputs(
+            &mut env,
+            models_root_dir,
+            model_id,
+            pcm16,
+            sample_rate,
+            0,
+        )
+    });
     match result {
-        Ok(text) => new_java_string(&mut env, text),
+        Ok((report, _)) => new_java_string(&mut env, report.text),
         Err(error) => new_java_string(&mut env, format!("GigaAM error: {error}")),
     }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeTranscribeResult(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    model_id: JString,
+    pcm16: JShortArray,
+    sample_rate: jint,
+) -> jstring {
+    let result = catch_panic(|| {
+        transcribe_from_jni_inputs(
+            &mut env,
+            models_root_dir,
+            model_id,
+            pcm16,
+            sample_rate,
+            0,
+        )
+    });
+    let json = match &result {
+        Ok((report, metrics)) => result_json(Ok((&report.text, Some(metrics)))),
+        Err(error) => result_json(Err(error)),
+    };
+    new_java_string(&mut env, json)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeTranscribeDetailed(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    model_id: JString,
+    pcm16: JShortArray,
+    sample_rate: jint,
+) -> jstring {
+    let result = catch_panic(|| {
+        transcribe_from_jni_inputs(
+            &mut env,
+            models_root_dir,
+            model_id,
+            pcm16,
+            sample_rate,
+            0,
+        )
+    });
+    let detailed = result.as_ref().map(|(report, _)| report.result());
+    new_java_string(&mut env, data_json(detailed))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeTranscribeBatch(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    model_id: JString,
+    utterances: JObjectArray,
+    sample_rate: jint,
+) -> jstring {
+    let result = catch_panic(|| {
+        transcribe_batch_from_jni_inputs(
+            &mut env,
+            models_root_dir,
+            model_id,
+            utterances,
+            sample_rate,
+        )
+    });
+    let results = result.as_ref().map(|reports| {
+        reports
+            .iter()
+            .map(NativeTranscriptionReport::result)
+            .collect::<Vec<_>>()
+    });
+    new_java_string(&mut env, data_json(results))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeTranscribeAlternatives(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    model_id: JString,
+    pcm16: JShortArray,
+    sample_rate: jint,
+    max_alternatives: jint,
+) -> jstring {
+    let result = catch_panic(|| {
+        transcribe_from_jni_inputs(
+            &mut env,
+            models_root_dir,
+            model_id,
+            pcm16,
+            sample_rate,
+            max_alternatives.clamp(1, MAX_ALTERNATIVES) as usize,
+        )
+    });
+    let alternatives = result
+        .as_ref()
+        .map(|(report, _)| report.alternatives_result());
+    new_java_string(&mut env, data_json(alternatives))
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeWarmup(
     mut env: JNIEnv,
@@ -66,11 +252,8 @@ pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBri
     models_root_dir: JString,
     model_id: JString,
 ) -> jstring {
-    let result = warmup_from_jni_inputs(&mut env, models_root_dir, model_id);
-    match result {
-        Ok(message) => new_java_string(&mut env, message),
-        Err(error) => new_java_string(&mut env, format!("error: {error}")),
-    }
+    let result = catch_panic(|| warmup_from_jni_inputs(&mut env, models_root_dir, model_id));
+    status_string(&mut env, result)
 }
 
 #[no_mangle]
@@ -81,11 +264,192 @@ pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBri
     speed_profile: JString,
     accelerator_mode: JString,
 ) -> jstring {
-    let result = set_runtime_options_from_jni_inputs(&mut env, speed_profile, accelerator_mode);
-    match result {
-        Ok(message) => new_java_string(&mut env, message),
-        Err(error) => new_java_string(&mut env, format!("error: {error}")),
-    }
+    let result = catch_panic(|| {
+        set_runtime_options_from_jni_inputs(&mut env, speed_profile, accelerator_mode)
+    });
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetDecodeOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    decode_strategy: JString,
+    beam_width: jint,
+    lm_alpha: jfloat,
+    lm_beta: jfloat,
+) -> jstring {
+    let result = catch_panic(|| {
+        set_decode_options_from_jni_inputs(
+            &mut env,
+            decode_strategy,
+            beam_width,
+            lm_alpha,
+            lm_beta,
+        )
+    });
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetHotwords(
+    mut env: JNIEnv,
+    _class: JClass,
+    phrases: JObjectArray,
+    weights: JFloatArray,
+) -> jstring {
+    let result = catch_panic(|| set_hotwords_from_jni_inputs(&mut env, phrases, weights));
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetVadOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    vad_mode: JString,
+    threshold: jfloat,
+    min_speech_ms: jint,
+    min_silence_ms: jint,
+    speech_pad_ms: jint,
+) -> jstring {
+    let result = catch_panic(|| {
+        set_vad_options_from_jni_inputs(
+            &mut env,
+            models_root_dir,
+            vad_mode,
+            VadOptions::from_values(threshold, min_speech_ms, min_silence_ms, speech_pad_ms),
+        )
+    });
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetLongFormOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    max_segment_ms: jint,
+    overlap_ms: jint,
+) -> jstring {
+    let options = LongFormOptions::from_values(max_segment_ms, overlap_ms);
+    let result = catch_panic(|| {
+        lock_engine_cache().long_form = options;
+        Ok::<_, BridgeError>(format!(
+            "ok: max_segment_ms={}, overlap_ms={}",
+            options.max_segment_ms, options.overlap_ms
+        ))
+    });
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetBatchOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    max_batch_size: jint,
+    sort_by_length: jboolean,
+) -> jstring {
+    let options = BatchOptions::from_values(max_batch_size, sort_by_length == JNI_TRUE);
+    let result = catch_panic(|| {
+        let mut cache = lock_engine_cache();
+        cache.batch_options = options;
+        if let Some(engine) = cache.engine.as_mut() {
+            engine.set_batch_options(options);
+        }
+        Ok::<_, BridgeError>(format!(
+            "ok: max_batch_size={}, sort_by_length={}",
+            options.max_batch_size, options.sort_by_length
+        ))
+    });
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetResampleQuality(
+    mut env: JNIEnv,
+    _class: JClass,
+    quality: JString,
+) -> jstring {
+    let result = catch_panic(|| set_resample_quality_from_jni_inputs(&mut env, quality));
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetRuntimeOptionsJson(
+    mut env: JNIEnv,
+    _class: JClass,
+    options_json: JString,
+) -> jstring {
+    let result = catch_panic(|| set_runtime_options_json_from_jni_inputs(&mut env, options_json));
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeSetModelCacheDir(
+    mut env: JNIEnv,
+    _class: JClass,
+    cache_dir: JString,
+) -> jstring {
+    let result = catch_panic(|| set_model_cache_dir_from_jni_inputs(&mut env, cache_dir));
+    status_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeDetectSpeech(
+    mut env: JNIEnv,
+    _class: JClass,
+    pcm16: JShortArray,
+    sample_rate: jint,
+) -> jstring {
+    let result = catch_panic(|| detect_speech_from_jni_inputs(&mut env, pcm16, sample_rate));
+    data_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeStreamStart(
+    mut env: JNIEnv,
+    _class: JClass,
+    models_root_dir: JString,
+    model_id: JString,
+    sample_rate: jint,
+) -> jstring {
+    let result = catch_panic(|| {
+        stream_start_from_jni_inputs(&mut env, models_root_dir, model_id, sample_rate)
+    });
+    let result = result.map(|session_id| StreamStarted { session_id });
+    data_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeStreamPush(
+    mut env: JNIEnv,
+    _class: JClass,
+    session_id: jlong,
+    pcm16: JShortArray,
+) -> jstring {
+    let result = catch_panic(|| stream_push_from_jni_inputs(&mut env, session_id, pcm16));
+    data_string(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeStreamFinish(
+    mut env: JNIEnv,
+    _class: JClass,
+    session_id: jlong,
+) -> jstring {
+    data_string(&mut env, catch_panic(|| stream_finish(session_id)))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBridge_nativeStreamCancel(
+    _env: JNIEnv,
+    _class: JClass,
+    session_id: jlong,
+) {
+    let _ = catch_panic(|| {
+        lock_engine_cache().streams.remove(&session_id);
+        Ok::<_, BridgeError>(())
+    });
 }
 
 #[no_mangle]
@@ -93,18 +457,14 @@ pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBri
     mut env: JNIEnv,
     _class: JClass,
 ) -> jstring {
-    let summary = ENGINE_CACHE
-        .lock()
-        .ok()
-        .map(|cache| {
-            if cache.last_profile_summary.is_empty() {
-                "{}".to_string()
-            } else {
-                cache.last_profile_summary.clone()
-            }
+    let summary = catch_panic(|| {
+        let cache = lock_engine_cache();
+        Ok::<_, BridgeError>(match &cache.last_metrics {
+            Some(metrics) => ProfilingSummary::Metrics(Box::new(metrics.clone())),
+            None => ProfilingSummary::Empty {},
         })
-        .unwrap_or_else(|| "{\"error\":\"cache_lock_failed\"}".to_string());
-    new_java_string(&mut env, summary)
+    });
+    data_string(&mut env, summary)
 }
 
 #[no_mangle]
@@ -112,81 +472,280 @@ pub extern "system" fn Java_com_servideus_gigaamime_nativebridge_GigaamNativeBri
     _env: JNIEnv,
     _class: JClass,
 ) {
-    if let Ok(mut cache) = ENGINE_CACHE.lock() {
-        cache.engine = None;
+    let _ = catch_panic(|| {
+        let mut cache = lock_engine_cache();
+        if let Some(mut engine) = cache.engine.take() {
+            engine.unload_model();
+        }
         cache.model_key = None;
-        cache.last_profile_summary.clear();
-    }
+        cache.streams.clear();
+        cache.last_metrics = None;
+        Ok::<_, BridgeError>(())
+    });
 }
 
 fn validate_model_from_jni_inputs(
     env: &mut JNIEnv,
     models_root_dir: JString,
     model_id: JString,
-) -> Result<(), String> {
+) -> Result<(), BridgeError> {
+    let models_root = jstring_to_rust(env, models_root_dir)?;
+    let model_id = jstring_to_rust(env, model_id)?;
+    resolve_model(&models_root, &model_id).map(|_| ())
+}
+
+fn verify_model_from_jni_inputs(
+    env: &mut JNIEnv,
+    models_root_dir: JString,
+    model_id: JString,
+) -> Result<(), BridgeError> {
     let models_root = jstring_to_rust(env, models_root_dir)?;
     let model_id = jstring_to_rust(env, model_id)?;
-    let model_path = resolve_model_directory(&models_root, &model_id)?;
-    validate_model_directory(&model_path, &model_id)
+    let (model_path, manifest) = resolve_model(&models_root, &model_id)?;
+    manifest
+        .verify_checksums(&model_path)
+        .map_err(|e| BridgeError::new(ErrorCode::InvalidManifest, format!("{e:#}")))
+}
+
+/// `data` of `nativeStreamStart`.
+#[derive(Serialize)]
+struct StreamStarted {
+    session_id: jlong,
+}
+
+/// `data` of `nativeGetLastProfilingSummary`; an empty object before the first
+/// transcription.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ProfilingSummary {
+    Metrics(Box<TranscriptionMetrics>),
+    Empty {},
+}
+
+/// `data` of `nativeListModels`.
+#[derive(Serialize)]
+struct ModelList {
+    models: Vec<ModelListing>,
+}
+
+/// One model directory under the models root; `error` is set exactly when `valid` is
+/// false, and the manifest fields are then `null`.
+#[derive(Serialize)]
+struct ModelListing {
+    id: Option<String>,
+    architecture: Option<&'static str>,
+    directory: String,
+    sample_rate: Option<u32>,
+    valid: bool,
+    error: Option<String>,
+}
+
+fn list_models(models_root: &Path) -> ModelList {
+    let models = discover(models_root)
+        .into_iter()
+        .map(|(model_dir, manifest)| {
+            let directory = model_dir.display().to_string();
+            let manifest = manifest.and_then(|manifest| {
+                manifest.validate_files(&model_dir)?;
+                Ok(manifest)
+            });
+            match manifest {
+                Ok(manifest) => ModelListing {
+                    id: Some(manifest.id),
+                    architecture: Some(manifest.architecture.as_id()),
+                    directory,
+                    sample_rate: manifest
+                        .sample_rate
+                        .and_then(|rate| u32::try_from(rate).ok()),
+                    valid: true,
+                    error: None,
+                },
+                Err(error) => ModelListing {
+                    id: None,
+                    architecture: None,
+                    directory,
+                    sample_rate: None,
+                    valid: false,
+                    error: Some(format!("{error:#}")),
+                },
+            }
+        })
+        .collect();
+    ModelList { models }
 }
 
 fn set_runtime_options_from_jni_inputs(
     env: &mut JNIEnv,
     speed_profile: JString,
     accelerator_mode: JString,
-) -> Result<String, String> {
+) -> Result<String, BridgeError> {
     let speed_profile = jstring_to_rust(env, speed_profile)?;
     let accelerator_mode = jstring_to_rust(env, accelerator_mode)?;
     let options = RuntimeOptions::from_ids(&speed_profile, &accelerator_mode);
+    replace_runtime_options(options.clone());
 
-    let mut cache = ENGINE_CACHE
-        .lock()
-        .map_err(|_| "Engine cache lock poisoned".to_string())?;
+    Ok(format!(
+        "ok: speed_profile={}, accelerator_mode={}",
+        options.speed_profile.as_id(),
+        options.accelerator_mode.as_id()
+    ))
+}
+
+fn set_runtime_options_json_from_jni_inputs(
+    env: &mut JNIEnv,
+    options_json: JString,
+) -> Result<String, BridgeError> {
+    let options_json = jstring_to_rust(env, options_json)?;
+    let options = RuntimeOptions::from_json(&options_json)
+        .map_err(|e| BridgeError::new(ErrorCode::InvalidArgument, format!("{e:#}")))?;
+    let message = format!("ok: {}", options.cache_fragment());
+    replace_runtime_options(options);
+    Ok(message)
+}
+
+/// Changed options drop the loaded engine and its streams; the next call reloads.
+fn replace_runtime_options(options: RuntimeOptions) {
+    let mut cache = lock_engine_cache();
     if cache.runtime_options != options {
         cache.runtime_options = options;
         cache.engine = None;
         cache.model_key = None;
+        cache.streams.clear();
+    }
+}
+
+fn set_decode_options_from_jni_inputs(
+    env: &mut JNIEnv,
+    decode_strategy: JString,
+    beam_width: jint,
+    lm_alpha: jfloat,
+    lm_beta: jfloat,
+) -> Result<String, BridgeError> {
+    let decode_strategy = jstring_to_rust(env, decode_strategy)?;
+    let beam_width = usize::try_from(beam_width)
+        .map_err(|_| BridgeError::new(ErrorCode::InvalidArgument, "Invalid beam width"))?;
+    let options =
+        DecodeOptions::from_ids(&decode_strategy, beam_width).with_lm_weights(lm_alpha, lm_beta);
+
+    let mut cache = lock_engine_cache();
+    cache.decode_options = options;
+    if let Some(engine) = cache.engine.as_mut() {
+        engine.set_decode_options(options);
     }
 
     Ok(format!(
-        "ok: speed_profile={}, accelerator_mode={}",
-        cache.runtime_options.speed_profile.as_id(),
-        cache.runtime_options.accelerator_mode.as_id()
+        "ok: decode_strategy={}, beam_width={}, lm_alpha={}, lm_beta={}{}",
+        options.strategy.as_id(),
+        options.beam_width,
+        options.lm_alpha,
+        options.lm_beta,
+        decoding_warning(&cache)
     ))
 }
 
+fn set_hotwords_from_jni_inputs(
+    env: &mut JNIEnv,
+    phrases: JObjectArray,
+    weights: JFloatArray,
+) -> Result<String, BridgeError> {
+    let invalid_argument = |message: String| BridgeError::new(ErrorCode::InvalidArgument, message);
+    let phrase_count = env
+        .get_array_length(&phrases)
+        .map_err(|e| invalid_argument(format!("Failed to get hotword array length: {e}")))?;
+    let weight_count = env
+        .get_array_length(&weights)
+        .map_err(|e| invalid_argument(format!("Failed to get hotword weight array length: {e}")))?;
+    if phrase_count != weight_count {
+        return Err(invalid_argument(format!(
+            "Hotword phrase count ({phrase_count}) does not match weight count ({weight_count})"
+        )));
+    }
+
+    let mut weight_values = vec![0.0_f32; weight_count as usize];
+    env.get_float_array_region(&weights, 0, &mut weight_values)
+        .map_err(|e| invalid_argument(format!("Failed to read hotword weights: {e}")))?;
+
+    let mut hotwords = Vec::with_capacity(phrase_count as usize);
+    for (index, weight) in weight_values.into_iter().enumerate() {
+        let phrase = env
+            .get_object_array_element(&phrases, index as i32)
+            .map_err(|e| invalid_argument(format!("Failed to read hotword phrase: {e}")))?;
+        // Freed at the end of the iteration, so long phrase lists stay within the local
+        // reference table.
+        let phrase = env.auto_local(JString::from(phrase));
+        let phrase: String = env
+            .get_string(&phrase)
+            .map(Into::into)
+            .map_err(|e| invalid_argument(format!("Failed to decode hotword phrase: {e}")))?;
+        let hotword = HotwordPhrase::new(&phrase, weight);
+        if !hotword.text.is_empty() {
+            hotwords.push(hotword);
+        }
+    }
+
+    let mut cache = lock_engine_cache();
+    if let Some(engine) = cache.engine.as_mut() {
+        engine.set_hotwords(hotwords.clone());
+    }
+    let count = hotwords.len();
+    cache.hotwords = hotwords;
+
+    Ok(format!("ok: hotwords={count}{}", decoding_warning(&cache)))
+}
+
+/// Hotwords are only fused into CTC beam search, so flag a configuration where they or
+/// beam search are set but would be ignored.
+fn decoding_warning(cache: &EngineCache) -> &'static str {
+    let greedy_only = cache
+        .engine
+        .as_ref()
+        .is_some_and(GigaamEngine::decodes_greedily_only);
+    let beam_search = cache.decode_options.strategy == DecodeStrategy::BeamSearch;
+    if greedy_only && (beam_search || !cache.hotwords.is_empty()) {
+        "; warning: the loaded transducer model is decoded greedily and ignores beam search, hotwords and the language model"
+    } else if !cache.hotwords.is_empty() && !beam_search {
+        "; warning: hotwords are ignored by greedy decoding, select beam search to apply them"
+    } else {
+        ""
+    }
+}
+
 fn warmup_from_jni_inputs(
     env: &mut JNIEnv,
     models_root_dir: JString,
     model_id: JString,
-) -> Result<String, String> {
+) -> Result<String, BridgeError> {
     let models_root = jstring_to_rust(env, models_root_dir)?;
     let model_id = jstring_to_rust(env, model_id)?;
-    let model_path = resolve_model_directory(&models_root, &model_id)?;
-    validate_model_directory(&model_path, &model_id)?;
+    let (model_path, manifest) = resolve_model(&models_root, &model_id)?;
 
-    let mut cache = ENGINE_CACHE
-        .lock()
-        .map_err(|_| "Engine cache lock poisoned".to_string())?;
-    ensure_engine_loaded(&mut cache, &models_root, &model_id, &model_path)?;
+    let mut cache = lock_engine_cache();
+    let load_start = Instant::now();
+    ensure_engine_loaded(&mut cache, &model_path, &manifest)?;
+    let load_ms = load_start.elapsed().as_millis();
 
     let warmup_input = vec![0.0_f32; TARGET_SAMPLE_RATE / 2];
-    let report_json = {
-        let engine = cache
-            .engine
-            .as_mut()
-            .ok_or_else(|| "Model engine is not loaded".to_string())?;
-        let report = engine
-            .transcribe_samples(&warmup_input)
-            .map_err(|e| format!("Warmup failed: {e}"))?;
-        report.to_json()
-    };
-    cache.last_profile_summary = format!(
-        "{{\"warmup\":true,\"pcm_to_f32_ms\":0,\"resample_ms\":0,{}}}",
-        report_json.trim_start_matches('{').trim_end_matches('}')
+    let engine = cache.engine.as_mut().ok_or_else(engine_not_loaded)?;
+    let report = engine
+        .transcribe_samples(&warmup_input)
+        .map_err(|e| BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Warmup failed"))?;
+    let model_load = engine.load_metrics().cloned().unwrap_or_default();
+    let mut metrics = TranscriptionMetrics::from_report(
+        &report,
+        warmup_input.len(),
+        TARGET_SAMPLE_RATE,
+        model_load,
     );
+    metrics.warmup = true;
+    metrics.load_ms = load_ms;
+    metrics.wall_ms = load_start.elapsed().as_millis();
+    cache.last_metrics = Some(metrics);
 
-    Ok("ok".to_string())
+    Ok(format!(
+        "ok: load_ms={load_ms}, inference_ms={}{}",
+        report.timings.total_ms,
+        decoding_warning(&cache)
+    ))
 }
 
 fn transcribe_from_jni_inputs(
@@ -195,21 +754,18 @@ fn transcribe_from_jni_inputs(
     model_id: JString,
     pcm16: JShortArray,
     sample_rate: jint,
-) -> Result<String, String> {
+    max_alternatives: usize,
+) -> Result<(NativeTranscriptionReport, TranscriptionMetrics), BridgeError> {
+    let wall_start = Instant::now();
     let models_root = jstring_to_rust(env, models_root_dir)?;
     let model_id = jstring_to_rust(env, model_id)?;
-    let model_path = resolve_model_directory(&models_root, &model_id)?;
-    validate_model_directory(&model_path, &model_id)?;
+    let (model_path, manifest) = resolve_model(&models_root, &model_id)?;
 
-    let mut pcm = vec![
-        0_i16;
-        env.get_array_length(&pcm16)
-            .map_err(|e| format!("Failed to get PCM array length: {e}"))? as usize
-    ];
-    env.get_short_array_region(&pcm16, 0, &mut pcm)
-        .map_err(|e| format!("Failed to read PCM samples: {e}"))?;
+    let pcm_copy_start = Instant::now();
+    let pcm = read_pcm16(env, &pcm16)?;
+    let pcm_copy_ms = pcm_copy_start.elapsed().as_millis();
 
-    let source_rate = usize::try_from(sample_rate).map_err(|_| "Invalid sample rate".to_string())?;
+    let source_rate = parse_sample_rate(sample_rate)?;
 
     let pcm_to_f32_start = Instant::now();
     let mut samples = pcm
@@ -218,126 +774,431 @@ fn transcribe_from_jni_inputs(
         .collect::<Vec<f32>>();
     let pcm_to_f32_ms = pcm_to_f32_start.elapsed().as_millis();
 
+    // Resampling runs without the lock, so it does not hold up other calls.
+    let resample_quality = lock_engine_cache().resample_quality;
     let resample_start = Instant::now();
     if source_rate != TARGET_SAMPLE_RATE {
-        samples = resample_linear(&samples, source_rate, TARGET_SAMPLE_RATE);
+        samples = resample(&samples, source_rate, TARGET_SAMPLE_RATE, resample_quality);
     }
     let resample_ms = resample_start.elapsed().as_millis();
 
-    let mut cache = ENGINE_CACHE
-        .lock()
-        .map_err(|_| "Engine cache lock poisoned".to_string())?;
-    ensure_engine_loaded(&mut cache, &models_root, &model_id, &model_path)?;
+    let mut cache = lock_engine_cache();
+    let load_start = Instant::now();
+    ensure_engine_loaded(&mut cache, &model_path, &manifest)?;
+    let load_ms = load_start.elapsed().as_millis();
 
-    let report = {
-        let engine = cache
-            .engine
+    let (report, model_load) = {
+        let EngineCache {
+            engine,
+            vad,
+            long_form,
+            ..
+        } = &mut *cache;
+        let engine = engine
             .as_mut()
-            .ok_or_else(|| "Model engine is not loaded".to_string())?;
-        engine
-            .transcribe_samples(&samples)
-            .map_err(|e| format!("Transcription failed: {e}"))?
+            .ok_or_else(engine_not_loaded)?;
+        let use_long_form = long_form.max_segment_samples() > 0
+            && samples.len() > long_form.max_segment_samples();
+        // Alternatives are only computed over a single encoder run, which the long-form
+        // window bounds.
+        if use_long_form && max_alternatives > 0 {
+            return Err(BridgeError::new(
+                ErrorCode::InvalidAudio,
+                format!(
+                    "Alternatives are limited to {} ms of audio",
+                    long_form.max_segment_ms
+                ),
+            ));
+        }
+        let report = match vad.as_mut() {
+            vad if use_long_form => engine.transcribe_long_form(&samples, vad, long_form),
+            Some(vad) => engine.transcribe_speech(&samples, vad, max_alternatives),
+            None if max_alternatives > 0 => {
+                engine.transcribe_samples_with_alternatives(&samples, max_alternatives)
+            }
+            None => engine.transcribe_samples(&samples),
+        }
+        .map_err(|e| BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Transcription failed"))?;
+        (report, engine.load_metrics().cloned().unwrap_or_default())
     };
 
-    cache.last_profile_summary = format!(
-        "{{\"warmup\":false,\"pcm_to_f32_ms\":{pcm_to_f32_ms},\"resample_ms\":{resample_ms},{}}}",
-        report.to_json().trim_start_matches('{').trim_end_matches('}')
-    );
-    Ok(report.text)
+    let mut metrics =
+        TranscriptionMetrics::from_report(&report, samples.len(), TARGET_SAMPLE_RATE, model_load);
+    metrics.load_ms = load_ms;
+    metrics.pcm_copy_ms = pcm_copy_ms;
+    metrics.pcm_to_f32_ms = pcm_to_f32_ms;
+    metrics.resample_ms = resample_ms;
+    metrics.wall_ms = wall_start.elapsed().as_millis();
+    cache.last_metrics = Some(metrics.clone());
+    Ok((report, metrics))
+}
+
+/// Transcribes every PCM array of `utterances`, sharing encoder runs as the batch
+/// options allow. Arrays longer than the long-form window are split and stitched.
+fn transcribe_batch_from_jni_inputs(
+    env: &mut JNIEnv,
+    models_root_dir: JString,
+    model_id: JString,
+    utterances: JObjectArray,
+    sample_rate: jint,
+) -> Result<Vec<NativeTranscriptionReport>, BridgeError> {
+    let models_root = jstring_to_rust(env, models_root_dir)?;
+    let model_id = jstring_to_rust(env, model_id)?;
+    let (model_path, manifest) = resolve_model(&models_root, &model_id)?;
+    let source_rate = parse_sample_rate(sample_rate)?;
+
+    let count = env.get_array_length(&utterances).map_err(|e| {
+        BridgeError::new(
+            ErrorCode::InvalidAudio,
+            format!("Failed to get utterance count: {e}"),
+        )
+    })?;
+    let mut pcm = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let element = env
+            .get_object_array_element(&utterances, index)
+            .map_err(|e| {
+                BridgeError::new(
+                    ErrorCode::InvalidAudio,
+                    format!("Failed to read utterance {index}: {e}"),
+                )
+            })?;
+        // Freed at the end of the iteration, so long batches stay within the local
+        // reference table.
+        let pcm16 = env.auto_local(JShortArray::from(element));
+        pcm.push(read_pcm16(env, &pcm16)?);
+    }
+
+    let resample_quality = lock_engine_cache().resample_quality;
+    let samples = pcm
+        .iter()
+        .map(|pcm| {
+            let samples = pcm
+                .iter()
+                .map(|sample| *sample as f32 / i16::MAX as f32)
+                .collect::<Vec<f32>>();
+            if source_rate == TARGET_SAMPLE_RATE {
+                samples
+            } else {
+                resample(&samples, source_rate, TARGET_SAMPLE_RATE, resample_quality)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut cache = lock_engine_cache();
+    ensure_engine_loaded(&mut cache, &model_path, &manifest)?;
+    let long_form = cache.long_form;
+    let engine = cache.engine.as_mut().ok_or_else(engine_not_loaded)?;
+    let utterances = samples.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    engine
+        .transcribe_batch(&utterances, &long_form)
+        .map_err(|e| BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Transcription failed"))
+}
+
+fn set_vad_options_from_jni_inputs(
+    env: &mut JNIEnv,
+    models_root_dir: JString,
+    vad_mode: JString,
+    options: VadOptions,
+) -> Result<String, BridgeError> {
+    let models_root = jstring_to_rust(env, models_root_dir)?;
+    let mode = VadMode::from_id(&jstring_to_rust(env, vad_mode)?);
+    let vad =
+        VoiceActivityDetector::from_mode(mode, Path::new(&models_root), options).map_err(|e| {
+            BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Failed to initialize VAD")
+        })?;
+
+    let mut cache = lock_engine_cache();
+    cache.vad = vad;
+
+    Ok(format!(
+        "ok: vad_mode={}, threshold={}, min_speech_ms={}, min_silence_ms={}, speech_pad_ms={}",
+        mode.as_id(),
+        options.threshold,
+        options.min_speech_ms,
+        options.min_silence_ms,
+        options.speech_pad_ms
+    ))
+}
+
+fn set_resample_quality_from_jni_inputs(
+    env: &mut JNIEnv,
+    quality: JString,
+) -> Result<String, BridgeError> {
+    let quality = ResampleQuality::from_id(jstring_to_rust(env, quality)?.trim());
+    let mut cache = lock_engine_cache();
+    cache.resample_quality = quality;
+    Ok(format!("ok: resample_quality={}", quality.as_id()))
+}
+
+/// An empty directory turns the optimized model cache off. The loaded engine is kept;
+/// the setting applies from the next model load.
+fn set_model_cache_dir_from_jni_inputs(
+    env: &mut JNIEnv,
+    cache_dir: JString,
+) -> Result<String, BridgeError> {
+    let cache_dir = jstring_to_rust(env, cache_dir)?;
+    let cache_dir = cache_dir.trim();
+    let mut cache = lock_engine_cache();
+    cache.model_cache = (!cache_dir.is_empty()).then(|| ModelCache::new(cache_dir));
+    Ok(match &cache.model_cache {
+        Some(model_cache) => format!("ok: model_cache={}", model_cache.dir().display()),
+        None => "ok: model_cache=off".to_string(),
+    })
+}
+
+fn detect_speech_from_jni_inputs(
+    env: &mut JNIEnv,
+    pcm16: JShortArray,
+    sample_rate: jint,
+) -> Result<SpeechDetection, BridgeError> {
+    let pcm = read_pcm16(env, &pcm16)?;
+    let source_rate = parse_sample_rate(sample_rate)?;
+
+    let mut samples = pcm
+        .iter()
+        .map(|sample| *sample as f32 / i16::MAX as f32)
+        .collect::<Vec<f32>>();
+
+    let resample_quality = lock_engine_cache().resample_quality;
+    if source_rate != VAD_SAMPLE_RATE {
+        samples = resample(&samples, source_rate, VAD_SAMPLE_RATE, resample_quality);
+    }
+    let mut cache = lock_engine_cache();
+    // Without a configured detector fall back to the model-free energy detector.
+    let mut fallback = None;
+    let vad = match cache.vad.as_mut() {
+        Some(vad) => vad,
+        None => fallback.insert(
+            VoiceActivityDetector::energy(VadOptions::default()).map_err(|e| {
+                BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Failed to initialize VAD")
+            })?,
+        ),
+    };
+    let segments = vad.detect(&samples).map_err(|e| {
+        BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Speech detection failed")
+    })?;
+    Ok(SpeechDetection::new(segments, vad.mode().as_id()))
+}
+
+fn stream_start_from_jni_inputs(
+    env: &mut JNIEnv,
+    models_root_dir: JString,
+    model_id: JString,
+    sample_rate: jint,
+) -> Result<jlong, BridgeError> {
+    let models_root = jstring_to_rust(env, models_root_dir)?;
+    let model_id = jstring_to_rust(env, model_id)?;
+    let (model_path, manifest) = resolve_model(&models_root, &model_id)?;
+    let source_rate = parse_sample_rate(sample_rate)?;
+
+    let mut cache = lock_engine_cache();
+    ensure_engine_loaded(&mut cache, &model_path, &manifest)?;
+    let state = cache
+        .engine
+        .as_ref()
+        .ok_or_else(engine_not_loaded)?
+        .start_stream()
+        .map_err(|e| {
+            BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Failed to start stream")
+        })?;
+
+    // Each session keeps its own filter history so chunk edges leave no seams.
+    let resampler = Resampler::new(source_rate, TARGET_SAMPLE_RATE, cache.resample_quality);
+    cache.next_stream_id += 1;
+    let session_id = cache.next_stream_id;
+    cache
+        .streams
+        .insert(session_id, StreamingSession { resampler, state });
+    Ok(session_id)
+}
+
+fn stream_push_from_jni_inputs(
+    env: &mut JNIEnv,
+    session_id: jlong,
+    pcm16: JShortArray,
+) -> Result<SessionUpdate, BridgeError> {
+    let pcm = read_pcm16(env, &pcm16)?;
+    let samples = pcm
+        .iter()
+        .map(|sample| *sample as f32 / i16::MAX as f32)
+        .collect::<Vec<f32>>();
+
+    let mut cache = lock_engine_cache();
+    let EngineCache { engine, streams, .. } = &mut *cache;
+    let session = streams
+        .get_mut(&session_id)
+        .ok_or_else(|| unknown_session(session_id))?;
+    let engine = engine
+        .as_mut()
+        .ok_or_else(engine_not_loaded)?;
+
+    let samples = session.resampler.process(&samples);
+    let update = engine
+        .push_stream(&mut session.state, &samples)
+        .map_err(|e| streaming_failed(&e))?;
+    Ok(SessionUpdate { session_id, update })
+}
+
+fn stream_finish(session_id: jlong) -> Result<SessionUpdate, BridgeError> {
+    let mut cache = lock_engine_cache();
+    let mut session = cache
+        .streams
+        .remove(&session_id)
+        .ok_or_else(|| unknown_session(session_id))?;
+    let engine = cache
+        .engine
+        .as_mut()
+        .ok_or_else(engine_not_loaded)?;
+    let tail = session.resampler.finish();
+    if !tail.is_empty() {
+        engine
+            .push_stream(&mut session.state, &tail)
+            .map_err(|e| streaming_failed(&e))?;
+    }
+    let update = engine
+        .finish_stream(&mut session.state)
+        .map_err(|e| streaming_failed(&e))?;
+    Ok(SessionUpdate { session_id, update })
 }
 
 fn ensure_engine_loaded(
     cache: &mut EngineCache,
-    models_root: &str,
-    model_id: &str,
     model_path: &Path,
-) -> Result<(), String> {
-    let cache_key = compose_cache_key(models_root, model_id, cache.runtime_options)?;
+    manifest: &ModelManifest,
+) -> Result<(), BridgeError> {
+    let cache_key = compose_cache_key(model_path, &cache.runtime_options);
     if cache.model_key.as_deref() != Some(cache_key.as_str()) {
         let mut engine = GigaamEngine::new();
         engine
-            .load_model(model_path, cache.runtime_options)
-            .map_err(|e| format!("Failed to load model: {e}"))?;
+            .load_model(
+                model_path,
+                manifest,
+                &cache.runtime_options,
+                cache.model_cache.as_ref(),
+            )
+            .map_err(|e| {
+                BridgeError::from_engine(&e, ErrorCode::OrtFailure, "Failed to load model")
+            })?;
+        engine.set_decode_options(cache.decode_options);
+        engine.set_batch_options(cache.batch_options);
+        engine.set_hotwords(cache.hotwords.clone());
         cache.model_key = Some(cache_key);
         cache.engine = Some(engine);
+        // Streaming state is tied to the frontend of the model it was started with.
+        cache.streams.clear();
     }
     Ok(())
 }
 
-fn compose_cache_key(
-    models_root: &str,
-    model_id: &str,
-    runtime_options: RuntimeOptions,
-) -> Result<String, String> {
-    Ok(format!(
-        "{models_root}/{}?{}",
-        model_subdirectory_name(model_id)?,
+fn compose_cache_key(model_path: &Path, runtime_options: &RuntimeOptions) -> String {
+    format!(
+        "{}?{}",
+        model_path.display(),
         runtime_options.cache_fragment()
-    ))
+    )
 }
 
-fn jstring_to_rust(env: &mut JNIEnv, value: JString) -> Result<String, String> {
-    env.get_string(&value)
-        .map(|s| s.into())
-        .map_err(|e| format!("Failed to decode Java string: {e}"))
+/// Locks the engine cache, recovering it when an earlier call panicked while holding it.
+/// The engine and streaming sessions may have been left half-updated, so they are
+/// dropped and the model is reloaded on next use; options set by the host are kept.
+fn lock_engine_cache() -> MutexGuard<'static, EngineCache> {
+    ENGINE_CACHE.lock().unwrap_or_else(|poisoned| {
+        log::warn!("GigaAM engine cache recovered after a panic; the model will be reloaded");
+        ENGINE_CACHE.clear_poison();
+        let mut cache = poisoned.into_inner();
+        cache.model_key = None;
+        cache.engine = None;
+        cache.streams.clear();
+        cache
+    })
 }
 
-fn resolve_model_directory(models_root: &str, model_id: &str) -> Result<PathBuf, String> {
-    let subdir = model_subdirectory_name(model_id)?;
-    Ok(Path::new(models_root).join(subdir))
+fn engine_not_loaded() -> BridgeError {
+    BridgeError::new(ErrorCode::EngineNotLoaded, "Model engine is not loaded")
 }
 
-fn validate_model_directory(model_dir: &Path, model_id: &str) -> Result<(), String> {
-    if !model_dir.exists() {
-        return Err(format!("Model directory does not exist: {}", model_dir.display()));
-    }
-    let onnx_file = model_onnx_filename(model_id)?;
-    let required_files = [onnx_file, VOCAB_FILE, CONFIG_FILE];
-    for required_file in required_files {
-        let path = model_dir.join(required_file);
-        if !path.exists() {
-            return Err(format!("Required file not found: {}", path.display()));
-        }
-    }
-    Ok(())
+fn unknown_session(session_id: jlong) -> BridgeError {
+    BridgeError::new(
+        ErrorCode::UnknownSession,
+        format!("Unknown streaming session: {session_id}"),
+    )
 }
 
-fn model_subdirectory_name(model_id: &str) -> Result<&'static str, String> {
-    match model_id {
-        MODEL_INT8_ID => Ok("gigaam-v3-e2e-ctc-int8"),
-        MODEL_FULL_ID => Ok("gigaam-v3-e2e-ctc"),
-        _ => Err(format!("Unsupported model id: {model_id}")),
-    }
+fn streaming_failed(error: &anyhow::Error) -> BridgeError {
+    BridgeError::from_engine(
+        error,
+        ErrorCode::OrtFailure,
+        "Streaming transcription failed",
+    )
 }
 
-fn model_onnx_filename(model_id: &str) -> Result<&'static str, String> {
-    match model_id {
-        MODEL_INT8_ID => Ok("v3_e2e_ctc.int8.onnx"),
-        MODEL_FULL_ID => Ok("v3_e2e_ctc.onnx"),
-        _ => Err(format!("Unsupported model id: {model_id}")),
-    }
+/// Result of the calls that configure or load the engine, as [`result_json`] whose `text`
+/// is the `ok: ...` summary of what was applied.
+fn status_string(env: &mut JNIEnv, result: Result<String, BridgeError>) -> jstring {
+    let json = match &result {
+        Ok(message) => result_json(Ok((message, None))),
+        Err(error) => result_json(Err(error)),
+    };
+    new_java_string(env, json)
 }
 
-fn resample_linear(input: &[f32], source_rate: usize, target_rate: usize) -> Vec<f32> {
-    if input.is_empty() || source_rate == 0 || target_rate == 0 || source_rate == target_rate {
-        return input.to_vec();
-    }
+/// Result of the calls that answer with their own object, as [`data_json`].
+fn data_string<D: Serialize>(env: &mut JNIEnv, result: Result<D, BridgeError>) -> jstring {
+    new_java_string(env, data_json(result.as_ref()))
+}
 
-    let ratio = target_rate as f64 / source_rate as f64;
-    let output_len = ((input.len() as f64) * ratio).round().max(1.0) as usize;
-    let mut output = vec![0.0_f32; output_len];
-
-    for (index, value) in output.iter_mut().enumerate() {
-        let source_pos = index as f64 / ratio;
-        let left = source_pos.floor() as usize;
-        let right = (left + 1).min(input.len().saturating_sub(1));
-        let fraction = source_pos - left as f64;
-        let left_sample = input[left];
-        let right_sample = input[right];
-        *value = (left_sample as f64 * (1.0 - fraction) + right_sample as f64 * fraction) as f32;
-    }
+fn jstring_to_rust(env: &mut JNIEnv, value: JString) -> Result<String, BridgeError> {
+    env.get_string(&value).map(|s| s.into()).map_err(|e| {
+        BridgeError::new(
+            ErrorCode::InvalidArgument,
+            format!("Failed to decode Java string: {e}"),
+        )
+    })
+}
+
+fn read_pcm16(env: &mut JNIEnv, pcm16: &JShortArray) -> Result<Vec<i16>, BridgeError> {
+    let invalid_audio = |message: String| BridgeError::new(ErrorCode::InvalidAudio, message);
+    let length = env
+        .get_array_length(pcm16)
+        .map_err(|e| invalid_audio(format!("Failed to get PCM array length: {e}")))?;
+    let mut pcm = vec![0_i16; length as usize];
+    env.get_short_array_region(pcm16, 0, &mut pcm)
+        .map_err(|e| invalid_audio(format!("Failed to read PCM samples: {e}")))?;
+    Ok(pcm)
+}
+
+fn parse_sample_rate(sample_rate: jint) -> Result<usize, BridgeError> {
+    usize::try_from(sample_rate)
+        .ok()
+        .filter(|rate| *rate > 0)
+        .ok_or_else(|| {
+            BridgeError::new(
+                ErrorCode::InvalidAudio,
+                format!("Invalid sample rate: {sample_rate}"),
+            )
+        })
+}
+
+/// Finds `model_id` under `models_root` through its manifest and checks that all of
+/// its files are present.
+fn resolve_model(
+    models_root: &str,
+    model_id: &str,
+) -> Result<(PathBuf, ModelManifest), BridgeError> {
+    let (model_dir, manifest) = ModelManifest::find(Path::new(models_root), model_id)
+        .map_err(|e| BridgeError::new(ErrorCode::InvalidManifest, format!("{e:#}")))?
+        .ok_or_else(|| unsupported_model(model_id))?;
+    manifest
+        .validate_files(&model_dir)
+        .map_err(|e| BridgeError::new(ErrorCode::ModelMissing, e.to_string()))?;
+    Ok((model_dir, manifest))
+}
 
-    output
+fn unsupported_model(model_id: &str) -> BridgeError {
+    BridgeError::new(
+        ErrorCode::UnsupportedModel,
+        format!("Unsupported model id: {model_id}"),
+    )
 }
 
 fn new_java_string(env: &mut JNIEnv, value: String) -> jstring {
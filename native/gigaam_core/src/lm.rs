@@ -0,0 +1,582 @@
+use crate::decoder::PrefixScorer;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::f32::consts::LN_10;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+pub const LM_BINARY_FILENAME: &str = "lm.bin";
+pub const LM_ARPA_FILENAME: &str = "lm.arpa";
+
+const BINARY_MAGIC: &[u8; 4] = b"GLM1";
+const SENTENCE_START: &str = "<s>";
+const SENTENCE_END: &str = "</s>";
+const UNKNOWN_WORD: &str = "<unk>";
+/// log10 probability used for words missing from an LM without `<unk>`.
+const UNKNOWN_WORD_LOG10_PROB: f32 = -10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NgramEntry {
+    log_prob: f32,
+    backoff: f32,
+}
+
+/// Word-level back-off n-gram model. Probabilities are stored as natural logarithms.
+#[derive(Debug, Clone)]
+pub struct NgramLanguageModel {
+    words: Vec<String>,
+    word_ids: HashMap<String, u32>,
+    // ngrams[n - 1] holds every n-gram of order n.
+    ngrams: Vec<HashMap<Vec<u32>, NgramEntry>>,
+}
+
+impl NgramLanguageModel {
+    /// Loads `lm.bin` or, failing that, `lm.arpa` from the model directory and
+    /// converts it to `lm.bin`. An `lm.bin` older than `lm.arpa` is rebuilt. Returns
+    /// `Ok(None)` when there is no language model.
+    pub fn load_optional(model_dir: &Path) -> Result<Option<Self>> {
+        let binary_path = model_dir.join(LM_BINARY_FILENAME);
+        let arpa_path = model_dir.join(LM_ARPA_FILENAME);
+        if binary_path.exists() && !is_stale(&binary_path, &arpa_path) {
+            let bytes = fs::read(&binary_path).with_context(|| {
+                format!("Failed to read GigaAM LM file: {}", binary_path.display())
+            })?;
+            return Self::from_binary(&bytes).map(Some);
+        }
+
+        if arpa_path.exists() {
+            let content = fs::read_to_string(&arpa_path).with_context(|| {
+                format!("Failed to read GigaAM LM file: {}", arpa_path.display())
+            })?;
+            let model = Self::from_arpa(&content)?;
+            // ARPA parsing is slow on device; keep the compact form for the next load.
+            let written = fs::File::create(&binary_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut file| model.write_binary(&mut file));
+            if let Err(error) = written {
+                log::warn!(
+                    "Failed to cache GigaAM LM binary {}: {error}",
+                    binary_path.display()
+                );
+                let _ = fs::remove_file(&binary_path);
+            }
+            return Ok(Some(model));
+        }
+
+        Ok(None)
+    }
+
+    pub fn from_arpa(content: &str) -> Result<Self> {
+        let mut model = Self {
+            words: Vec::new(),
+            word_ids: HashMap::new(),
+            ngrams: Vec::new(),
+        };
+        let mut current_order = 0_usize;
+        let mut seen_data = false;
+
+        for (line_idx, raw_line) in content.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "\\data\\" {
+                seen_data = true;
+                continue;
+            }
+            if line == "\\end\\" {
+                break;
+            }
+            if let Some(order) = line
+                .strip_prefix('\\')
+                .and_then(|rest| rest.strip_suffix("-grams:"))
+            {
+                current_order = order
+                    .parse::<usize>()
+                    .with_context(|| format!("Invalid ARPA section header '{line}'"))?;
+                if current_order == 0 {
+                    return Err(anyhow::anyhow!("Invalid ARPA section header '{line}'"));
+                }
+                if model.ngrams.len() < current_order {
+                    model.ngrams.resize_with(current_order, HashMap::new);
+                }
+                continue;
+            }
+            if current_order == 0 {
+                // `\data\` counts; the tables themselves size the model.
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let log_prob = parse_log10(fields.next(), line_idx)?;
+            let mut key = Vec::with_capacity(current_order);
+            for _ in 0..current_order {
+                let word = fields.next().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "ARPA line {} has fewer than {} words",
+                        line_idx + 1,
+                        current_order
+                    )
+                })?;
+                key.push(model.intern(word));
+            }
+            let backoff = match fields.next() {
+                Some(value) => parse_log10(Some(value), line_idx)?,
+                None => 0.0,
+            };
+            model.ngrams[current_order - 1].insert(key, NgramEntry { log_prob, backoff });
+        }
+
+        if !seen_data || model.ngrams.first().is_none_or(HashMap::is_empty) {
+            return Err(anyhow::anyhow!(
+                "ARPA file has no \\data\\ section or unigrams"
+            ));
+        }
+        Ok(model)
+    }
+
+    /// Compact little-endian form: magic, order, vocabulary, then every n-gram table.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader { bytes, offset: 0 };
+        if reader.take(BINARY_MAGIC.len())? != BINARY_MAGIC {
+            return Err(anyhow::anyhow!("Invalid GigaAM LM binary header"));
+        }
+
+        let order = reader.read_u32()? as usize;
+        let word_count = reader.read_u32()? as usize;
+        let mut words = Vec::with_capacity(word_count);
+        let mut word_ids = HashMap::with_capacity(word_count);
+        for id in 0..word_count {
+            let len = reader.read_u32()? as usize;
+            let word = std::str::from_utf8(reader.take(len)?)
+                .context("Invalid UTF-8 word in GigaAM LM binary")?
+                .to_string();
+            word_ids.insert(word.clone(), id as u32);
+            words.push(word);
+        }
+
+        let mut ngrams = Vec::with_capacity(order);
+        for n in 1..=order {
+            let count = reader.read_u32()? as usize;
+            let mut table = HashMap::with_capacity(count);
+            for _ in 0..count {
+                let mut key = Vec::with_capacity(n);
+                for _ in 0..n {
+                    let id = reader.read_u32()?;
+                    if id as usize >= word_count {
+                        return Err(anyhow::anyhow!(
+                            "GigaAM LM binary word id {id} out of range"
+                        ));
+                    }
+                    key.push(id);
+                }
+                let log_prob = reader.read_f32()?;
+                let backoff = reader.read_f32()?;
+                table.insert(key, NgramEntry { log_prob, backoff });
+            }
+            ngrams.push(table);
+        }
+
+        if ngrams.first().is_none_or(HashMap::is_empty) {
+            return Err(anyhow::anyhow!("GigaAM LM binary has no unigrams"));
+        }
+        Ok(Self {
+            words,
+            word_ids,
+            ngrams,
+        })
+    }
+
+    pub fn write_binary(&self, writer: &mut impl Write) -> Result<()> {
+        writer.write_all(BINARY_MAGIC)?;
+        writer.write_all(&(self.ngrams.len() as u32).to_le_bytes())?;
+        writer.write_all(&(self.words.len() as u32).to_le_bytes())?;
+        for word in &self.words {
+            writer.write_all(&(word.len() as u32).to_le_bytes())?;
+            writer.write_all(word.as_bytes())?;
+        }
+        for table in &self.ngrams {
+            writer.write_all(&(table.len() as u32).to_le_bytes())?;
+            let mut entries = table.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+            for (key, entry) in entries {
+                for id in key {
+                    writer.write_all(&id.to_le_bytes())?;
+                }
+                writer.write_all(&entry.log_prob.to_le_bytes())?;
+                writer.write_all(&entry.backoff.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn order(&self) -> usize {
+        self.ngrams.len()
+    }
+
+    /// Natural-log probability of `word` after `context`, with Katz back-off.
+    pub fn log_prob(&self, context: &[&str], word: &str) -> f32 {
+        let Some(word_id) = self.lookup(word).or_else(|| self.lookup(UNKNOWN_WORD)) else {
+            return UNKNOWN_WORD_LOG10_PROB * LN_10;
+        };
+        let context_ids = context
+            .iter()
+            .map(|word| self.lookup(word).or_else(|| self.lookup(UNKNOWN_WORD)))
+            .collect::<Vec<_>>();
+        let usable = context_ids.len().min(self.order().saturating_sub(1));
+        let context_ids = &context_ids[context_ids.len() - usable..];
+
+        let mut backoff = 0.0;
+        for start in 0..=context_ids.len() {
+            let history = &context_ids[start..];
+            if history.iter().all(Option::is_some) {
+                let mut key = history.iter().flatten().copied().collect::<Vec<_>>();
+                key.push(word_id);
+                if let Some(entry) = self.ngrams[key.len() - 1].get(&key) {
+                    return backoff + entry.log_prob;
+                }
+                key.pop();
+                if !key.is_empty() {
+                    if let Some(entry) = self.ngrams[key.len() - 1].get(&key) {
+                        backoff += entry.backoff;
+                    }
+                }
+            }
+        }
+        backoff + UNKNOWN_WORD_LOG10_PROB * LN_10
+    }
+
+    fn lookup(&self, word: &str) -> Option<u32> {
+        self.word_ids.get(word).copied()
+    }
+
+    fn intern(&mut self, word: &str) -> u32 {
+        if let Some(id) = self.word_ids.get(word) {
+            return *id;
+        }
+        let id = self.words.len() as u32;
+        self.words.push(word.to_string());
+        self.word_ids.insert(word.to_string(), id);
+        id
+    }
+}
+
+/// Shallow fusion of an n-gram LM into CTC beam search. A word is scored when the
+/// next word-initial token arrives, or at the end of decoding for the last word. The
+/// words it conditions on travel in the beam as its [`LanguageModelState`].
+pub struct LanguageModelScorer<'a> {
+    lm: &'a NgramLanguageModel,
+    vocab: &'a [String],
+    alpha: f32,
+    beta: f32,
+}
+
+/// Word context of one prefix.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LanguageModelState {
+    /// Most recent finished words, as many as the language model conditions on.
+    words: Vec<String>,
+    /// Text of the word still being spelled out.
+    partial_word: String,
+}
+
+impl<'a> LanguageModelScorer<'a> {
+    pub fn new(lm: &'a NgramLanguageModel, vocab: &'a [String], alpha: f32, beta: f32) -> Self {
+        Self {
+            lm,
+            vocab,
+            alpha,
+            beta,
+        }
+    }
+
+    /// Scores the word spelled out in `state` and moves it into the word history.
+    /// Returns no score for a word that normalizes to nothing, such as a lone dash.
+    fn finish_word(&self, state: &LanguageModelState) -> (f32, Vec<String>) {
+        let word = normalize_lm_word(&state.partial_word);
+        if word.is_empty() {
+            return (0.0, state.words.clone());
+        }
+        let context = lm_context(&state.words, self.lm.order());
+        let score = self.alpha * self.lm.log_prob(&context, &word) + self.beta;
+        let mut words = state.words.clone();
+        words.push(word);
+        // Only the context the LM can use is kept, but at least one word so that
+        // `final_score` can tell an empty transcript from a unigram LM's context.
+        let keep = self.lm.order().saturating_sub(1).max(1);
+        if words.len() > keep {
+            words.drain(..words.len() - keep);
+        }
+        (score, words)
+    }
+}
+
+impl PrefixScorer for LanguageModelScorer<'_> {
+    type State = LanguageModelState;
+
+    fn initial_state(&self) -> LanguageModelState {
+        LanguageModelState::default()
+    }
+
+    fn extension_score(
+        &self,
+        state: &LanguageModelState,
+        token: usize,
+    ) -> (f32, LanguageModelState) {
+        let Some(text) = self.vocab.get(token) else {
+            return (0.0, state.clone());
+        };
+        if !text.starts_with(' ') {
+            let mut next = state.clone();
+            next.partial_word.push_str(text);
+            return (0.0, next);
+        }
+        let (score, words) = self.finish_word(state);
+        let next = LanguageModelState {
+            words,
+            partial_word: text.trim_start().to_string(),
+        };
+        (score, next)
+    }
+
+    fn final_score(&self, state: &LanguageModelState) -> f32 {
+        let (score, words) = self.finish_word(state);
+        if words.is_empty() {
+            return 0.0;
+        }
+        let context = lm_context(&words, self.lm.order());
+        score + self.alpha * self.lm.log_prob(&context, SENTENCE_END)
+    }
+}
+
+/// Back-off context for the next word after `history`, which holds at most the
+/// `order - 1` most recent words; shorter histories start at `<s>`.
+fn lm_context(history: &[String], order: usize) -> Vec<&str> {
+    let keep = order.saturating_sub(1);
+    let mut context = Vec::with_capacity(keep);
+    if history.len() < keep {
+        context.push(SENTENCE_START);
+    }
+    let start = history.len().saturating_sub(keep);
+    context.extend(history[start..].iter().map(String::as_str));
+    context
+}
+
+/// Whether `arpa` was modified after `binary` was written from it. Missing
+/// timestamps keep the binary.
+fn is_stale(binary: &Path, arpa: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    match (modified(binary), modified(arpa)) {
+        (Some(binary), Some(arpa)) => arpa > binary,
+        _ => false,
+    }
+}
+
+/// LM vocabularies are lowercase and unpunctuated, while the e2e model emits both.
+fn normalize_lm_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+fn parse_log10(value: Option<&str>, line_idx: usize) -> Result<f32> {
+    let value = value.ok_or_else(|| anyhow::anyhow!("ARPA line {} is empty", line_idx + 1))?;
+    let log10 = value.parse::<f32>().with_context(|| {
+        format!(
+            "Invalid ARPA probability '{value}' on line {}",
+            line_idx + 1
+        )
+    })?;
+    Ok(log10 * LN_10)
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| anyhow::anyhow!("Truncated GigaAM LM binary"))?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_f32(&mut self) -> Result<f32> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{ctc_prefix_beam_search, token_ids, DecodeOptions, DecodeStrategy};
+    use crate::test_support::ScratchDir;
+    use ndarray::Array3;
+
+    const TEST_ARPA: &str = "\\data\\
+ngram 1=5
+ngram 2=2
+
+\\1-grams:
+-1.0\t<s>\t-0.5
+-1.0\t</s>
+-0.5\tcat\t-0.2
+-3.0\tcad
+-2.0\tsat\t-0.1
+
+\\2-grams:
+-0.1\t<s> cat
+-0.3\tcat sat
+
+\\end\\
+";
+
+    fn assert_close(actual: f32, expected_log10: f32) {
+        let expected = expected_log10 * LN_10;
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn arpa_lookup_uses_highest_order_then_backs_off() -> Result<()> {
+        let lm = NgramLanguageModel::from_arpa(TEST_ARPA)?;
+
+        assert_eq!(lm.order(), 2);
+        assert_close(lm.log_prob(&["cat"], "sat"), -0.3);
+        // No "sat cat" bigram: backoff(sat) + P(cat).
+        assert_close(lm.log_prob(&["sat"], "cat"), -0.1 + -0.5);
+        // Unseen history words contribute no backoff.
+        assert_close(lm.log_prob(&["dog"], "cad"), -3.0);
+        assert_close(lm.log_prob(&[], "dog"), UNKNOWN_WORD_LOG10_PROB);
+        Ok(())
+    }
+
+    #[test]
+    fn binary_form_round_trips_arpa_model() -> Result<()> {
+        let lm = NgramLanguageModel::from_arpa(TEST_ARPA)?;
+        let mut bytes = Vec::new();
+        lm.write_binary(&mut bytes)?;
+
+        let restored = NgramLanguageModel::from_binary(&bytes)?;
+        assert_eq!(restored.order(), 2);
+        assert_eq!(restored.ngrams, lm.ngrams);
+        assert_close(restored.log_prob(&["<s>"], "cat"), -0.1);
+
+        assert!(NgramLanguageModel::from_binary(&bytes[..bytes.len() - 3]).is_err());
+        assert!(NgramLanguageModel::from_binary(b"nope").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_language_model_is_optional() -> Result<()> {
+        let dir = ScratchDir::new("lm-missing");
+        assert!(NgramLanguageModel::load_optional(&dir)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn arpa_model_is_cached_as_binary_next_to_vocab() -> Result<()> {
+        let dir = ScratchDir::new("lm-arpa");
+        fs::write(dir.join(LM_ARPA_FILENAME), TEST_ARPA)?;
+
+        let from_arpa = NgramLanguageModel::load_optional(&dir)?;
+        let binary_written = dir.join(LM_BINARY_FILENAME).exists();
+        fs::remove_file(dir.join(LM_ARPA_FILENAME))?;
+        let from_binary = NgramLanguageModel::load_optional(&dir)?;
+
+        assert!(binary_written);
+        assert_eq!(
+            from_arpa.map(|lm| lm.ngrams),
+            from_binary.map(|lm| lm.ngrams)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn replaced_arpa_rebuilds_a_stale_binary() -> Result<()> {
+        let dir = ScratchDir::new("lm-stale");
+        let arpa_path = dir.join(LM_ARPA_FILENAME);
+        fs::write(&arpa_path, TEST_ARPA)?;
+        NgramLanguageModel::load_optional(&dir)?;
+
+        let replaced = TEST_ARPA.replace("-0.3\tcat sat", "-0.7\tcat sat");
+        fs::write(&arpa_path, replaced)?;
+        let later = fs::metadata(dir.join(LM_BINARY_FILENAME))?.modified()?
+            + std::time::Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&arpa_path)?
+            .set_modified(later)?;
+        let rebuilt = NgramLanguageModel::load_optional(&dir)?;
+        fs::remove_file(&arpa_path)?;
+        let cached = NgramLanguageModel::load_optional(&dir)?;
+
+        assert_close(rebuilt.unwrap().log_prob(&["cat"], "sat"), -0.7);
+        assert_close(cached.unwrap().log_prob(&["cat"], "sat"), -0.7);
+        Ok(())
+    }
+
+    #[test]
+    fn scorer_state_carries_the_word_context() -> Result<()> {
+        let lm = NgramLanguageModel::from_arpa(TEST_ARPA)?;
+        let vocab = vec![" c".to_string(), "at".to_string(), " sat".to_string()];
+        let scorer = LanguageModelScorer::new(&lm, &vocab, 1.0, 0.5);
+
+        let (score, state) = scorer.extension_score(&scorer.initial_state(), 0);
+        assert_eq!(score, 0.0);
+        let (score, state) = scorer.extension_score(&state, 1);
+        assert_eq!((score, state.partial_word.as_str()), (0.0, "cat"));
+        // "sat" finishes "cat", scored after <s>.
+        let (score, state) = scorer.extension_score(&state, 2);
+        assert_close(score - 0.5, -0.1);
+        assert_eq!(state.words, vec!["cat".to_string()]);
+        // The final word follows "cat", then </s> follows "sat", which backs off.
+        let expected = (-0.3 + 0.5 / LN_10) + (-0.1 + -1.0);
+        assert_close(scorer.final_score(&state), expected);
+        assert_eq!(scorer.final_score(&scorer.initial_state()), 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn shallow_fusion_prefers_language_model_spelling() -> Result<()> {
+        let lm = NgramLanguageModel::from_arpa(TEST_ARPA)?;
+        let vocab = vec![" cat".to_string(), " cad".to_string(), "<blk>".to_string()];
+        // "cad" is acoustically slightly ahead of "cat".
+        let logits = Array3::from_shape_vec((1, 1, 3), vec![1.0, 1.2, -2.0])?;
+        let options = DecodeOptions {
+            strategy: DecodeStrategy::BeamSearch,
+            beam_width: 4,
+            ..DecodeOptions::default()
+        };
+
+        assert_eq!(
+            token_ids(&ctc_prefix_beam_search(logits.view(), 1, 2, &options, &[])),
+            vec![1]
+        );
+        let scorer = LanguageModelScorer::new(&lm, &vocab, 1.0, 0.0);
+        assert_eq!(
+            token_ids(&ctc_prefix_beam_search(
+                logits.view(),
+                1,
+                2,
+                &options,
+                &[&scorer]
+            )),
+            vec![0]
+        );
+        Ok(())
+    }
+}
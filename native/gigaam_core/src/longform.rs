@@ -0,0 +1,387 @@
+use crate::timestamps::{word_timings, TokenTiming};
+use crate::vad::{SpeechSegment, VAD_SAMPLE_RATE};
+use std::ops::Range;
+
+const DEFAULT_MAX_SEGMENT_MS: u64 = 20_000;
+const DEFAULT_OVERLAP_MS: u64 = 2_000;
+/// Largest window or overlap accepted from the host, so that a bad value cannot plan
+/// windows longer than any recording.
+pub const MAX_SEGMENT_MS: u64 = 300_000;
+/// Shortest window accepted from the host. Much shorter windows leave the encoder too
+/// few samples for a single feature frame and transcribe to nothing.
+pub const MIN_SEGMENT_MS: u64 = 1_000;
+/// How far from a seam a word may start and still be taken for the previous chunk's
+/// last word seen again.
+const SEAM_REPEAT_MS: u64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongFormOptions {
+    /// Longest audio fed to the encoder in one run; 0 disables long-form splitting.
+    pub max_segment_ms: u64,
+    /// Overlap between fixed windows, used only where no pause allows a clean cut.
+    pub overlap_ms: u64,
+}
+
+impl Default for LongFormOptions {
+    fn default() -> Self {
+        Self {
+            max_segment_ms: DEFAULT_MAX_SEGMENT_MS,
+            overlap_ms: DEFAULT_OVERLAP_MS,
+        }
+    }
+}
+
+impl LongFormOptions {
+    /// Builds options from host-supplied values; negative values keep the defaults, a
+    /// nonzero window is raised to [`MIN_SEGMENT_MS`] and both are capped at
+    /// [`MAX_SEGMENT_MS`].
+    pub fn from_values(max_segment_ms: i32, overlap_ms: i32) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_segment_ms: match u64::try_from(max_segment_ms) {
+                Ok(0) => 0,
+                Ok(value) => value.clamp(MIN_SEGMENT_MS, MAX_SEGMENT_MS),
+                Err(_) => defaults.max_segment_ms,
+            },
+            overlap_ms: u64::try_from(overlap_ms)
+                .unwrap_or(defaults.overlap_ms)
+                .min(MAX_SEGMENT_MS),
+        }
+    }
+
+    pub fn max_segment_samples(&self) -> usize {
+        ms_to_samples(self.max_segment_ms)
+    }
+}
+
+/// Planned encoder input; `end_sample` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioChunk {
+    pub start_sample: usize,
+    pub end_sample: usize,
+}
+
+impl AudioChunk {
+    pub fn start_ms(&self) -> u64 {
+        samples_to_ms(self.start_sample)
+    }
+}
+
+/// Splits `range` into chunks of at most `max_segment_ms`. Cuts go into the middle of
+/// the latest pause between `speech` segments that fits; stretches without a usable
+/// pause fall back to fixed windows overlapping by `overlap_ms`.
+pub fn plan_chunks(
+    range: Range<usize>,
+    speech: &[SpeechSegment],
+    options: &LongFormOptions,
+) -> Vec<AudioChunk> {
+    if range.is_empty() {
+        return Vec::new();
+    }
+    let max_len = options.max_segment_samples();
+    if max_len == 0 {
+        return vec![AudioChunk {
+            start_sample: range.start,
+            end_sample: range.end,
+        }];
+    }
+    let overlap = ms_to_samples(options.overlap_ms).min(max_len / 2);
+    // Keeps a pause right after the chunk start from producing a sliver of a chunk.
+    let min_len = max_len / 4;
+    let pauses = pauses_within(&range, speech);
+
+    let mut chunks = Vec::new();
+    let mut start = range.start;
+    while range.end - start > max_len {
+        let window = (start + min_len, start + max_len);
+        let cut = pauses.iter().rev().find_map(|&(pause_start, pause_end)| {
+            let from = pause_start.max(window.0);
+            let to = pause_end.min(window.1);
+            (from < to).then_some((from + to) / 2)
+        });
+        match cut {
+            Some(cut) => {
+                chunks.push(AudioChunk {
+                    start_sample: start,
+                    end_sample: cut,
+                });
+                start = cut;
+            }
+            None => {
+                chunks.push(AudioChunk {
+                    start_sample: start,
+                    end_sample: start + max_len,
+                });
+                start += max_len - overlap;
+            }
+        }
+    }
+    chunks.push(AudioChunk {
+        start_sample: start,
+        end_sample: range.end,
+    });
+    chunks
+}
+
+fn pauses_within(range: &Range<usize>, speech: &[SpeechSegment]) -> Vec<(usize, usize)> {
+    let mut pauses = Vec::new();
+    let mut previous_end = range.start;
+    for segment in speech {
+        let segment_start = segment.start_sample.clamp(range.start, range.end);
+        if segment_start > previous_end {
+            pauses.push((previous_end, segment_start));
+        }
+        previous_end = previous_end.max(segment.end_sample.min(range.end));
+    }
+    if range.end > previous_end {
+        pauses.push((previous_end, range.end));
+    }
+    pauses
+}
+
+/// Merges per-chunk tokens (already shifted to absolute time) into one sequence. Every
+/// chunk owns the time up to the middle of its overlap with the next one, and a word is
+/// kept by the chunk that owns its midpoint, so words are neither lost nor repeated at
+/// the seams. Words cut by a window edge always lie outside the owned part. A chunk's
+/// first word is dropped when it repeats the previous chunk's last one at the seam.
+pub fn stitch_chunks(chunks: &[AudioChunk], transcripts: &[Vec<TokenTiming>]) -> Vec<TokenTiming> {
+    let mut stitched = Vec::<TokenTiming>::new();
+    let mut last_word: Option<(String, u64)> = None;
+
+    for (index, tokens) in transcripts.iter().enumerate().take(chunks.len()) {
+        let owned_from = match index {
+            0 => 0,
+            _ => seam_ms(&chunks[index - 1], &chunks[index]),
+        };
+        let owned_until = chunks
+            .get(index + 1)
+            .map_or(u64::MAX, |next| seam_ms(&chunks[index], next));
+        let mut after_seam = index > 0;
+
+        for word in word_timings(tokens) {
+            let midpoint = (word.start_ms + word.end_ms) / 2;
+            if midpoint < owned_from || midpoint >= owned_until {
+                continue;
+            }
+            // The same word seen by both chunks with slightly different timing may land
+            // on both sides of the seam. Elsewhere a repeat ("no no") is real speech.
+            let near_seam = std::mem::take(&mut after_seam)
+                && word.start_ms.abs_diff(owned_from) <= SEAM_REPEAT_MS;
+            let repeated = near_seam
+                && last_word
+                    .as_ref()
+                    .is_some_and(|(text, end_ms)| *text == word.text && word.start_ms < *end_ms);
+            if repeated {
+                continue;
+            }
+            stitched.extend_from_slice(&tokens[word.tokens.clone()]);
+            last_word = Some((word.text, word.end_ms));
+        }
+    }
+    stitched
+}
+
+fn seam_ms(previous: &AudioChunk, next: &AudioChunk) -> u64 {
+    if next.start_sample < previous.end_sample {
+        samples_to_ms((next.start_sample + previous.end_sample) / 2)
+    } else {
+        samples_to_ms(next.start_sample)
+    }
+}
+
+/// Saturates rather than overflowing a 32-bit `usize`.
+fn ms_to_samples(ms: u64) -> usize {
+    let samples = ms.saturating_mul(VAD_SAMPLE_RATE as u64) / 1000;
+    usize::try_from(samples).unwrap_or(usize::MAX)
+}
+
+fn samples_to_ms(samples: usize) -> u64 {
+    (samples as u64 * 1000) / VAD_SAMPLE_RATE as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES_PER_MS: usize = VAD_SAMPLE_RATE / 1000;
+
+    fn token(text: &str, start_ms: u64, end_ms: u64) -> TokenTiming {
+        TokenTiming {
+            token_id: 0,
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 1.0,
+        }
+    }
+
+    // Word `i` spans [600 * i, 600 * i + 400) ms and is spelled with two tokens.
+    fn ground_truth(word_count: u64) -> Vec<TokenTiming> {
+        (0..word_count)
+            .flat_map(|i| {
+                let start = 600 * i;
+                [
+                    token(&format!(" w{i}"), start, start + 200),
+                    token("x", start + 200, start + 400),
+                ]
+            })
+            .collect()
+    }
+
+    // What a chunk "hears": words fully inside it are exact, words cut by an edge come
+    // out garbled.
+    fn recognize(truth: &[TokenTiming], chunk: &AudioChunk) -> Vec<TokenTiming> {
+        let (from, to) = (chunk.start_ms(), samples_to_ms(chunk.end_sample));
+        truth
+            .chunks(2)
+            .filter(|word| word[1].end_ms > from && word[0].start_ms < to)
+            .flat_map(|word| {
+                if word[0].start_ms >= from && word[1].end_ms <= to {
+                    word.to_vec()
+                } else {
+                    vec![token(
+                        " garbled",
+                        word[0].start_ms.max(from),
+                        word[1].end_ms.min(to),
+                    )]
+                }
+            })
+            .collect()
+    }
+
+    fn segment(start_ms: usize, end_ms: usize) -> SpeechSegment {
+        SpeechSegment {
+            start_sample: start_ms * SAMPLES_PER_MS,
+            end_sample: end_ms * SAMPLES_PER_MS,
+            start_ms: start_ms as u64,
+            end_ms: end_ms as u64,
+        }
+    }
+
+    #[test]
+    fn chunks_are_cut_in_the_latest_pause_that_fits() {
+        let options = LongFormOptions {
+            max_segment_ms: 10_000,
+            overlap_ms: 1_000,
+        };
+        let speech = [
+            segment(0, 4_000),
+            segment(5_000, 9_000),
+            segment(9_400, 16_000),
+        ];
+        let chunks = plan_chunks(0..16_000 * SAMPLES_PER_MS, &speech, &options);
+
+        let spans = chunks
+            .iter()
+            .map(|chunk| (chunk.start_ms(), samples_to_ms(chunk.end_sample)))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(0, 9_200), (9_200, 16_000)]);
+    }
+
+    #[test]
+    fn speech_without_pauses_falls_back_to_overlapping_windows() {
+        let options = LongFormOptions {
+            max_segment_ms: 4_000,
+            overlap_ms: 1_000,
+        };
+        let chunks = plan_chunks(0..10_000 * SAMPLES_PER_MS, &[segment(0, 10_000)], &options);
+
+        let spans = chunks
+            .iter()
+            .map(|chunk| (chunk.start_ms(), samples_to_ms(chunk.end_sample)))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(0, 4_000), (3_000, 7_000), (6_000, 10_000)]);
+        assert!(plan_chunks(0..0, &[], &options).is_empty());
+    }
+
+    #[test]
+    fn stitching_overlapping_windows_reproduces_the_token_sequence() {
+        let truth = ground_truth(20);
+        let total_ms = 600 * 20;
+        for (max_segment_ms, overlap_ms) in [(3_000, 1_000), (4_000, 2_000), (5_000, 1_200)] {
+            let options = LongFormOptions {
+                max_segment_ms,
+                overlap_ms,
+            };
+            let chunks = plan_chunks(
+                0..total_ms * SAMPLES_PER_MS,
+                &[segment(0, total_ms)],
+                &options,
+            );
+            assert!(chunks.len() > 2);
+            let transcripts = chunks
+                .iter()
+                .map(|chunk| recognize(&truth, chunk))
+                .collect::<Vec<_>>();
+
+            assert_eq!(stitch_chunks(&chunks, &transcripts), truth, "{options:?}");
+        }
+    }
+
+    #[test]
+    fn stitching_drops_a_word_seen_on_both_sides_of_the_seam() {
+        let chunks = [
+            AudioChunk {
+                start_sample: 0,
+                end_sample: 3_000 * SAMPLES_PER_MS,
+            },
+            AudioChunk {
+                start_sample: 2_000 * SAMPLES_PER_MS,
+                end_sample: 5_000 * SAMPLES_PER_MS,
+            },
+        ];
+        // The seam is at 2.5 s; each chunk places "two" on its own side of it.
+        let transcripts = [
+            vec![token(" one", 1_000, 1_400), token(" two", 2_200, 2_700)],
+            vec![token(" two", 2_400, 2_900), token(" three", 3_200, 3_600)],
+        ];
+
+        let texts = stitch_chunks(&chunks, &transcripts)
+            .into_iter()
+            .map(|token| token.text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec![" one", " two", " three"]);
+    }
+
+    #[test]
+    fn stitching_keeps_a_repeated_word_away_from_the_seam() {
+        let chunks = [
+            AudioChunk {
+                start_sample: 0,
+                end_sample: 3_000 * SAMPLES_PER_MS,
+            },
+            AudioChunk {
+                start_sample: 2_000 * SAMPLES_PER_MS,
+                end_sample: 8_000 * SAMPLES_PER_MS,
+            },
+        ];
+        // Timestamp jitter makes each second "no" start before the first one ends.
+        let transcripts = [
+            vec![token(" no", 1_000, 1_400), token(" no", 1_300, 1_700)],
+            vec![token(" no", 5_000, 5_400), token(" no", 5_300, 5_700)],
+        ];
+
+        let texts = stitch_chunks(&chunks, &transcripts)
+            .into_iter()
+            .map(|token| token.text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec![" no"; 4]);
+    }
+
+    #[test]
+    fn host_values_are_clamped() {
+        let options = LongFormOptions::from_values(i32::MAX, i32::MAX);
+        assert_eq!(options.max_segment_ms, MAX_SEGMENT_MS);
+        assert_eq!(options.overlap_ms, MAX_SEGMENT_MS);
+        assert_eq!(options.max_segment_samples(), 300_000 * SAMPLES_PER_MS);
+        assert_eq!(
+            LongFormOptions::from_values(-1, -1),
+            LongFormOptions::default()
+        );
+        assert_eq!(
+            LongFormOptions::from_values(20, 0).max_segment_ms,
+            MIN_SEGMENT_MS
+        );
+        assert_eq!(LongFormOptions::from_values(0, 0).max_segment_ms, 0);
+    }
+}
@@ -0,0 +1,460 @@
+use crate::error::{BridgeError, ErrorCode};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+
+const MODEL_INT8_ID: &str = "gigaam-v3-e2e-ctc-int8";
+const MODEL_FULL_ID: &str = "gigaam-v3-e2e-ctc";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelArchitecture {
+    Ctc,
+    /// Transducer exported as separate encoder (`model`), `decoder` and `joint` graphs.
+    Rnnt,
+}
+
+impl ModelArchitecture {
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::Ctc => "ctc",
+            Self::Rnnt => "rnnt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VocabFormat {
+    /// One `<token> <id>` pair per line, `▁` marking a word start and `<blk>` the blank.
+    #[default]
+    TokenIdLines,
+}
+
+/// A file of the model directory, relative to it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelFile {
+    pub path: String,
+    /// Hex SHA-256, checked by [`ModelManifest::verify_checksums`].
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// Frontend settings; every field set here overrides the model's YAML config and takes
+/// the same values as its `preprocessor` key.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Preprocessing {
+    pub n_mels: Option<usize>,
+    pub win_length: Option<usize>,
+    pub hop_length: Option<usize>,
+    pub n_fft: Option<usize>,
+    pub center: Option<bool>,
+    pub pad_mode: Option<String>,
+    pub mel_scale: Option<String>,
+    pub mel_norm: Option<String>,
+    pub f_min: Option<f64>,
+    pub f_max: Option<f64>,
+    pub preemph: Option<f64>,
+    pub dither: Option<f64>,
+    pub subsampling_factor: Option<usize>,
+    /// Rounds the Hann window and mel filters to bfloat16, as the GigaAM v3 checkpoints
+    /// store them. Not a YAML key; when unset it is on for configs whose `model_name`
+    /// contains `v3` and for the uncentered 320-point STFT of those checkpoints.
+    pub quantize_bf16: Option<bool>,
+}
+
+/// Contents of `manifest.json` in a model directory. Directories without one are still
+/// accepted for the bundled GigaAM v3 CTC models, which get a built-in manifest.
+/// Unknown keys are rejected at every level, so a misspelt one is not silently ignored.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelManifest {
+    #[serde(default = "default_manifest_version")]
+    pub manifest_version: u32,
+    pub id: String,
+    pub architecture: ModelArchitecture,
+    pub model: ModelFile,
+    pub vocab: ModelFile,
+    /// Prediction network of an RNN-T model.
+    #[serde(default)]
+    pub decoder: Option<ModelFile>,
+    /// Joint network of an RNN-T model.
+    #[serde(default)]
+    pub joint: Option<ModelFile>,
+    #[serde(default)]
+    pub config: Option<ModelFile>,
+    /// Input rate of the model; when absent the YAML config decides.
+    #[serde(default)]
+    pub sample_rate: Option<usize>,
+    #[serde(default)]
+    pub vocab_format: VocabFormat,
+    #[serde(default)]
+    pub preprocessing: Preprocessing,
+}
+
+fn default_manifest_version() -> u32 {
+    MANIFEST_VERSION
+}
+
+impl ModelManifest {
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest = serde_json::from_str::<Self>(content).context("Invalid model manifest")?;
+        manifest.check()?;
+        Ok(manifest)
+    }
+
+    /// Manifest of a bundled model id, used when its directory carries no manifest.
+    pub fn builtin(model_id: &str) -> Option<Self> {
+        let onnx = match model_id {
+            MODEL_INT8_ID => "v3_e2e_ctc.int8.onnx",
+            MODEL_FULL_ID => "v3_e2e_ctc.onnx",
+            _ => return None,
+        };
+        let file = |path: &str| ModelFile {
+            path: path.to_string(),
+            sha256: None,
+        };
+        Some(Self {
+            manifest_version: MANIFEST_VERSION,
+            id: model_id.to_string(),
+            architecture: ModelArchitecture::Ctc,
+            model: file(onnx),
+            vocab: file("v3_e2e_ctc_vocab.txt"),
+            decoder: None,
+            joint: None,
+            config: Some(file("v3_e2e_ctc.yaml")),
+            sample_rate: Some(16_000),
+            vocab_format: VocabFormat::TokenIdLines,
+            preprocessing: Preprocessing {
+                quantize_bf16: Some(true),
+                ..Preprocessing::default()
+            },
+        })
+    }
+
+    /// Reads the manifest of `model_dir`. Without `manifest.json` the directory is
+    /// matched against the bundled models by the ONNX file it contains.
+    pub fn from_directory(model_dir: &Path) -> Result<Self> {
+        let manifest_path = model_dir.join(MANIFEST_FILENAME);
+        if manifest_path.exists() {
+            let content = fs::read_to_string(&manifest_path).with_context(|| {
+                format!("Failed to read model manifest: {}", manifest_path.display())
+            })?;
+            return Self::parse(&content)
+                .with_context(|| format!("In {}", manifest_path.display()));
+        }
+        [MODEL_INT8_ID, MODEL_FULL_ID]
+            .into_iter()
+            .filter_map(Self::builtin)
+            .find(|manifest| model_dir.join(&manifest.model.path).exists())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No {MANIFEST_FILENAME} and no bundled GigaAM model in {}",
+                    model_dir.display()
+                )
+            })
+    }
+
+    /// Locates `model_id` under `models_root`: `<root>/<id>` first, then any
+    /// subdirectory whose manifest declares that id. Bundled ids resolve to `<root>/<id>`
+    /// even before the model is downloaded.
+    pub fn find(models_root: &Path, model_id: &str) -> Result<Option<(PathBuf, Self)>> {
+        if !is_safe_id(model_id) {
+            return Ok(None);
+        }
+        let direct = models_root.join(model_id);
+        if direct.join(MANIFEST_FILENAME).exists() {
+            let manifest = Self::from_directory(&direct)?;
+            if manifest.id == model_id {
+                return Ok(Some((direct, manifest)));
+            }
+        } else if let Some(manifest) = Self::builtin(model_id) {
+            return Ok(Some((direct, manifest)));
+        }
+        Ok(discover(models_root)
+            .into_iter()
+            .find_map(|(dir, manifest)| match manifest {
+                Ok(manifest) if manifest.id == model_id => Some((dir, manifest)),
+                _ => None,
+            }))
+    }
+
+    pub fn vocab_path(&self, model_dir: &Path) -> PathBuf {
+        model_dir.join(&self.vocab.path)
+    }
+
+    pub fn config_path(&self, model_dir: &Path) -> Option<PathBuf> {
+        self.config
+            .as_ref()
+            .map(|config| model_dir.join(&config.path))
+    }
+
+    fn files(&self) -> impl Iterator<Item = &ModelFile> {
+        [&self.model, &self.vocab]
+            .into_iter()
+            .chain(&self.config)
+            .chain(&self.decoder)
+            .chain(&self.joint)
+    }
+
+    /// Checks that the directory and every declared file exist.
+    pub fn validate_files(&self, model_dir: &Path) -> Result<()> {
+        let missing = |message: String| BridgeError::new(ErrorCode::ModelMissing, message).into();
+        if !model_dir.is_dir() {
+            return Err(missing(format!(
+                "Model directory does not exist: {}",
+                model_dir.display()
+            )));
+        }
+        for file in self.files() {
+            let path = model_dir.join(&file.path);
+            if !path.is_file() {
+                return Err(missing(format!(
+                    "Required file not found: {}",
+                    path.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Hashes every file that declares a checksum. Reads the whole model, so this is
+    /// meant for post-download checks rather than every load.
+    pub fn verify_checksums(&self, model_dir: &Path) -> Result<()> {
+        for file in self.files() {
+            let Some(expected) = &file.sha256 else {
+                continue;
+            };
+            let path = model_dir.join(&file.path);
+            let actual = sha256_hex(&path)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                anyhow::bail!(
+                    "Checksum mismatch for {}: expected {expected}, got {actual}",
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.manifest_version > MANIFEST_VERSION {
+            anyhow::bail!(
+                "Manifest version {} is newer than the supported {MANIFEST_VERSION}",
+                self.manifest_version
+            );
+        }
+        if !is_safe_id(&self.id) {
+            anyhow::bail!("Invalid model id: {:?}", self.id);
+        }
+        if self.sample_rate == Some(0) {
+            anyhow::bail!("Model sample rate must be positive");
+        }
+        let has_transducer_files = (self.decoder.is_some(), self.joint.is_some());
+        match (self.architecture, has_transducer_files) {
+            (ModelArchitecture::Ctc, (false, false)) | (ModelArchitecture::Rnnt, (true, true)) => {}
+            (ModelArchitecture::Ctc, _) => {
+                anyhow::bail!("CTC models must not declare decoder or joint files")
+            }
+            (ModelArchitecture::Rnnt, _) => {
+                anyhow::bail!("RNN-T models need both decoder and joint files")
+            }
+        }
+        for file in self.files() {
+            let relative = Path::new(&file.path);
+            let inside = !file.path.is_empty()
+                && relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)));
+            if !inside {
+                anyhow::bail!(
+                    "Model file must be a path inside the model directory: {:?}",
+                    file.path
+                );
+            }
+            let valid_checksum = file.sha256.as_ref().is_none_or(|sha256| {
+                sha256.len() == 64 && sha256.chars().all(|ch| ch.is_ascii_hexdigit())
+            });
+            if !valid_checksum {
+                anyhow::bail!("Invalid SHA-256 for {}", file.path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every model directory under `models_root`, sorted by directory name, with its
+/// manifest or the reason it could not be read. Directories that are neither described
+/// by a manifest nor named after a bundled model are skipped.
+pub fn discover(models_root: &Path) -> Vec<(PathBuf, Result<ModelManifest>)> {
+    let Ok(entries) = fs::read_dir(models_root) else {
+        return Vec::new();
+    };
+    let mut dirs = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs.into_iter()
+        .filter_map(|dir| {
+            if dir.join(MANIFEST_FILENAME).exists() {
+                let manifest = ModelManifest::from_directory(&dir);
+                return Some((dir, manifest));
+            }
+            let name = dir.file_name()?.to_str()?;
+            let manifest = ModelManifest::builtin(name)?;
+            Some((dir, Ok(manifest)))
+        })
+        .collect()
+}
+
+fn is_safe_id(model_id: &str) -> bool {
+    !model_id.is_empty()
+        && model_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        && !model_id.starts_with('.')
+}
+
+fn sha256_hex(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0_u8; 1 << 16];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    const CUSTOM_MANIFEST: &str = r#"{
+        "id": "custom-ctc",
+        "architecture": "ctc",
+        "model": {"path": "encoder.onnx"},
+        "vocab": {"path": "tokens.txt", "sha256": "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824"},
+        "sample_rate": 16000,
+        "preprocessing": {"n_mels": 80, "center": true, "pad_mode": "constant", "f_min": 20}
+    }"#;
+
+    #[test]
+    fn manifests_parse_with_defaults_and_reject_unsafe_entries() {
+        let manifest = ModelManifest::parse(CUSTOM_MANIFEST).unwrap();
+        assert_eq!(manifest.id, "custom-ctc");
+        assert_eq!(manifest.architecture, ModelArchitecture::Ctc);
+        assert_eq!(manifest.vocab_format, VocabFormat::TokenIdLines);
+        assert_eq!(manifest.config, None);
+        assert_eq!(manifest.preprocessing.n_mels, Some(80));
+        assert_eq!(manifest.preprocessing.hop_length, None);
+        assert_eq!(manifest.preprocessing.pad_mode.as_deref(), Some("constant"));
+        assert_eq!(manifest.preprocessing.f_min, Some(20.0));
+
+        let escaping = CUSTOM_MANIFEST.replace("encoder.onnx", "../encoder.onnx");
+        assert!(ModelManifest::parse(&escaping).is_err());
+        let bad_id = CUSTOM_MANIFEST.replace("custom-ctc", "../custom");
+        assert!(ModelManifest::parse(&bad_id).is_err());
+        let bad_sha = CUSTOM_MANIFEST.replace("2CF24DBA", "XYZ");
+        assert!(ModelManifest::parse(&bad_sha).is_err());
+        let tdt = CUSTOM_MANIFEST.replace("\"ctc\"", "\"tdt\"");
+        assert!(ModelManifest::parse(&tdt).is_err());
+        let with_joint =
+            CUSTOM_MANIFEST.replacen('{', "{\"joint\": {\"path\": \"joint.onnx\"},", 1);
+        assert!(ModelManifest::parse(&with_joint).is_err());
+        let future = CUSTOM_MANIFEST.replacen('{', "{\"manifest_version\": 99,", 1);
+        assert!(ModelManifest::parse(&future).is_err());
+        let misspelt = CUSTOM_MANIFEST.replace("\"sample_rate\"", "\"sample_rte\"");
+        assert!(ModelManifest::parse(&misspelt).is_err());
+        let misspelt = CUSTOM_MANIFEST.replace("\"f_min\"", "\"fmin\"");
+        assert!(ModelManifest::parse(&misspelt).is_err());
+    }
+
+    #[test]
+    fn rnnt_manifests_need_decoder_and_joint() {
+        let rnnt = CUSTOM_MANIFEST.replace("\"ctc\"", "\"rnnt\"");
+        assert!(ModelManifest::parse(&rnnt).is_err());
+        let rnnt = rnnt.replacen(
+            '{',
+            "{\"decoder\": {\"path\": \"decoder.onnx\"}, \"joint\": {\"path\": \"joint.onnx\"},",
+            1,
+        );
+        let manifest = ModelManifest::parse(&rnnt).unwrap();
+        assert_eq!(manifest.architecture, ModelArchitecture::Rnnt);
+        assert_eq!(
+            manifest
+                .decoder
+                .as_ref()
+                .map(|decoder| decoder.path.as_str()),
+            Some("decoder.onnx")
+        );
+        assert_eq!(manifest.files().count(), 4);
+    }
+
+    #[test]
+    fn custom_models_are_found_by_manifest_id_and_checksummed() {
+        let root = ScratchDir::new("manifest-custom");
+        let dir = root.join("downloaded-42");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILENAME), CUSTOM_MANIFEST).unwrap();
+        fs::write(dir.join("encoder.onnx"), b"onnx").unwrap();
+        fs::write(dir.join("tokens.txt"), b"hello").unwrap();
+
+        let (found_dir, manifest) = ModelManifest::find(&root, "custom-ctc").unwrap().unwrap();
+        assert_eq!(found_dir, dir);
+        manifest.validate_files(&dir).unwrap();
+        manifest.verify_checksums(&dir).unwrap();
+
+        fs::write(dir.join("tokens.txt"), b"hellO").unwrap();
+        assert!(manifest.verify_checksums(&dir).is_err());
+        fs::remove_file(dir.join("encoder.onnx")).unwrap();
+        assert!(manifest.validate_files(&dir).is_err());
+
+        assert!(ModelManifest::find(&root, "unknown").unwrap().is_none());
+        assert!(ModelManifest::find(&root, "../downloaded-42")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn bundled_models_work_without_a_manifest() {
+        let root = ScratchDir::new("manifest-bundled");
+        let (dir, manifest) = ModelManifest::find(&root, MODEL_FULL_ID).unwrap().unwrap();
+        assert_eq!(dir, root.join(MODEL_FULL_ID));
+        assert!(manifest.validate_files(&dir).is_err());
+
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["v3_e2e_ctc.onnx", "v3_e2e_ctc_vocab.txt", "v3_e2e_ctc.yaml"] {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        manifest.validate_files(&dir).unwrap();
+        assert_eq!(
+            ModelManifest::from_directory(&dir).unwrap().id,
+            MODEL_FULL_ID
+        );
+
+        let listed = discover(&root);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].1.as_ref().unwrap().id, MODEL_FULL_ID);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::gigaam::NativeTranscriptionReport;
+use crate::providers::ProviderSummary;
+use serde::Serialize;
+
+/// What it took to make a model ready, recorded once per load.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ModelLoadMetrics {
+    /// Providers of the main session and the nodes they ran.
+    pub provider: ProviderSummary,
+    /// Reading and parsing the vocab, YAML config and language model.
+    pub assets_ms: u128,
+    /// Creating every ONNX Runtime session of the model.
+    pub session_ms: u128,
+    pub total_ms: u128,
+}
+
+/// Phases of one transcription call, from copying the PCM out of the JVM to the
+/// decoded text, kept as the last profiling summary.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TranscriptionMetrics {
+    pub warmup: bool,
+    /// Duration of the 16 kHz audio handed to the model.
+    pub audio_ms: u64,
+    pub feature_frames: usize,
+    pub encoder_frames: usize,
+    /// Model load time spent in this call; 0 when the loaded model was reused.
+    pub load_ms: u128,
+    /// The load of the model that ran, which may have happened in an earlier call.
+    pub model_load: ModelLoadMetrics,
+    pub pcm_copy_ms: u128,
+    pub pcm_to_f32_ms: u128,
+    pub resample_ms: u128,
+    pub vad_ms: u128,
+    pub feature_extraction_ms: u128,
+    pub ort_run_ms: u128,
+    pub decode_ms: u128,
+    /// Inference time, from VAD to decoding.
+    pub total_ms: u128,
+    /// The whole call, including the PCM copy and a model load.
+    pub wall_ms: u128,
+    /// `total_ms / audio_ms`; below 1 is faster than real time, 0 without audio.
+    pub real_time_factor: f64,
+    pub confidence: f32,
+    pub word_confidences: Vec<f32>,
+}
+
+impl TranscriptionMetrics {
+    /// Inference phases, frame counts and confidences of `report` over `sample_count`
+    /// samples at `sample_rate`. The host-side phases are left at 0 for the caller.
+    pub fn from_report(
+        report: &NativeTranscriptionReport,
+        sample_count: usize,
+        sample_rate: usize,
+        model_load: ModelLoadMetrics,
+    ) -> Self {
+        let timings = &report.timings;
+        let audio_ms = (sample_count as u64 * 1000) / sample_rate.max(1) as u64;
+        let real_time_factor = if audio_ms == 0 {
+            0.0
+        } else {
+            timings.total_ms as f64 / audio_ms as f64
+        };
+        Self {
+            audio_ms,
+            feature_frames: timings.feature_frames,
+            encoder_frames: timings.encoder_frames,
+            model_load,
+            vad_ms: timings.vad_ms,
+            feature_extraction_ms: timings.feature_extraction_ms,
+            ort_run_ms: timings.ort_run_ms,
+            decode_ms: timings.decode_ms,
+            total_ms: timings.total_ms,
+            real_time_factor,
+            confidence: report.confidence,
+            word_confidences: report.words.iter().map(|word| word.confidence).collect(),
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gigaam::NativeTranscriptionTimings;
+    use crate::timestamps::WordTiming;
+
+    #[test]
+    fn metrics_serialize_every_phase() {
+        let report = NativeTranscriptionReport {
+            text: "да".to_string(),
+            confidence: 0.5,
+            tokens: Vec::new(),
+            words: vec![WordTiming {
+                text: "да".to_string(),
+                start_ms: 0,
+                end_ms: 80,
+                confidence: 0.5,
+                tokens: 0..0,
+            }],
+            alternatives: Vec::new(),
+            word_alternatives: Vec::new(),
+            timings: NativeTranscriptionTimings {
+                vad_ms: 1,
+                feature_extraction_ms: 2,
+                ort_run_ms: 30,
+                decode_ms: 4,
+                total_ms: 40,
+                feature_frames: 99,
+                encoder_frames: 25,
+            },
+        };
+        let model_load = ModelLoadMetrics {
+            provider: ProviderSummary {
+                mode: "cpu",
+                execution_provider: "CPUExecutionProvider",
+                ..ProviderSummary::default()
+            },
+            assets_ms: 5,
+            session_ms: 60,
+            total_ms: 70,
+        };
+        let mut metrics = TranscriptionMetrics::from_report(&report, 16_000, 16_000, model_load);
+        metrics.pcm_copy_ms = 3;
+        metrics.wall_ms = 45;
+        assert_eq!(metrics.audio_ms, 1000);
+        assert_eq!(metrics.real_time_factor, 0.04);
+
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["warmup"], false);
+        assert_eq!(json["feature_frames"], 99);
+        assert_eq!(json["encoder_frames"], 25);
+        assert_eq!(json["pcm_copy_ms"], 3);
+        assert_eq!(json["ort_run_ms"], 30);
+        assert_eq!(json["wall_ms"], 45);
+        assert_eq!(json["word_confidences"], serde_json::json!([0.5]));
+        assert_eq!(json["model_load"]["provider"]["mode"], "cpu");
+        assert_eq!(
+            json["model_load"]["provider"]["execution_provider"],
+            "CPUExecutionProvider"
+        );
+        assert_eq!(json["model_load"]["session_ms"], 60);
+
+        let silent =
+            TranscriptionMetrics::from_report(&report, 0, 16_000, ModelLoadMetrics::default());
+        assert_eq!(silent.real_time_factor, 0.0);
+    }
+}
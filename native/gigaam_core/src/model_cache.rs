@@ -0,0 +1,239 @@
+use crate::gigaam::RuntimeOptions;
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Directory of ONNX Runtime optimized models, so that later loads skip most graph
+/// optimization. Entries are regenerated on a miss, so the host may point this at a
+/// cache directory the OS is free to clear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelCache {
+    dir: PathBuf,
+}
+
+/// The optimized copy of one model for one set of runtime options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// `<model stem>-<directory hash>-<model hash>-<options hash>.onnx` inside the
+    /// cache directory.
+    path: PathBuf,
+    /// `<model stem>-<directory hash>`, shared by every revision of the model file.
+    source: String,
+    model_hash: String,
+}
+
+impl ModelCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Entry for `model_path` loaded with `runtime_options`. The model is identified by
+    /// its manifest `checksum`, or by size and modification time when it declares none,
+    /// so a replaced model never reuses a stale optimized copy. Entries also carry a hash
+    /// of the model directory, so models of different manifests that share a file name
+    /// (`model.onnx`, `decoder.onnx`, ...) keep separate copies.
+    pub fn entry(
+        &self,
+        model_path: &Path,
+        checksum: Option<&str>,
+        runtime_options: &RuntimeOptions,
+    ) -> Result<CacheEntry> {
+        let fingerprint = match checksum {
+            Some(checksum) => checksum.to_ascii_lowercase(),
+            None => {
+                let metadata = fs::metadata(model_path)
+                    .with_context(|| format!("Failed to stat {}", model_path.display()))?;
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|since| since.as_nanos())
+                    .unwrap_or_default();
+                format!("{}-{modified}", metadata.len())
+            }
+        };
+        let stem = model_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("model");
+        let model_dir = model_path.parent().unwrap_or(Path::new("."));
+        let model_dir = fs::canonicalize(model_dir).unwrap_or_else(|_| model_dir.to_path_buf());
+        let source = format!("{stem}-{}", short_hash(&model_dir.to_string_lossy()));
+        let model_hash = short_hash(&fingerprint);
+        let options_hash = short_hash(&format!(
+            "{};ort-api={}",
+            runtime_options.cache_fragment(),
+            ort::MINOR_VERSION
+        ));
+        Ok(CacheEntry {
+            path: self
+                .dir
+                .join(format!("{source}-{model_hash}-{options_hash}.onnx")),
+            source,
+            model_hash,
+        })
+    }
+}
+
+impl CacheEntry {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.is_file()
+    }
+
+    /// Where ONNX Runtime writes the optimized model before [`Self::publish`] moves it
+    /// into place, so an interrupted save never leaves a truncated entry.
+    pub fn staging_path(&self) -> Result<PathBuf> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create model cache {}", dir.display()))?;
+        Ok(self.path.with_extension("onnx.tmp"))
+    }
+
+    /// Moves the staged model into place and deletes entries of earlier revisions of
+    /// the same model file in the same directory.
+    pub fn publish(&self) -> Result<()> {
+        let staged = self.path.with_extension("onnx.tmp");
+        fs::rename(&staged, &self.path)
+            .with_context(|| format!("Failed to store optimized model {}", self.path.display()))?;
+        let Some(dir) = self.path.parent() else {
+            return Ok(());
+        };
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            let hashes = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(self.source.as_str()))
+                .and_then(|rest| rest.strip_prefix('-'))
+                .and_then(|rest| rest.strip_suffix(".onnx"))
+                .and_then(|rest| rest.split_once('-'));
+            let stale = hashes.is_some_and(|(model_hash, options_hash)| {
+                model_hash != self.model_hash
+                    && is_short_hash(model_hash)
+                    && is_short_hash(options_hash)
+            });
+            if stale {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the entry and any staged copy, e.g. after ONNX Runtime rejected it.
+    pub fn discard(&self) {
+        let _ = fs::remove_file(&self.path);
+        let _ = fs::remove_file(self.path.with_extension("onnx.tmp"));
+    }
+}
+
+/// Maps a model file read-only for one session commit. ONNX Runtime copies what it
+/// keeps, so the mapping only spares reading the file into a buffer first; it is meant
+/// to be dropped once the session exists.
+pub fn map_model(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    // SAFETY: the mapping is held only while a session is committed. Cache entries are
+    // published by rename and downloads replace a model by rename or by deleting it
+    // before copying, so a mapped file is never rewritten in place.
+    unsafe { Mmap::map(&file) }.with_context(|| format!("Failed to map {}", path.display()))
+}
+
+/// Whether the weights of `model_path` live in an external data file next to it, under
+/// the names `torch.onnx.export` and `onnx.save` give it.
+pub fn has_external_data(model_path: &Path) -> bool {
+    let Some(file_name) = model_path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let stem = file_name.strip_suffix(".onnx").unwrap_or(file_name);
+    [
+        format!("{file_name}.data"),
+        format!("{file_name}_data"),
+        format!("{stem}.data"),
+    ]
+    .iter()
+    .any(|name| model_path.with_file_name(name).is_file())
+}
+
+fn is_short_hash(value: &str) -> bool {
+    value.len() == 16 && value.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+fn short_hash(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    #[test]
+    fn entries_are_keyed_by_model_and_options_and_replace_old_revisions_of_the_same_file() {
+        let root = ScratchDir::new("cache-keys");
+        let model = root.join("v3_e2e_ctc.onnx");
+        fs::write(&model, b"onnx").unwrap();
+        let cache = ModelCache::new(root.join("optimized"));
+        let cpu = RuntimeOptions::from_ids("balanced", "cpu");
+        let auto = RuntimeOptions::from_ids("balanced", "auto");
+
+        let first = cache.entry(&model, Some("AB"), &cpu).unwrap();
+        assert_eq!(first, cache.entry(&model, Some("ab"), &cpu).unwrap());
+        let other_options = cache.entry(&model, Some("ab"), &auto).unwrap();
+        assert_ne!(first.path(), other_options.path());
+        let by_metadata = cache.entry(&model, None, &cpu).unwrap();
+        assert_ne!(first.path(), by_metadata.path());
+
+        for entry in [&first, &other_options] {
+            fs::write(entry.staging_path().unwrap(), b"optimized").unwrap();
+            entry.publish().unwrap();
+            assert!(entry.exists());
+        }
+        let revised = cache.entry(&model, Some("cd"), &cpu).unwrap();
+        fs::write(revised.staging_path().unwrap(), b"optimized").unwrap();
+        revised.publish().unwrap();
+        assert!(revised.exists());
+        assert!(!first.exists());
+        assert!(!other_options.exists());
+
+        assert_eq!(&*map_model(revised.path()).unwrap(), b"optimized");
+
+        let shared_name = root.join("other").join("v3_e2e_ctc.onnx");
+        fs::create_dir_all(shared_name.parent().unwrap()).unwrap();
+        fs::write(&shared_name, b"onnx").unwrap();
+        let neighbour = cache.entry(&shared_name, Some("ef"), &cpu).unwrap();
+        assert_ne!(revised.path(), neighbour.path());
+        fs::write(neighbour.staging_path().unwrap(), b"optimized").unwrap();
+        neighbour.publish().unwrap();
+        assert!(revised.exists());
+        assert!(neighbour.exists());
+
+        revised.discard();
+        assert!(!revised.exists());
+    }
+
+    #[test]
+    fn external_data_is_found_next_to_the_model() {
+        let root = ScratchDir::new("cache-external");
+        let model = root.join("encoder.onnx");
+        fs::write(&model, b"onnx").unwrap();
+        assert!(!has_external_data(&model));
+        for name in ["encoder.onnx.data", "encoder.onnx_data", "encoder.data"] {
+            fs::write(root.join(name), b"weights").unwrap();
+            assert!(has_external_data(&model), "{name}");
+            fs::remove_file(root.join(name)).unwrap();
+        }
+    }
+}
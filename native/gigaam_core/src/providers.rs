@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use ort::execution_providers::ExecutionProvider;
+use ort::session::builder::SessionBuilder;
+use ort::session::Session;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Arc;
+
+/// Name ONNX Runtime gives the CPU provider, in registrations and in profiles.
+pub const CPU_PROVIDER: &str = "CPUExecutionProvider";
+
+/// Outcome of registering one requested execution provider on a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderRegistration {
+    pub name: &'static str,
+    /// Whether the loaded ONNX Runtime library was built with the provider.
+    pub available: bool,
+    pub registered: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Where the graph of a session runs: what was requested, what registered and, once
+/// the first inference was profiled, how many nodes each provider executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProviderSummary {
+    pub mode: &'static str,
+    pub profile: &'static str,
+    pub intra_threads: usize,
+    pub inter_threads: usize,
+    pub parallel_execution: bool,
+    pub xnnpack_threads: usize,
+    pub memory_arena: bool,
+    pub memory_pattern: bool,
+    pub optimization_level: &'static str,
+    /// Requested providers in priority order.
+    pub requested: Vec<ProviderRegistration>,
+    /// First registered provider; ONNX Runtime offers it every node before the others.
+    pub execution_provider: &'static str,
+    /// Accelerators were requested but none of them registered.
+    pub fell_back_to_cpu: bool,
+    /// Optimized model cache status: `off`, `reused`, `saved` or `failed`.
+    pub optimized_model: &'static str,
+    /// Nodes each provider ran in the first inference; empty until that run.
+    pub node_assignment: BTreeMap<String, usize>,
+}
+
+impl ProviderSummary {
+    /// Records `registrations` and derives the effective provider from them.
+    pub fn set_registrations(&mut self, registrations: Vec<ProviderRegistration>) {
+        self.execution_provider = registrations
+            .iter()
+            .find(|registration| registration.registered)
+            .map_or(CPU_PROVIDER, |registration| registration.name);
+        self.fell_back_to_cpu = self.execution_provider == CPU_PROVIDER
+            && registrations
+                .iter()
+                .any(|registration| registration.name != CPU_PROVIDER);
+        self.requested = registrations;
+    }
+
+    /// Whether any provider other than CPU registered.
+    pub fn has_accelerator(&self) -> bool {
+        self.requested
+            .iter()
+            .any(|registration| registration.registered && registration.name != CPU_PROVIDER)
+    }
+
+    /// Ends profiling on `session`, whose first run was profiled, and records the nodes
+    /// each provider ran. Failures only cost the diagnostics and are logged.
+    pub fn record_node_assignment(&mut self, session: &mut Session) {
+        match collect_node_assignment(session) {
+            Ok(counts) => {
+                log::info!("GigaAM node assignment: {counts:?}");
+                self.node_assignment = counts;
+            }
+            Err(error) => log::warn!("GigaAM node assignment unavailable: {error:#}"),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("provider summaries contain only plain fields")
+    }
+}
+
+/// Registers `providers` on `builder` in order, one by one, so that every failure is
+/// reported instead of only logged.
+pub fn register_providers(
+    builder: &mut SessionBuilder,
+    providers: &[Arc<dyn ExecutionProvider>],
+) -> Vec<ProviderRegistration> {
+    providers
+        .iter()
+        .map(|provider| {
+            let available = provider.is_available().unwrap_or(false);
+            let error = provider
+                .register(builder)
+                .err()
+                .map(|error| error.to_string());
+            if let Some(error) = &error {
+                log::warn!("GigaAM {} not registered: {error}", provider.name());
+            }
+            ProviderRegistration {
+                name: provider.name(),
+                available,
+                registered: error.is_none(),
+                error,
+            }
+        })
+        .collect()
+}
+
+/// Ends profiling on `session` and counts the nodes each provider ran. The profile file
+/// is deleted.
+fn collect_node_assignment(session: &mut Session) -> Result<BTreeMap<String, usize>> {
+    let path = session.end_profiling()?;
+    let trace = fs::read_to_string(&path).with_context(|| format!("Failed to read {path}"));
+    let _ = fs::remove_file(&path);
+    count_node_providers(&trace?)
+}
+
+/// Counts kernel events per provider in an ONNX Runtime profile (a Chrome trace).
+fn count_node_providers(trace: &str) -> Result<BTreeMap<String, usize>> {
+    #[derive(serde::Deserialize)]
+    struct Event {
+        #[serde(default)]
+        cat: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        args: BTreeMap<String, serde_json::Value>,
+    }
+
+    let events = serde_json::from_str::<Vec<Event>>(trace).context("Invalid ORT profile")?;
+    let mut counts = BTreeMap::new();
+    for event in events {
+        if event.cat != "Node" || !event.name.ends_with("_kernel_time") {
+            continue;
+        }
+        if let Some(provider) = event.args.get("provider").and_then(|value| value.as_str()) {
+            *counts.entry(provider.to_string()).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(name: &'static str, registered: bool) -> ProviderRegistration {
+        ProviderRegistration {
+            name,
+            available: registered,
+            registered,
+            error: (!registered).then(|| "not built in".to_string()),
+        }
+    }
+
+    #[test]
+    fn summary_reports_the_first_registered_provider() {
+        let mut summary = ProviderSummary::default();
+        summary.set_registrations(vec![
+            registration("XnnpackExecutionProvider", false),
+            registration("NnapiExecutionProvider", true),
+            registration(CPU_PROVIDER, true),
+        ]);
+        assert_eq!(summary.execution_provider, "NnapiExecutionProvider");
+        assert!(!summary.fell_back_to_cpu);
+        assert!(summary.has_accelerator());
+
+        summary.set_registrations(vec![
+            registration("XnnpackExecutionProvider", false),
+            registration(CPU_PROVIDER, true),
+        ]);
+        assert_eq!(summary.execution_provider, CPU_PROVIDER);
+        assert!(summary.fell_back_to_cpu);
+        assert!(!summary.has_accelerator());
+
+        let json = serde_json::from_str::<serde_json::Value>(&summary.to_json()).unwrap();
+        assert_eq!(json["requested"][0]["error"], "not built in");
+        assert!(json["requested"][1].get("error").is_none());
+
+        summary.set_registrations(vec![registration(CPU_PROVIDER, true)]);
+        assert!(!summary.fell_back_to_cpu);
+    }
+
+    #[test]
+    fn node_assignment_counts_kernel_events() {
+        let trace = r#"[
+            {"cat": "Session", "name": "session_initialization", "args": {}},
+            {"cat": "Node", "name": "/conv/Conv_fence_before", "args": {"op_name": "Conv"}},
+            {"cat": "Node", "name": "/conv/Conv_kernel_time",
+             "args": {"op_name": "Conv", "provider": "XnnpackExecutionProvider"}},
+            {"cat": "Node", "name": "/pos/Add_kernel_time",
+             "args": {"op_name": "Add", "provider": "CPUExecutionProvider"}},
+            {"cat": "Node", "name": "/out/Softmax_kernel_time",
+             "args": {"op_name": "Softmax", "provider": "CPUExecutionProvider"}},
+            {"cat": "Session", "name": "model_run", "args": {}}
+        ]"#;
+        let counts = count_node_providers(trace).unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["XnnpackExecutionProvider"], 1);
+        assert_eq!(counts[CPU_PROVIDER], 2);
+        assert!(count_node_providers("{}").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+
+// Above this many polyphase branches the ratio is approximated; the pitch error stays
+// below 0.02%.
+const MAX_PHASES: usize = 4_096;
+
+type KernelKey = (usize, usize, ResampleQuality);
+
+static KERNEL_CACHE: Lazy<Mutex<HashMap<KernelKey, Arc<PolyphaseKernel>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ResampleQuality {
+    /// Plain linear interpolation without anti-aliasing, as used before.
+    Linear,
+    /// Kaiser-windowed sinc, 16 zero crossings per side, ~80 dB stopband.
+    #[default]
+    Balanced,
+    /// Kaiser-windowed sinc, 32 zero crossings per side, ~100 dB stopband.
+    High,
+}
+
+impl ResampleQuality {
+    pub fn from_id(value: &str) -> Self {
+        match value {
+            "linear" => Self::Linear,
+            "high" => Self::High,
+            _ => Self::Balanced,
+        }
+    }
+
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Balanced => "balanced",
+            Self::High => "high",
+        }
+    }
+
+    /// `(zero crossings per side, Kaiser beta, cutoff relative to the lower Nyquist)`.
+    fn sinc_design(self) -> Option<(usize, f64, f64)> {
+        match self {
+            Self::Linear => None,
+            Self::Balanced => Some((16, 8.0, 0.90)),
+            Self::High => Some((32, 10.0, 0.94)),
+        }
+    }
+}
+
+/// Resamples a complete buffer.
+pub fn resample(
+    input: &[f32],
+    source_rate: usize,
+    target_rate: usize,
+    quality: ResampleQuality,
+) -> Vec<f32> {
+    let mut resampler = Resampler::new(source_rate, target_rate, quality);
+    let mut output = resampler.process(input);
+    output.extend(resampler.finish());
+    output
+}
+
+/// Polyphase filter bank for the rational ratio `up / down`. Output sample `m` sits at
+/// upsampled position `t = m * down`; with `k0 = t / up` and `p = t % up` it is
+/// `sum_i x[k0 + phase.offset + i] * phase.coefficients[i]` for `phase = phases[p]`.
+#[derive(Debug)]
+struct PolyphaseKernel {
+    up: usize,
+    down: usize,
+    phases: Vec<Phase>,
+    min_offset: isize,
+}
+
+#[derive(Debug)]
+struct Phase {
+    offset: isize,
+    coefficients: Vec<f32>,
+}
+
+impl PolyphaseKernel {
+    fn cached(source_rate: usize, target_rate: usize, quality: ResampleQuality) -> Arc<Self> {
+        let (up, down) = rational_ratio(source_rate, target_rate);
+        let build = || Arc::new(Self::build(up, down, quality));
+        match KERNEL_CACHE.lock() {
+            Ok(mut cache) => cache
+                .entry((up, down, quality))
+                .or_insert_with(build)
+                .clone(),
+            Err(_) => build(),
+        }
+    }
+
+    fn build(up: usize, down: usize, quality: ResampleQuality) -> Self {
+        // Impulse response over the upsampled grid, non-zero for |n| <= half_width.
+        let (half_width, response): (usize, Box<dyn Fn(f64) -> f64>) = match quality.sinc_design() {
+            None => (up - 1, Box::new(move |n: f64| 1.0 - n.abs() / up as f64)),
+            Some((zero_crossings, beta, rolloff)) => {
+                let widest = up.max(down) as f64;
+                let cutoff = rolloff * 0.5 / widest;
+                let half_width = (zero_crossings as f64 * widest / rolloff).ceil() as usize;
+                let norm = bessel_i0(beta);
+                let response = move |n: f64| {
+                    let x = n / half_width as f64;
+                    if x.abs() > 1.0 {
+                        return 0.0;
+                    }
+                    let window = bessel_i0(beta * (1.0 - x * x).sqrt()) / norm;
+                    2.0 * cutoff * sinc(2.0 * cutoff * n) * window
+                };
+                (half_width, Box::new(response))
+            }
+        };
+
+        let half_width = half_width as isize;
+        let up_signed = up as isize;
+        let phases = (0..up_signed)
+            .map(|p| {
+                let offset = (p - half_width).div_euclid(up_signed)
+                    + isize::from((p - half_width).rem_euclid(up_signed) != 0);
+                let last = (p + half_width).div_euclid(up_signed);
+                let mut coefficients = (offset..=last)
+                    .map(|o| response((p - o * up_signed) as f64))
+                    .collect::<Vec<_>>();
+                // Unit DC gain per branch, so no phase adds a ripple of its own.
+                let sum = coefficients.iter().sum::<f64>();
+                if sum.abs() > f64::EPSILON {
+                    coefficients.iter_mut().for_each(|c| *c /= sum);
+                }
+                Phase {
+                    offset,
+                    coefficients: coefficients.into_iter().map(|c| c as f32).collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let min_offset = phases.iter().map(|phase| phase.offset).min().unwrap_or(0);
+
+        Self {
+            up,
+            down,
+            phases,
+            min_offset,
+        }
+    }
+}
+
+/// Incremental resampler: feeding a signal in pieces gives the same output as one call
+/// to [`resample`].
+pub struct Resampler {
+    kernel: Option<Arc<PolyphaseKernel>>,
+    history: Vec<f32>,
+    // Absolute input index of `history[0]`.
+    history_start: usize,
+    input_len: usize,
+    next_output: usize,
+}
+
+impl Resampler {
+    /// Rates of 0 or equal rates pass samples through unchanged.
+    pub fn new(source_rate: usize, target_rate: usize, quality: ResampleQuality) -> Self {
+        let kernel = (source_rate != 0 && target_rate != 0 && source_rate != target_rate)
+            .then(|| PolyphaseKernel::cached(source_rate, target_rate, quality));
+        Self {
+            kernel,
+            history: Vec::new(),
+            history_start: 0,
+            input_len: 0,
+            next_output: 0,
+        }
+    }
+
+    /// Returns every output sample whose filter support is covered by the input so far.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let Some(kernel) = self.kernel.clone() else {
+            return input.to_vec();
+        };
+        self.history.extend_from_slice(input);
+        self.input_len += input.len();
+        let output = self.produce(&kernel, false);
+
+        let next_k0 = (self.next_output * kernel.down / kernel.up) as isize;
+        let keep_from = (next_k0 + kernel.min_offset).max(0) as usize;
+        if keep_from > self.history_start {
+            let drop = (keep_from - self.history_start).min(self.history.len());
+            self.history.drain(..drop);
+            self.history_start += drop;
+        }
+        output
+    }
+
+    /// Emits the remaining output, treating the input as silent past its end.
+    pub fn finish(&mut self) -> Vec<f32> {
+        match self.kernel.clone() {
+            Some(kernel) => self.produce(&kernel, true),
+            None => Vec::new(),
+        }
+    }
+
+    fn produce(&mut self, kernel: &PolyphaseKernel, flush: bool) -> Vec<f32> {
+        let mut output = Vec::new();
+        // Output `m` belongs to the signal while `m * down / up` is inside the input.
+        while self.next_output * kernel.down < self.input_len * kernel.up {
+            let position = self.next_output * kernel.down;
+            let k0 = (position / kernel.up) as isize;
+            let phase = &kernel.phases[position % kernel.up];
+            let first = k0 + phase.offset;
+            let last = first + phase.coefficients.len() as isize - 1;
+            if !flush && last >= self.input_len as isize {
+                break;
+            }
+
+            let mut value = 0.0_f32;
+            for (i, &coefficient) in phase.coefficients.iter().enumerate() {
+                let index = first + i as isize - self.history_start as isize;
+                if let Some(&sample) = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| self.history.get(index))
+                {
+                    value += sample * coefficient;
+                }
+            }
+            output.push(value);
+            self.next_output += 1;
+        }
+        output
+    }
+}
+
+fn rational_ratio(source_rate: usize, target_rate: usize) -> (usize, usize) {
+    let divisor = gcd(source_rate, target_rate);
+    let (up, down) = (target_rate / divisor, source_rate / divisor);
+    if up <= MAX_PHASES {
+        return (up, down);
+    }
+    let approx_down = ((MAX_PHASES as f64 * down as f64 / up as f64).round() as usize).max(1);
+    let divisor = gcd(MAX_PHASES, approx_down);
+    (MAX_PHASES / divisor, approx_down / divisor)
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+// Zeroth-order modified Bessel function of the first kind (power series).
+fn bessel_i0(x: f64) -> f64 {
+    let quarter_x_sq = x * x / 4.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 1..64 {
+        term *= quarter_x_sq / (k * k) as f64;
+        sum += term;
+        if term < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(rate: usize, frequency: f64, amplitude: f64, seconds: f64) -> Vec<f32> {
+        (0..(rate as f64 * seconds) as usize)
+            .map(|n| (amplitude * (2.0 * PI * frequency * n as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    /// Amplitude of `frequency` over the middle second of 16 kHz output (whole cycles
+    /// only, so a plain DFT bin has no leakage).
+    fn amplitude_at(samples: &[f32], frequency: f64) -> f64 {
+        let window = &samples[4_000..20_000];
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, &sample) in window.iter().enumerate() {
+            let angle = 2.0 * PI * frequency * n as f64 / 16_000.0;
+            re += sample as f64 * angle.cos();
+            im -= sample as f64 * angle.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / window.len() as f64
+    }
+
+    fn db(ratio: f64) -> f64 {
+        20.0 * ratio.max(1e-12).log10()
+    }
+
+    #[test]
+    fn sinc_resampler_suppresses_aliases_that_linear_folds_back() {
+        // 12 kHz at 48 kHz folds to 4 kHz, 10 kHz at 44.1 kHz folds to 6 kHz.
+        for (rate, frequency, alias) in [(48_000, 12_000.0, 4_000.0), (44_100, 10_000.0, 6_000.0)] {
+            let input = tone(rate, frequency, 0.5, 1.5);
+            let linear = amplitude_at(
+                &resample(&input, rate, 16_000, ResampleQuality::Linear),
+                alias,
+            );
+            let balanced = amplitude_at(
+                &resample(&input, rate, 16_000, ResampleQuality::Balanced),
+                alias,
+            );
+            let high = amplitude_at(
+                &resample(&input, rate, 16_000, ResampleQuality::High),
+                alias,
+            );
+
+            assert!(
+                db(linear / 0.5) > -30.0,
+                "linear alias at {rate}: {} dB",
+                db(linear / 0.5)
+            );
+            assert!(
+                db(balanced / 0.5) < -70.0,
+                "balanced alias at {rate}: {} dB",
+                db(balanced / 0.5)
+            );
+            assert!(
+                db(high / 0.5) < -90.0,
+                "high alias at {rate}: {} dB",
+                db(high / 0.5)
+            );
+        }
+    }
+
+    #[test]
+    fn speech_band_tones_keep_their_level() {
+        for rate in [8_000, 22_050, 44_100, 48_000] {
+            for quality in [ResampleQuality::Balanced, ResampleQuality::High] {
+                let output = resample(&tone(rate, 1_000.0, 0.5, 1.5), rate, 16_000, quality);
+                assert_eq!(output.len(), 24_000);
+                let level = amplitude_at(&output, 1_000.0);
+                assert!(
+                    (level - 0.5).abs() < 0.005,
+                    "{rate} Hz {quality:?}: {level}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_processing_matches_one_shot() {
+        let input = tone(44_100, 440.0, 0.3, 0.5);
+        let expected = resample(&input, 44_100, 16_000, ResampleQuality::Balanced);
+
+        let mut resampler = Resampler::new(44_100, 16_000, ResampleQuality::Balanced);
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(1_234) {
+            chunked.extend(resampler.process(chunk));
+        }
+        chunked.extend(resampler.finish());
+        assert_eq!(chunked, expected);
+    }
+
+    /// Streaming sessions resample every pushed chunk; neither the chunk edges nor the
+    /// rounding of per-chunk lengths may shift the output.
+    #[test]
+    fn streamed_chunks_leave_no_seams_or_length_drift() {
+        for rate in [8_000, 22_050, 44_100, 48_000] {
+            let input = tone(rate, 300.0, 0.3, 0.3);
+            for quality in [
+                ResampleQuality::Linear,
+                ResampleQuality::Balanced,
+                ResampleQuality::High,
+            ] {
+                let expected = resample(&input, rate, 16_000, quality);
+                assert_eq!(
+                    expected.len(),
+                    (input.len() * 16_000).div_ceil(rate),
+                    "{rate} Hz {quality:?}"
+                );
+                for chunk_len in [1, 7, 160, 441, 1_000] {
+                    let mut resampler = Resampler::new(rate, 16_000, quality);
+                    let mut streamed = Vec::new();
+                    for chunk in input.chunks(chunk_len) {
+                        streamed.extend(resampler.process(chunk));
+                    }
+                    streamed.extend(resampler.finish());
+                    assert_eq!(streamed, expected, "{rate} Hz {quality:?} by {chunk_len}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ratios_are_reduced_and_identity_passes_through() {
+        assert_eq!(rational_ratio(48_000, 16_000), (1, 3));
+        assert_eq!(rational_ratio(44_100, 16_000), (160, 441));
+        let (up, down) = rational_ratio(44_101, 16_000);
+        assert!(up <= MAX_PHASES);
+        assert!((up as f64 / down as f64 - 16_000.0 / 44_101.0).abs() < 1e-4);
+
+        let input = vec![0.1, -0.2, 0.3];
+        assert_eq!(
+            resample(&input, 16_000, 16_000, ResampleQuality::High),
+            input
+        );
+        assert_eq!(ResampleQuality::from_id("high"), ResampleQuality::High);
+        assert_eq!(
+            ResampleQuality::from_id("unknown"),
+            ResampleQuality::Balanced
+        );
+    }
+}
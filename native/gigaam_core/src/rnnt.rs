@@ -0,0 +1,792 @@
+use crate::batch::{pad_features, plan_batches, BatchOptions};
+use crate::decoder::{log_softmax_into, AlignedToken};
+use crate::gigaam::{
+    build_model_session, report_from_tokens, ModelAssets, NativeTranscriptionReport,
+    NativeTranscriptionTimings, RuntimeOptions,
+};
+use crate::manifest::ModelManifest;
+use crate::metrics::ModelLoadMetrics;
+use crate::model_cache::ModelCache;
+use crate::timestamps::token_timings;
+use anyhow::Result;
+use ndarray::{s, Array2, Array3, ArrayD, ArrayView1, ArrayView2, ArrayView3, Axis, Ix3, IxDyn};
+use ort::inputs;
+use ort::session::{Session, SessionInputValue};
+use ort::tensor::TensorElementType;
+use ort::value::{DynValue, Tensor, TensorRef};
+use std::path::Path;
+use std::time::Instant;
+
+/// Upper bound on tokens emitted for one encoder frame, so a joint network that never
+/// predicts blank cannot stall decoding.
+const MAX_SYMBOLS_PER_FRAME: usize = 10;
+
+/// Prediction and joint networks of a transducer, one step at a time.
+pub trait TransducerNetwork {
+    type State;
+
+    fn initial_state(&self) -> Self::State;
+
+    /// Advances the prediction network by `token`; returns its output and the next state.
+    fn predict(&mut self, token: usize, state: &Self::State) -> Result<(Vec<f32>, Self::State)>;
+
+    /// Scores over the vocabulary (blank included) for one encoder frame. Logits and
+    /// log-probabilities are both accepted.
+    fn joint(&mut self, frame: ArrayView1<'_, f32>, prediction: &[f32]) -> Result<Vec<f32>>;
+}
+
+/// Greedy transducer search over `frames` (`[time, dim]`). The prediction network starts
+/// from blank; each frame emits tokens until blank wins or `max_symbols_per_frame` is
+/// reached. Returns the tokens, each on the frame that emitted it, with their
+/// probabilities.
+pub fn transducer_greedy_decode<N: TransducerNetwork>(
+    network: &mut N,
+    frames: ArrayView2<'_, f32>,
+    blank_idx: usize,
+    max_symbols_per_frame: usize,
+) -> Result<(Vec<AlignedToken>, Vec<f32>)> {
+    let mut tokens = Vec::new();
+    let mut confidences = Vec::new();
+    let initial_state = network.initial_state();
+    let (mut prediction, mut state) = network.predict(blank_idx, &initial_state)?;
+    let mut log_probs = Vec::new();
+
+    for (frame_idx, frame) in frames.outer_iter().enumerate() {
+        for _ in 0..max_symbols_per_frame {
+            let scores = network.joint(frame, &prediction)?;
+            log_softmax_into(ArrayView1::from(&scores[..]), &mut log_probs);
+            let (token, log_prob) = log_probs.iter().copied().enumerate().fold(
+                (blank_idx, f32::NEG_INFINITY),
+                |best, (token, log_prob)| {
+                    if log_prob > best.1 {
+                        (token, log_prob)
+                    } else {
+                        best
+                    }
+                },
+            );
+            if token == blank_idx {
+                break;
+            }
+            tokens.push(AlignedToken {
+                id: token,
+                start_frame: frame_idx,
+                end_frame: frame_idx,
+            });
+            confidences.push(log_prob.exp());
+            (prediction, state) = network.predict(token, &state)?;
+        }
+    }
+    Ok((tokens, confidences))
+}
+
+/// GigaAM RNN-T exported as three ONNX graphs: the encoder (the manifest's `model`),
+/// the prediction network (`decoder`) and the joint network (`joint`).
+pub struct RnntModel {
+    assets: ModelAssets,
+    encoder: TransducerEncoder,
+    network: OrtTransducer,
+    load_metrics: ModelLoadMetrics,
+    /// The next encoder run is the profiled first one.
+    node_profile_pending: bool,
+}
+
+impl RnntModel {
+    pub fn new(
+        model_dir: &Path,
+        manifest: &ModelManifest,
+        runtime_options: &RuntimeOptions,
+        model_cache: Option<&ModelCache>,
+    ) -> Result<Self> {
+        let load_start = Instant::now();
+        let assets = ModelAssets::load(model_dir, manifest)?;
+        let assets_ms = load_start.elapsed().as_millis();
+        let (decoder_file, joint_file) = manifest
+            .decoder
+            .as_ref()
+            .zip(manifest.joint.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("RNN-T manifest must declare decoder and joint"))?;
+
+        // Only the encoder is profiled: the small networks run once per emitted token.
+        let (encoder, encoder_info) = build_model_session(
+            model_dir,
+            &manifest.model,
+            runtime_options,
+            model_cache,
+            true,
+        )?;
+        let (decoder, decoder_info) =
+            build_model_session(model_dir, decoder_file, runtime_options, model_cache, false)?;
+        let (joint, joint_info) =
+            build_model_session(model_dir, joint_file, runtime_options, model_cache, false)?;
+        for (label, session) in [
+            ("encoder", &encoder),
+            ("decoder", &decoder),
+            ("joint", &joint),
+        ] {
+            for input in &session.inputs {
+                log::info!(
+                    "GigaAM RNN-T {label} input: name={}, type={:?}",
+                    input.name,
+                    input.input_type
+                );
+            }
+            for output in &session.outputs {
+                log::info!("GigaAM RNN-T {label} output: name={}", output.name);
+            }
+        }
+
+        let network = OrtTransducer::new(decoder, joint)?;
+        Ok(Self {
+            encoder: TransducerEncoder::new(encoder, network.encoder_size())?,
+            network,
+            assets,
+            node_profile_pending: encoder_info.profiling,
+            load_metrics: ModelLoadMetrics {
+                session_ms: encoder_info.create_ms + decoder_info.create_ms + joint_info.create_ms,
+                provider: encoder_info.providers,
+                assets_ms,
+                total_ms: load_start.elapsed().as_millis(),
+            },
+        })
+    }
+
+    pub fn vocab(&self) -> &[String] {
+        &self.assets.vocab
+    }
+
+    pub fn load_metrics(&self) -> &ModelLoadMetrics {
+        &self.load_metrics
+    }
+
+    /// Greedy transcription. `ort_run_ms` covers the encoder; the per-token decoder and
+    /// joint runs are counted in `decode_ms`.
+    pub fn transcribe_samples(&mut self, samples: &[f32]) -> Result<NativeTranscriptionReport> {
+        let mut reports = self.transcribe_batch(&[samples], &BatchOptions::default())?;
+        Ok(reports.remove(0))
+    }
+
+    /// Like [`Self::transcribe_samples`] for several utterances, with the encoder run
+    /// over padded batches; each report's `ort_run_ms` is its share of the batch run.
+    /// The transducer search still runs one utterance at a time.
+    pub fn transcribe_batch(
+        &mut self,
+        utterances: &[&[f32]],
+        batch_options: &BatchOptions,
+    ) -> Result<Vec<NativeTranscriptionReport>> {
+        let lengths = utterances
+            .iter()
+            .map(|samples| self.assets.frontend.feature_frame_count(samples.len()))
+            .collect::<Vec<_>>();
+        let mut tokens = vec![Vec::new(); utterances.len()];
+        let mut timings = vec![NativeTranscriptionTimings::default(); utterances.len()];
+        for batch in plan_batches(&lengths, batch_options) {
+            let mut features = Vec::with_capacity(batch.len());
+            for &index in &batch {
+                let feature_start = Instant::now();
+                features.push(self.assets.frontend.extract_features(utterances[index])?.0);
+                timings[index].feature_extraction_ms = feature_start.elapsed().as_millis();
+            }
+            let views = features.iter().map(Array3::view).collect::<Vec<_>>();
+            let (padded, feature_lengths) =
+                pad_features(&views, self.assets.frontend.silence_level())?;
+            let expected_frames = feature_lengths
+                .iter()
+                .map(|&length| ((length - 1) / self.assets.subsampling_factor as i64 + 1) as usize)
+                .collect::<Vec<_>>();
+
+            let ort_start = Instant::now();
+            let encoded = self
+                .encoder
+                .encode(padded.view(), &feature_lengths, &expected_frames)?;
+            let ort_run_ms = ort_start.elapsed().as_millis() / batch.len() as u128;
+            if self.node_profile_pending {
+                self.node_profile_pending = false;
+                self.load_metrics
+                    .provider
+                    .record_node_assignment(&mut self.encoder.session);
+            }
+
+            for (&index, frames) in batch.iter().zip(encoded) {
+                let decode_start = Instant::now();
+                let (aligned, confidences) = transducer_greedy_decode(
+                    &mut self.network,
+                    frames.view(),
+                    self.assets.blank_idx,
+                    MAX_SYMBOLS_PER_FRAME,
+                )?;
+                tokens[index] = token_timings(
+                    &aligned,
+                    &confidences,
+                    &self.assets.vocab,
+                    self.assets.frame_ms,
+                );
+                let timings = &mut timings[index];
+                timings.ort_run_ms = ort_run_ms;
+                timings.decode_ms = decode_start.elapsed().as_millis();
+                timings.feature_frames = lengths[index];
+                timings.encoder_frames = frames.nrows();
+            }
+        }
+
+        Ok(tokens
+            .into_iter()
+            .zip(timings)
+            .map(|(tokens, mut timings)| {
+                timings.total_ms =
+                    timings.feature_extraction_ms + timings.ort_run_ms + timings.decode_ms;
+                report_from_tokens(tokens, &self.assets.vocab, timings)
+            })
+            .collect())
+    }
+}
+
+/// Axis order of the encoder output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EncoderLayout {
+    /// `[batch, dim, time]`, as GigaAM exports it.
+    ChannelsFirst,
+    /// `[batch, time, dim]`.
+    TimeMajor,
+}
+
+impl EncoderLayout {
+    /// Reads the layout from the declared output shape: the channel axis is the static
+    /// one, of size `dim` when the joint network declares it.
+    fn from_declared(declared: &[i64], dim: Option<usize>) -> Result<Self> {
+        if declared.len() != 3 {
+            anyhow::bail!("RNN-T encoder output must be 3-D, but is declared as {declared:?}");
+        }
+        let channels =
+            |axis: usize| declared[axis] > 0 && dim.is_none_or(|dim| declared[axis] == dim as i64);
+        match (channels(1), channels(2)) {
+            (true, false) => Ok(Self::ChannelsFirst),
+            (false, true) => Ok(Self::TimeMajor),
+            _ => anyhow::bail!(
+                "Cannot tell the time axis of the RNN-T encoder output {declared:?} from its \
+                 channel axis (joint encoder size {dim:?})"
+            ),
+        }
+    }
+}
+
+struct TransducerEncoder {
+    session: Session,
+    features_input_name: String,
+    lengths_input_name: String,
+    encoded_output_name: String,
+    encoded_lengths_output_name: Option<String>,
+    layout: EncoderLayout,
+}
+
+impl TransducerEncoder {
+    /// `dim` is the encoder size the joint network expects, when it declares one.
+    fn new(session: Session, dim: Option<usize>) -> Result<Self> {
+        let features_input_name = find_input(&session, &["audio_signal", "features"], 3)
+            .ok_or_else(|| anyhow::anyhow!("Failed to determine RNN-T encoder features input"))?;
+        let lengths_input_name = find_input(&session, &["length", "feature_lengths"], 1)
+            .ok_or_else(|| anyhow::anyhow!("Failed to determine RNN-T encoder length input"))?;
+        let encoded_output = session
+            .outputs
+            .iter()
+            .find(|output| output.name == "encoded")
+            .or_else(|| session.outputs.first())
+            .ok_or_else(|| anyhow::anyhow!("RNN-T encoder has no outputs"))?;
+        let layout =
+            EncoderLayout::from_declared(&declared_shape(&encoded_output.output_type), dim)?;
+        let encoded_output_name = encoded_output.name.clone();
+        let encoded_lengths_output_name = session
+            .outputs
+            .iter()
+            .find(|output| output.name == "encoded_len")
+            .map(|output| output.name.clone());
+        Ok(Self {
+            session,
+            features_input_name,
+            lengths_input_name,
+            encoded_output_name,
+            encoded_lengths_output_name,
+            layout,
+        })
+    }
+
+    /// Runs the encoder over a batch and returns the valid frames of every row as
+    /// `[time, dim]`.
+    fn encode(
+        &mut self,
+        features: ArrayView3<'_, f32>,
+        feature_lengths: &[i64],
+        expected_frames: &[usize],
+    ) -> Result<Vec<Array2<f32>>> {
+        let feature_lengths = ArrayView1::from(feature_lengths);
+        let outputs = self.session.run(inputs![
+            self.features_input_name.as_str() => TensorRef::from_array_view(features)?,
+            self.lengths_input_name.as_str() => TensorRef::from_array_view(feature_lengths)?,
+        ])?;
+
+        let encoded = outputs
+            .get(self.encoded_output_name.as_str())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "RNN-T encoder output '{}' not found in inference outputs",
+                    self.encoded_output_name
+                )
+            })?
+            .try_extract_array::<f32>()?
+            .into_dimensionality::<Ix3>()?;
+        let reported = self
+            .encoded_lengths_output_name
+            .as_deref()
+            .and_then(|name| outputs.get(name))
+            .and_then(lengths)
+            .filter(|lengths| lengths.len() == expected_frames.len());
+        let is_reported = reported.is_some();
+        let frame_counts = reported.unwrap_or_else(|| expected_frames.to_vec());
+
+        if encoded.len_of(Axis(0)) != frame_counts.len() {
+            anyhow::bail!(
+                "RNN-T encoder returned {} rows for a batch of {}",
+                encoded.len_of(Axis(0)),
+                frame_counts.len()
+            );
+        }
+        frame_counts
+            .iter()
+            .enumerate()
+            .map(|(row, &frame_count)| {
+                let encoded = encoded.index_axis(Axis(0), row);
+                let frames = match self.layout {
+                    EncoderLayout::ChannelsFirst => encoded.t(),
+                    EncoderLayout::TimeMajor => encoded,
+                };
+                // Counts derived from the subsampling factor may round past the end;
+                // counts the graph reports must fit.
+                if is_reported && frame_count > frames.nrows() {
+                    anyhow::bail!(
+                        "RNN-T encoder reports {frame_count} frames but returned {}",
+                        frames.nrows()
+                    );
+                }
+                let frame_count = frame_count.min(frames.nrows());
+                Ok(frames
+                    .slice(s![..frame_count, ..])
+                    .as_standard_layout()
+                    .into_owned())
+            })
+            .collect()
+    }
+}
+
+/// Decoder and joint ONNX sessions. The decoder takes the previous token plus its
+/// recurrent states and returns the prediction followed by the updated states, in the
+/// same order. State shapes come from the graphs and are checked by one decoder run
+/// at load.
+struct OrtTransducer {
+    decoder: Session,
+    joint: Session,
+    token_input_name: String,
+    token_type: TensorElementType,
+    token_shape: Vec<usize>,
+    state_inputs: Vec<(String, Vec<usize>)>,
+    prediction_output_name: String,
+    state_output_names: Vec<String>,
+    joint_encoder_input: (String, Vec<i64>),
+    joint_prediction_input: (String, Vec<i64>),
+    joint_output_name: String,
+}
+
+impl OrtTransducer {
+    fn new(decoder: Session, joint: Session) -> Result<Self> {
+        let token_input = decoder
+            .inputs
+            .iter()
+            .find(|input| matches!(input.name.as_str(), "x" | "targets"))
+            .or_else(|| {
+                decoder.inputs.iter().find(|input| {
+                    matches!(
+                        input.input_type.tensor_type(),
+                        Some(TensorElementType::Int64 | TensorElementType::Int32)
+                    )
+                })
+            })
+            .ok_or_else(|| anyhow::anyhow!("Failed to determine RNN-T decoder token input"))?;
+        let token_shape = declared_shape(&token_input.input_type)
+            .iter()
+            .map(|&dim| dim.max(1) as usize)
+            .collect();
+
+        let joint_input = |name: &str, position: usize| {
+            joint
+                .inputs
+                .iter()
+                .find(|input| input.name == name)
+                .or_else(|| joint.inputs.get(position))
+                .map(|input| (input.name.clone(), declared_shape(&input.input_type)))
+                .ok_or_else(|| anyhow::anyhow!("Failed to determine RNN-T joint input '{name}'"))
+        };
+        let joint_encoder_input = joint_input("enc", 0)?;
+        let joint_prediction_input = joint_input("dec", 1)?;
+        let joint_output_name = joint
+            .outputs
+            .first()
+            .map(|output| output.name.clone())
+            .ok_or_else(|| anyhow::anyhow!("RNN-T joint has no outputs"))?;
+
+        let prediction_output_name = decoder
+            .outputs
+            .iter()
+            .find(|output| output.name == "dec")
+            .or_else(|| decoder.outputs.first())
+            .map(|output| output.name.clone())
+            .ok_or_else(|| anyhow::anyhow!("RNN-T decoder has no outputs"))?;
+        let state_output_names = decoder
+            .outputs
+            .iter()
+            .filter(|output| output.name != prediction_output_name)
+            .map(|output| output.name.clone())
+            .collect::<Vec<_>>();
+        let state_inputs = decoder
+            .inputs
+            .iter()
+            .filter(|input| input.name != token_input.name)
+            .collect::<Vec<_>>();
+        if state_output_names.len() != state_inputs.len() {
+            anyhow::bail!(
+                "RNN-T decoder has {} state inputs but {} state outputs",
+                state_inputs.len(),
+                state_output_names.len()
+            );
+        }
+        let prediction_size = vector_size(&joint_prediction_input.1);
+        let state_inputs = state_inputs
+            .into_iter()
+            .zip(&state_output_names)
+            .map(|(input, output_name)| {
+                let output = decoder
+                    .outputs
+                    .iter()
+                    .find(|output| &output.name == output_name)
+                    .map(|output| declared_shape(&output.output_type))
+                    .unwrap_or_default();
+                let shape =
+                    state_shape(&declared_shape(&input.input_type), &output, prediction_size)
+                        .map_err(|error| {
+                            anyhow::anyhow!("RNN-T decoder state '{}': {error}", input.name)
+                        })?;
+                Ok((input.name.clone(), shape))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut network = Self {
+            token_input_name: token_input.name.clone(),
+            token_type: token_input
+                .input_type
+                .tensor_type()
+                .unwrap_or(TensorElementType::Int64),
+            token_shape,
+            state_inputs,
+            prediction_output_name,
+            state_output_names,
+            joint_encoder_input,
+            joint_prediction_input,
+            joint_output_name,
+            decoder,
+            joint,
+        };
+        network.check_shapes(prediction_size)?;
+        Ok(network)
+    }
+
+    /// Encoder frame size the joint network declares, if it is static.
+    fn encoder_size(&self) -> Option<usize> {
+        vector_size(&self.joint_encoder_input.1)
+    }
+
+    /// Runs the decoder once from the initial state and fails unless it hands back
+    /// states of the shapes it was fed and a prediction the joint network accepts.
+    fn check_shapes(&mut self, prediction_size: Option<usize>) -> Result<()> {
+        let initial = self.initial_state();
+        let (prediction, state) = self.predict(0, &initial)?;
+        for (((name, _), fed), returned) in self.state_inputs.iter().zip(&initial).zip(&state) {
+            if fed.shape() != returned.shape() {
+                anyhow::bail!(
+                    "RNN-T decoder state '{name}' was fed as {:?} but returned as {:?}",
+                    fed.shape(),
+                    returned.shape()
+                );
+            }
+        }
+        if let Some(size) = prediction_size.filter(|&size| size != prediction.len()) {
+            anyhow::bail!(
+                "RNN-T decoder predicts {} values but the joint network expects {size}",
+                prediction.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl TransducerNetwork for OrtTransducer {
+    type State = Vec<ArrayD<f32>>;
+
+    fn initial_state(&self) -> Self::State {
+        self.state_inputs
+            .iter()
+            .map(|(_, shape)| ArrayD::zeros(IxDyn(shape)))
+            .collect()
+    }
+
+    fn predict(&mut self, token: usize, state: &Self::State) -> Result<(Vec<f32>, Self::State)> {
+        let token_value: SessionInputValue = match self.token_type {
+            TensorElementType::Int32 => {
+                Tensor::from_array((self.token_shape.clone(), vec![token as i32]))?.into()
+            }
+            _ => Tensor::from_array((self.token_shape.clone(), vec![token as i64]))?.into(),
+        };
+        let mut inputs = vec![(self.token_input_name.as_str(), token_value)];
+        for ((name, _), value) in self.state_inputs.iter().zip(state) {
+            inputs.push((
+                name.as_str(),
+                TensorRef::from_array_view(value.view())?.into(),
+            ));
+        }
+
+        let outputs = self.decoder.run(inputs)?;
+        let output = |name: &str| {
+            outputs
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("RNN-T decoder output '{name}' not found"))
+        };
+        let prediction = output(&self.prediction_output_name)?
+            .try_extract_array::<f32>()?
+            .iter()
+            .copied()
+            .collect();
+        let next_state = self
+            .state_output_names
+            .iter()
+            .map(|name| Ok(output(name)?.try_extract_array::<f32>()?.to_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((prediction, next_state))
+    }
+
+    fn joint(&mut self, frame: ArrayView1<'_, f32>, prediction: &[f32]) -> Result<Vec<f32>> {
+        let (encoder_name, encoder_declared) = &self.joint_encoder_input;
+        let (prediction_name, prediction_declared) = &self.joint_prediction_input;
+        let encoder_value = ArrayD::from_shape_vec(
+            IxDyn(&vector_shape(encoder_declared, frame.len())),
+            frame.to_vec(),
+        )?;
+        let prediction_value = ArrayD::from_shape_vec(
+            IxDyn(&vector_shape(prediction_declared, prediction.len())),
+            prediction.to_vec(),
+        )?;
+
+        let outputs = self.joint.run(inputs![
+            encoder_name.as_str() => TensorRef::from_array_view(encoder_value.view())?,
+            prediction_name.as_str() => TensorRef::from_array_view(prediction_value.view())?,
+        ])?;
+        let scores = outputs
+            .get(self.joint_output_name.as_str())
+            .ok_or_else(|| {
+                anyhow::anyhow!("RNN-T joint output '{}' not found", self.joint_output_name)
+            })?
+            .try_extract_array::<f32>()?
+            .iter()
+            .copied()
+            .collect();
+        Ok(scores)
+    }
+}
+
+fn find_input(session: &Session, names: &[&str], rank: usize) -> Option<String> {
+    session
+        .inputs
+        .iter()
+        .find(|input| names.contains(&input.name.as_str()))
+        .or_else(|| {
+            session.inputs.iter().find(|input| {
+                input
+                    .input_type
+                    .tensor_shape()
+                    .is_some_and(|shape| shape.len() == rank)
+            })
+        })
+        .map(|input| input.name.clone())
+}
+
+fn declared_shape(value_type: &ort::value::ValueType) -> Vec<i64> {
+    value_type
+        .tensor_shape()
+        .map(|shape| shape.to_vec())
+        .unwrap_or_default()
+}
+
+/// Size of the vector an input declared as `declared` takes: its only static axis
+/// larger than 1.
+fn vector_size(declared: &[i64]) -> Option<usize> {
+    let mut sizes = declared.iter().filter(|&&dim| dim > 1);
+    match (sizes.next(), sizes.next()) {
+        (Some(&size), None) => Some(size as usize),
+        _ => None,
+    }
+}
+
+/// Shape of a decoder state fed as `input` and returned as `output`. An axis that both
+/// leave dynamic is the batch axis (1 here), unless it is the last one, which holds
+/// the `prediction_size` hidden units of the prediction network.
+fn state_shape(
+    input: &[i64],
+    output: &[i64],
+    prediction_size: Option<usize>,
+) -> Result<Vec<usize>> {
+    (0..input.len())
+        .map(|axis| {
+            let declared = Some(input[axis])
+                .filter(|&dim| dim > 0)
+                .or_else(|| output.get(axis).copied().filter(|&dim| dim > 0));
+            match declared {
+                Some(dim) => Ok(dim as usize),
+                None if axis + 1 < input.len() => Ok(1),
+                None => prediction_size.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "the hidden size is dynamic and the joint network does not declare it"
+                    )
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Shape that feeds one vector of `len` values into an input declared as `declared`: the
+/// vector goes on the static axis of that size, or on axis 1 (the channel axis of
+/// `[batch, dim, time]` graphs) when there is none; every other axis is 1.
+fn vector_shape(declared: &[i64], len: usize) -> Vec<usize> {
+    let rank = declared.len().max(1);
+    let axis = declared
+        .iter()
+        .position(|&dim| dim == len as i64)
+        .unwrap_or(1.min(rank - 1));
+    (0..rank)
+        .map(|index| if index == axis { len } else { 1 })
+        .collect()
+}
+
+/// Every entry of an `i64` or `i32` length output.
+fn lengths(value: &DynValue) -> Option<Vec<usize>> {
+    let lengths = match value.try_extract_array::<i64>() {
+        Ok(lengths) => lengths.iter().copied().collect::<Vec<_>>(),
+        Err(_) => value
+            .try_extract_array::<i32>()
+            .ok()?
+            .iter()
+            .map(|&length| i64::from(length))
+            .collect(),
+    };
+    lengths
+        .into_iter()
+        .map(|length| usize::try_from(length).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// Emits the token stored in each frame once, then blank; the prediction is the last
+    /// emitted token and the state counts emissions.
+    struct EchoNetwork {
+        vocab_size: usize,
+        never_blank: bool,
+    }
+
+    impl TransducerNetwork for EchoNetwork {
+        type State = usize;
+
+        fn initial_state(&self) -> usize {
+            0
+        }
+
+        fn predict(&mut self, token: usize, state: &usize) -> Result<(Vec<f32>, usize)> {
+            Ok((vec![token as f32], state + 1))
+        }
+
+        fn joint(&mut self, frame: ArrayView1<'_, f32>, prediction: &[f32]) -> Result<Vec<f32>> {
+            let wanted = if self.never_blank || frame[0] != prediction[0] {
+                frame[0] as usize
+            } else {
+                0
+            };
+            let mut scores = vec![0.0; self.vocab_size];
+            scores[wanted] = 5.0;
+            Ok(scores)
+        }
+    }
+
+    #[test]
+    fn greedy_search_emits_tokens_on_their_frames() {
+        let mut network = EchoNetwork {
+            vocab_size: 4,
+            never_blank: false,
+        };
+        let frames = array![[2.0], [0.0], [1.0], [1.0], [3.0]];
+        let (tokens, confidences) =
+            transducer_greedy_decode(&mut network, frames.view(), 0, MAX_SYMBOLS_PER_FRAME)
+                .unwrap();
+        let emitted = tokens
+            .iter()
+            .map(|token| (token.id, token.start_frame, token.end_frame))
+            .collect::<Vec<_>>();
+        assert_eq!(emitted, vec![(2, 0, 0), (1, 2, 2), (3, 4, 4)]);
+        assert!(confidences
+            .iter()
+            .all(|&confidence| confidence > 0.9 && confidence < 1.0));
+    }
+
+    #[test]
+    fn greedy_search_caps_symbols_per_frame() {
+        let mut network = EchoNetwork {
+            vocab_size: 3,
+            never_blank: true,
+        };
+        let frames = array![[1.0], [2.0]];
+        let (tokens, _) = transducer_greedy_decode(&mut network, frames.view(), 0, 3).unwrap();
+        let ids = tokens.iter().map(|token| token.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn encoder_layout_and_state_shapes_come_from_the_graph() {
+        use EncoderLayout::{ChannelsFirst, TimeMajor};
+        let layout = |declared: &[i64], dim| EncoderLayout::from_declared(declared, dim).ok();
+        assert_eq!(layout(&[-1, 768, -1], Some(768)), Some(ChannelsFirst));
+        assert_eq!(layout(&[-1, -1, 768], None), Some(TimeMajor));
+        // A time axis of the hidden size is ambiguous even when static.
+        assert_eq!(layout(&[1, 768, 768], Some(768)), None);
+        assert_eq!(layout(&[1, 768, 50], Some(768)), Some(ChannelsFirst));
+        assert_eq!(layout(&[-1, -1, -1], Some(768)), None);
+        assert_eq!(layout(&[-1, 512, -1], Some(768)), None);
+        assert_eq!(layout(&[-1, 768], Some(768)), None);
+
+        assert_eq!(vector_size(&[1, 320, 1]), Some(320));
+        assert_eq!(vector_size(&[-1, -1, -1]), None);
+        assert_eq!(
+            state_shape(&[1, -1, -1], &[1, -1, 320], None).unwrap(),
+            vec![1, 1, 320]
+        );
+        assert_eq!(
+            state_shape(&[-1, -1, -1], &[], Some(640)).unwrap(),
+            vec![1, 1, 640]
+        );
+        assert!(state_shape(&[1, -1, -1], &[], None).is_err());
+    }
+
+    #[test]
+    fn joint_vectors_follow_the_declared_axis() {
+        assert_eq!(vector_shape(&[1, 768, 1], 768), vec![1, 768, 1]);
+        assert_eq!(vector_shape(&[1, 1, 320], 320), vec![1, 1, 320]);
+        assert_eq!(vector_shape(&[-1, -1, -1], 320), vec![1, 320, 1]);
+        assert_eq!(vector_shape(&[], 5), vec![5]);
+    }
+}
@@ -0,0 +1,260 @@
+use std::borrow::Cow;
+
+/// How a centered STFT extends the signal ends, as `torch.stft`'s `pad_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadMode {
+    /// Mirror the samples next to the edge, excluding the edge sample itself.
+    Reflect,
+    /// Zeros.
+    Constant,
+}
+
+impl PadMode {
+    pub fn from_id(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("reflect") {
+            Some(Self::Reflect)
+        } else if value.eq_ignore_ascii_case("constant") {
+            Some(Self::Constant)
+        } else {
+            None
+        }
+    }
+}
+
+/// Sample-level steps ahead of framing, in NeMo's order: dither, preemphasis, then
+/// the `center_pad` samples a centered STFT adds at both ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalConditioner {
+    /// Standard deviation of the added Gaussian noise.
+    pub dither: f32,
+    /// `y[n] = x[n] - preemph * x[n - 1]`; the first sample is kept.
+    pub preemph: f32,
+    pub center_pad: usize,
+    pub pad_mode: PadMode,
+}
+
+/// What a stream carries between chunks so that conditioning it piecewise gives the
+/// same samples as conditioning the whole recording.
+#[derive(Debug, Clone, Default)]
+pub struct SignalCarry {
+    /// Last dithered input sample, for preemphasis.
+    previous: Option<f32>,
+    noise: GaussianNoise,
+    /// Conditioned samples held back until the left pad can be built.
+    head: Vec<f32>,
+    started: bool,
+    /// The last `center_pad + 1` conditioned samples, for the right reflect pad.
+    tail: Vec<f32>,
+}
+
+impl SignalConditioner {
+    fn is_identity(&self) -> bool {
+        self.dither == 0.0 && self.preemph == 0.0 && self.center_pad == 0
+    }
+
+    /// Length of [`Self::apply`]'s output for `sample_count` input samples.
+    pub fn conditioned_len(&self, sample_count: usize) -> usize {
+        let required = match self.pad_mode {
+            PadMode::Reflect => self.center_pad + 1,
+            PadMode::Constant => 1,
+        };
+        if self.center_pad == 0 {
+            sample_count
+        } else if sample_count < required {
+            0
+        } else {
+            sample_count + 2 * self.center_pad
+        }
+    }
+
+    /// Conditions a whole recording. Reflect padding needs more than `center_pad`
+    /// samples; shorter input comes back empty, which leaves it without frames.
+    pub fn apply<'a>(&self, samples: &'a [f32]) -> Cow<'a, [f32]> {
+        if self.is_identity() {
+            return Cow::Borrowed(samples);
+        }
+        let mut carry = SignalCarry::default();
+        let mut signal = self.push(&mut carry, samples);
+        signal.extend(self.finish(&mut carry));
+        Cow::Owned(signal)
+    }
+
+    /// Conditions the next chunk of a stream. Output starts with the left pad once
+    /// enough audio arrived to build it.
+    pub fn push(&self, carry: &mut SignalCarry, samples: &[f32]) -> Vec<f32> {
+        let conditioned = samples
+            .iter()
+            .map(|&sample| {
+                let value = if self.dither > 0.0 {
+                    sample + self.dither * carry.noise.next()
+                } else {
+                    sample
+                };
+                match carry.previous.replace(value) {
+                    Some(previous) if self.preemph != 0.0 => value - self.preemph * previous,
+                    _ => value,
+                }
+            })
+            .collect::<Vec<_>>();
+        if self.center_pad == 0 {
+            return conditioned;
+        }
+
+        if self.pad_mode == PadMode::Reflect {
+            carry.tail.extend_from_slice(&conditioned);
+            let excess = carry.tail.len().saturating_sub(self.center_pad + 1);
+            carry.tail.drain(..excess);
+        }
+        if carry.started {
+            return conditioned;
+        }
+
+        carry.head.extend(conditioned);
+        if self.conditioned_len(carry.head.len()) == 0 {
+            return Vec::new();
+        }
+        carry.started = true;
+        let head = std::mem::take(&mut carry.head);
+        let mut output = Vec::with_capacity(self.center_pad + head.len());
+        match self.pad_mode {
+            PadMode::Reflect => output.extend(head[1..=self.center_pad].iter().rev()),
+            PadMode::Constant => output.resize(self.center_pad, 0.0),
+        }
+        output.extend(head);
+        output
+    }
+
+    /// Ends a stream: the right pad, or nothing when the stream never started.
+    pub fn finish(&self, carry: &mut SignalCarry) -> Vec<f32> {
+        if self.center_pad == 0 || !carry.started {
+            return Vec::new();
+        }
+        match self.pad_mode {
+            PadMode::Reflect => carry.tail.iter().rev().skip(1).copied().collect(),
+            PadMode::Constant => vec![0.0; self.center_pad],
+        }
+    }
+}
+
+/// Seeded standard normal samples, so dithered features are reproducible.
+#[derive(Debug, Clone, Default)]
+struct GaussianNoise {
+    state: u64,
+}
+
+impl GaussianNoise {
+    fn next(&mut self) -> f32 {
+        // Box-Muller over two splitmix64 uniforms in [0, 1); `1 - u1` is never zero.
+        let u1 = (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+        let u2 = (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+        let radius = (-2.0 * (1.0 - u1).ln()).sqrt();
+        (radius * (2.0 * std::f64::consts::PI * u2).cos()) as f32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditioner(center_pad: usize, pad_mode: PadMode) -> SignalConditioner {
+        SignalConditioner {
+            dither: 0.0,
+            preemph: 0.0,
+            center_pad,
+            pad_mode,
+        }
+    }
+
+    #[test]
+    fn centered_padding_matches_torch_pad_modes() {
+        let samples = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let reflect = conditioner(2, PadMode::Reflect).apply(&samples);
+        assert_eq!(&*reflect, &[3.0, 2.0, 1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0]);
+        let constant = conditioner(2, PadMode::Constant).apply(&samples);
+        assert_eq!(&*constant, &[0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 0.0, 0.0]);
+
+        // Reflection needs a sample beyond the pad; zeros need any audio at all.
+        assert!(conditioner(2, PadMode::Reflect)
+            .apply(&samples[..2])
+            .is_empty());
+        assert_eq!(
+            conditioner(2, PadMode::Constant).apply(&samples[..1]).len(),
+            5
+        );
+        assert!(conditioner(2, PadMode::Constant).apply(&[]).is_empty());
+        for pad_mode in [PadMode::Reflect, PadMode::Constant] {
+            let conditioner = conditioner(2, pad_mode);
+            for len in 0..=samples.len() {
+                let signal = conditioner.apply(&samples[..len]);
+                assert_eq!(
+                    signal.len(),
+                    conditioner.conditioned_len(len),
+                    "{pad_mode:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn preemphasis_keeps_the_first_sample() {
+        let conditioner = SignalConditioner {
+            preemph: 0.5,
+            ..conditioner(0, PadMode::Reflect)
+        };
+        let signal = conditioner.apply(&[2.0, 4.0, 4.0, 0.0]);
+        assert_eq!(&*signal, &[2.0, 3.0, 2.0, -2.0]);
+        assert!(matches!(
+            self::conditioner(0, PadMode::Reflect).apply(&[1.0]),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn dither_is_seeded_gaussian_noise() {
+        let conditioner = SignalConditioner {
+            dither: 0.01,
+            ..conditioner(0, PadMode::Reflect)
+        };
+        let silence = vec![0.0_f32; 20_000];
+        let noise = conditioner.apply(&silence);
+        assert_eq!(noise, conditioner.apply(&silence));
+
+        let mean = noise.iter().sum::<f32>() / noise.len() as f32;
+        let std =
+            (noise.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / noise.len() as f32).sqrt();
+        assert!(mean.abs() < 5e-4, "mean {mean}");
+        assert!((std - 0.01).abs() < 5e-4, "std {std}");
+    }
+
+    #[test]
+    fn chunked_stream_matches_the_whole_recording() {
+        let samples = (0..997)
+            .map(|i| ((i * 7919 % 211) as f32 / 105.0 - 1.0) * 0.5)
+            .collect::<Vec<_>>();
+        for pad_mode in [PadMode::Reflect, PadMode::Constant] {
+            let conditioner = SignalConditioner {
+                dither: 1e-3,
+                preemph: 0.97,
+                ..conditioner(200, pad_mode)
+            };
+            let whole = conditioner.apply(&samples);
+            assert_eq!(whole.len(), samples.len() + 400);
+
+            let mut carry = SignalCarry::default();
+            let mut streamed = Vec::new();
+            for chunk in samples.chunks(37) {
+                streamed.extend(conditioner.push(&mut carry, chunk));
+            }
+            streamed.extend(conditioner.finish(&mut carry));
+            assert_eq!(streamed, *whole, "{pad_mode:?}");
+        }
+    }
+}
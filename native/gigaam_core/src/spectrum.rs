@@ -0,0 +1,140 @@
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Power spectra of windowed real frames. Even FFT sizes pack the frame into a complex
+/// FFT of half the size; the plan and the frame buffers are kept between calls.
+pub struct PowerSpectrum {
+    n_fft: usize,
+    plan: Plan,
+    buffer: Vec<Complex32>,
+    scratch: Vec<Complex32>,
+    power: Vec<f32>,
+}
+
+enum Plan {
+    /// `n_fft / 2`-point FFT of the even and odd samples as real and imaginary parts;
+    /// `twiddles[k]` is `exp(-2πik / n_fft)` for the `n_fft / 2 + 1` output bins.
+    Packed {
+        fft: Arc<dyn Fft<f32>>,
+        twiddles: Vec<Complex32>,
+    },
+    /// Odd sizes cannot be packed and run the full complex FFT.
+    Full(Arc<dyn Fft<f32>>),
+}
+
+impl PowerSpectrum {
+    pub fn new(n_fft: usize) -> Self {
+        let mut planner = FftPlanner::<f32>::new();
+        let plan = if n_fft.is_multiple_of(2) {
+            let half = n_fft / 2;
+            Plan::Packed {
+                fft: planner.plan_fft_forward(half),
+                twiddles: (0..=half)
+                    .map(|k| {
+                        let angle = -2.0 * PI * k as f64 / n_fft as f64;
+                        Complex32::new(angle.cos() as f32, angle.sin() as f32)
+                    })
+                    .collect(),
+            }
+        } else {
+            Plan::Full(planner.plan_fft_forward(n_fft))
+        };
+        let fft = match &plan {
+            Plan::Packed { fft, .. } | Plan::Full(fft) => fft,
+        };
+        Self {
+            n_fft,
+            buffer: vec![Complex32::default(); fft.len()],
+            scratch: vec![Complex32::default(); fft.get_inplace_scratch_len()],
+            power: vec![0.0; n_fft / 2 + 1],
+            plan,
+        }
+    }
+
+    /// Power of the `n_fft / 2 + 1` one-sided bins of `frame * window`, zero-padded to
+    /// `n_fft`. `window` must be as long as `frame`.
+    pub fn compute(&mut self, frame: &[f32], window: &[f32]) -> &[f32] {
+        debug_assert!(frame.len() == window.len() && frame.len() <= self.n_fft);
+        let sample = |index: usize| {
+            frame
+                .get(index)
+                .map_or(0.0, |&sample| sample * window[index])
+        };
+
+        match &self.plan {
+            Plan::Packed { fft, twiddles } => {
+                for (index, value) in self.buffer.iter_mut().enumerate() {
+                    *value = Complex32::new(sample(2 * index), sample(2 * index + 1));
+                }
+                fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+                let half = self.buffer.len();
+                for (bin, power) in self.power.iter_mut().enumerate() {
+                    let packed = self.buffer[bin % half];
+                    let mirrored = self.buffer[(half - bin) % half].conj();
+                    let even = (packed + mirrored) * 0.5;
+                    let odd = (packed - mirrored) * Complex32::new(0.0, -0.5);
+                    let value = even + twiddles[bin] * odd;
+                    *power = value.re.mul_add(value.re, value.im * value.im);
+                }
+            }
+            Plan::Full(fft) => {
+                for (index, value) in self.buffer.iter_mut().enumerate() {
+                    *value = Complex32::new(sample(index), 0.0);
+                }
+                fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+                for (power, value) in self.power.iter_mut().zip(&self.buffer) {
+                    *power = value.re.mul_add(value.re, value.im * value.im);
+                }
+            }
+        }
+        &self.power
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_power(frame: &[f32], n_fft: usize) -> Vec<f64> {
+        (0..=n_fft / 2)
+            .map(|bin| {
+                let (re, im) = frame
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (i, &x)| {
+                        let angle = -2.0 * PI * (bin * i) as f64 / n_fft as f64;
+                        (re + x as f64 * angle.cos(), im + x as f64 * angle.sin())
+                    });
+                re * re + im * im
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packed_and_full_transforms_match_the_dft() {
+        for (frame_len, n_fft) in [(320, 320), (300, 320), (9, 9), (7, 12)] {
+            let frame = (0..frame_len)
+                .map(|i| ((i * 7919 % 101) as f32 / 50.0 - 1.0) * 0.3)
+                .collect::<Vec<_>>();
+            let window = vec![1.0_f32; frame_len];
+            let expected = naive_power(&frame, n_fft);
+            let peak = expected.iter().cloned().fold(0.0, f64::max);
+
+            let mut spectrum = PowerSpectrum::new(n_fft);
+            for _ in 0..2 {
+                let actual = spectrum.compute(&frame, &window);
+                assert_eq!(actual.len(), n_fft / 2 + 1);
+                for (bin, (&actual, &expected)) in actual.iter().zip(&expected).enumerate() {
+                    assert!(
+                        (actual as f64 - expected).abs() <= peak * 1e-5,
+                        "n_fft {n_fft}, bin {bin}: {actual} != {expected}"
+                    );
+                }
+            }
+        }
+    }
+}
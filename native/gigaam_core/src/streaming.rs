@@ -0,0 +1,452 @@
+use crate::decoder::AlignedToken;
+use crate::signal::SignalCarry;
+use anyhow::Result;
+use ndarray::{Array3, ArrayView3};
+use serde::Serialize;
+
+// All window sizes are in feature frames (10 ms at the default hop length).
+/// Already-settled audio re-fed to the encoder in front of the unsettled part.
+const LEFT_CONTEXT_FRAMES: usize = 200;
+/// Upper bound on the encoder input; older audio is force-settled beyond this.
+const MAX_WINDOW_FRAMES: usize = 1_600;
+/// New audio required before the encoder is re-run.
+const DECODE_STEP_FRAMES: usize = 30;
+/// Tokens closer than this to the live edge may still change and stay tentative.
+const STABLE_MARGIN_FRAMES: usize = 60;
+/// Encoder frames a re-run may shift a committed token by and still be recognized as
+/// the same token.
+const REEMIT_TOLERANCE_FRAMES: usize = 2;
+
+/// Buffers PCM between pushes so frames that straddle chunk boundaries are computed
+/// exactly as they would be over the whole recording.
+#[derive(Debug, Clone)]
+pub struct SampleCarry {
+    frame_length: usize,
+    hop_length: usize,
+    pending: Vec<f32>,
+}
+
+impl SampleCarry {
+    pub fn new(frame_length: usize, hop_length: usize) -> Self {
+        Self {
+            frame_length,
+            hop_length,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Appends `samples` and returns the audio covering every frame that is now complete.
+    /// The last `frame_length - hop_length` samples (plus any partial hop) are kept for the
+    /// next call.
+    pub fn push(&mut self, samples: &[f32]) -> Option<Vec<f32>> {
+        self.pending.extend_from_slice(samples);
+        if self.pending.len() < self.frame_length {
+            return None;
+        }
+        let frame_count = (self.pending.len() - self.frame_length) / self.hop_length + 1;
+        let ready_len = (frame_count - 1) * self.hop_length + self.frame_length;
+        let ready = self.pending[..ready_len].to_vec();
+        self.pending.drain(..frame_count * self.hop_length);
+        Some(ready)
+    }
+}
+
+/// Feature frames of the session that the encoder may still need to see again.
+#[derive(Debug, Clone)]
+struct FeatureHistory {
+    n_mels: usize,
+    // Frame-major: `n_mels` values per frame.
+    frames: Vec<f32>,
+    first_frame: usize,
+}
+
+impl FeatureHistory {
+    fn total_frames(&self) -> usize {
+        self.first_frame + self.frames.len() / self.n_mels
+    }
+
+    fn append(&mut self, features: ArrayView3<'_, f32>) {
+        let frame_count = features.shape()[2];
+        self.frames.reserve(frame_count * self.n_mels);
+        for frame_idx in 0..frame_count {
+            for mel_idx in 0..self.n_mels {
+                self.frames.push(features[[0, mel_idx, frame_idx]]);
+            }
+        }
+    }
+
+    /// `[1, n_mels, T]` features from absolute frame `start` to the end.
+    fn window(&self, start: usize) -> Result<Array3<f32>> {
+        let frame_count = self.total_frames() - start;
+        let offset = (start - self.first_frame) * self.n_mels;
+        let mut window = vec![0.0_f32; self.n_mels * frame_count];
+        for frame_idx in 0..frame_count {
+            for mel_idx in 0..self.n_mels {
+                window[mel_idx * frame_count + frame_idx] =
+                    self.frames[offset + frame_idx * self.n_mels + mel_idx];
+            }
+        }
+        Ok(Array3::from_shape_vec(
+            (1, self.n_mels, frame_count),
+            window,
+        )?)
+    }
+
+    fn discard_before(&mut self, frame: usize) {
+        if frame > self.first_frame {
+            let frame = frame.min(self.total_frames());
+            self.frames
+                .drain(..(frame - self.first_frame) * self.n_mels);
+            self.first_frame = frame;
+        }
+    }
+}
+
+/// Encoder input for one streaming step. `encoder_offset` converts window-relative
+/// encoder frames back to session-absolute ones.
+pub struct StreamingWindow {
+    pub features: Array3<f32>,
+    pub feature_length: i64,
+    pub encoder_offset: usize,
+}
+
+/// Per-session streaming state: carried audio, feature history and the token
+/// sequence split into a committed prefix and a tentative tail.
+#[derive(Debug, Clone)]
+pub struct StreamingState {
+    signal: SignalCarry,
+    carry: SampleCarry,
+    history: FeatureHistory,
+    subsampling_factor: usize,
+    committed: Vec<AlignedToken>,
+    tentative: Vec<AlignedToken>,
+    // One past the last committed encoder frame; tokens ending before it are settled.
+    stable_until: usize,
+    // Encoder frame the next window starts from, ahead of its left context. Unlike
+    // `stable_until` it also advances through audio without tokens.
+    decode_from: usize,
+    decoded_frames: usize,
+}
+
+impl StreamingState {
+    pub fn new(
+        frame_length: usize,
+        hop_length: usize,
+        n_mels: usize,
+        subsampling_factor: usize,
+    ) -> Self {
+        Self {
+            signal: SignalCarry::default(),
+            carry: SampleCarry::new(frame_length, hop_length),
+            history: FeatureHistory {
+                n_mels,
+                frames: Vec::new(),
+                first_frame: 0,
+            },
+            subsampling_factor: subsampling_factor.max(1),
+            committed: Vec::new(),
+            tentative: Vec::new(),
+            stable_until: 0,
+            decode_from: 0,
+            decoded_frames: 0,
+        }
+    }
+
+    /// Dither, preemphasis and padding state of the audio received so far.
+    pub fn signal_mut(&mut self) -> &mut SignalCarry {
+        &mut self.signal
+    }
+
+    pub fn carry_mut(&mut self) -> &mut SampleCarry {
+        &mut self.carry
+    }
+
+    pub fn append_features(&mut self, features: ArrayView3<'_, f32>) {
+        self.history.append(features);
+    }
+
+    pub fn committed(&self) -> &[AlignedToken] {
+        &self.committed
+    }
+
+    pub fn tentative(&self) -> &[AlignedToken] {
+        &self.tentative
+    }
+
+    /// Feature frames received so far.
+    pub fn total_frames(&self) -> usize {
+        self.history.total_frames()
+    }
+
+    /// The next encoder input, or `None` when too little new audio arrived since the
+    /// last step. `flush` forces a step over whatever is left.
+    pub fn next_window(&mut self, flush: bool) -> Result<Option<StreamingWindow>> {
+        let total = self.history.total_frames();
+        let fresh = total.saturating_sub(self.decoded_frames);
+        if total == 0 || fresh == 0 || (!flush && fresh < DECODE_STEP_FRAMES) {
+            return Ok(None);
+        }
+
+        let sub = self.subsampling_factor;
+        let settled_frame = self.decode_from * sub;
+        let mut start = settled_frame.saturating_sub(LEFT_CONTEXT_FRAMES) / sub * sub;
+        let oldest_allowed = total.saturating_sub(MAX_WINDOW_FRAMES).div_ceil(sub) * sub;
+        if start < oldest_allowed {
+            start = oldest_allowed;
+            self.settle_before(start / sub);
+        }
+        start = start.max(self.history.first_frame);
+        self.history.discard_before(start);
+
+        let features = self.history.window(start)?;
+        self.decoded_frames = total;
+        Ok(Some(StreamingWindow {
+            feature_length: (total - start) as i64,
+            features,
+            encoder_offset: start / sub,
+        }))
+    }
+
+    /// Merges the tokens decoded from a window into the transcript. Tokens that repeat
+    /// the committed tail or end inside it are ignored, tokens that end well before the
+    /// live edge are committed and the rest replace the tentative tail.
+    pub fn apply_decode(&mut self, window: &StreamingWindow, tokens: &[AlignedToken], flush: bool) {
+        let sub = self.subsampling_factor;
+        let live_edge = self.history.total_frames().div_ceil(sub);
+        let boundary = if flush {
+            usize::MAX
+        } else {
+            live_edge.saturating_sub(STABLE_MARGIN_FRAMES / sub)
+        };
+
+        self.tentative.clear();
+        let previously_committed = self.committed.len();
+        for token in tokens {
+            let token = AlignedToken {
+                id: token.id,
+                start_frame: token.start_frame + window.encoder_offset,
+                end_frame: token.end_frame + window.encoder_offset,
+            };
+            if token.end_frame < self.stable_until
+                || is_reemitted(&self.committed[..previously_committed], &token)
+            {
+                continue;
+            }
+            if self.tentative.is_empty() && token.end_frame < boundary {
+                self.committed.push(token);
+            } else {
+                self.tentative.push(token);
+            }
+        }
+
+        self.advance_stable_until();
+        let settled = self
+            .tentative
+            .first()
+            .map_or(boundary.min(live_edge), |token| token.start_frame);
+        self.decode_from = self.decode_from.max(settled).max(self.stable_until);
+    }
+
+    /// Commits the tentative tail; used when the session ends.
+    pub fn settle_remaining(&mut self) {
+        self.committed.append(&mut self.tentative);
+    }
+
+    fn settle_before(&mut self, encoder_frame: usize) {
+        let keep_from = self
+            .tentative
+            .iter()
+            .position(|token| token.end_frame >= encoder_frame)
+            .unwrap_or(self.tentative.len());
+        self.committed.extend(self.tentative.drain(..keep_from));
+        self.advance_stable_until();
+        self.decode_from = self.decode_from.max(encoder_frame);
+    }
+
+    fn advance_stable_until(&mut self) {
+        if let Some(last) = self.committed.last() {
+            self.stable_until = self.stable_until.max(last.end_frame + 1);
+        }
+    }
+}
+
+/// Whether `token` is a committed token emitted again by a later window, possibly a few
+/// frames off. Only the tail of `committed` can overlap a new window.
+fn is_reemitted(committed: &[AlignedToken], token: &AlignedToken) -> bool {
+    committed
+        .iter()
+        .rev()
+        .take_while(|committed| committed.end_frame + REEMIT_TOLERANCE_FRAMES >= token.start_frame)
+        .any(|committed| {
+            committed.id == token.id
+                && committed.start_frame.abs_diff(token.start_frame) <= REEMIT_TOLERANCE_FRAMES
+        })
+}
+
+/// Snapshot returned after every push and on finish.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamingUpdate {
+    /// Committed prefix; it only ever grows within a session.
+    pub stable_text: String,
+    /// Committed prefix followed by the current tentative tail.
+    pub text: String,
+    pub audio_ms: u64,
+    #[serde(rename = "final")]
+    pub is_final: bool,
+}
+
+/// `data` of the streaming calls: an update tagged with the session it belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionUpdate {
+    pub session_id: i64,
+    #[serde(flatten)]
+    pub update: StreamingUpdate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(id: usize, start_frame: usize, end_frame: usize) -> AlignedToken {
+        AlignedToken {
+            id,
+            start_frame,
+            end_frame,
+        }
+    }
+
+    fn state_with_frames(frame_count: usize) -> StreamingState {
+        let mut state = StreamingState::new(4, 2, 2, 4);
+        let features = Array3::from_shape_fn((1, 2, frame_count), |(_, mel, frame)| {
+            (frame * 10 + mel) as f32
+        });
+        state.append_features(features.view());
+        state
+    }
+
+    #[test]
+    fn carry_yields_whole_frames_and_keeps_overlap() {
+        let mut carry = SampleCarry::new(4, 2);
+        assert_eq!(carry.push(&[1.0, 2.0, 3.0]), None);
+        assert_eq!(carry.push(&[4.0, 5.0]), Some(vec![1.0, 2.0, 3.0, 4.0]));
+        // Samples 3, 4 and 5 are still pending, so the next frame starts at sample 3.
+        assert_eq!(carry.push(&[6.0]), Some(vec![3.0, 4.0, 5.0, 6.0]));
+        assert_eq!(carry.push(&[]), None);
+    }
+
+    #[test]
+    fn windows_wait_for_enough_audio_and_keep_channel_layout() -> Result<()> {
+        let mut state = state_with_frames(DECODE_STEP_FRAMES - 1);
+        assert!(state.next_window(false)?.is_none());
+
+        let window = state.next_window(true)?.expect("flush decodes short input");
+        assert_eq!(window.feature_length, (DECODE_STEP_FRAMES - 1) as i64);
+        assert_eq!(window.encoder_offset, 0);
+        assert_eq!(window.features[[0, 1, 3]], 31.0);
+        assert!(state.next_window(true)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn committed_prefix_only_grows_and_tail_is_replaced() -> Result<()> {
+        // 100 feature frames = 25 encoder frames; tokens ending before frame 10 are stable.
+        let mut state = state_with_frames(100);
+        let window = state.next_window(false)?.expect("enough audio");
+        state.apply_decode(
+            &window,
+            &[token(1, 2, 3), token(2, 8, 9), token(3, 20, 21)],
+            false,
+        );
+        assert_eq!(state.committed(), &[token(1, 2, 3), token(2, 8, 9)]);
+        assert_eq!(state.tentative(), &[token(3, 20, 21)]);
+
+        // A revised hypothesis for the settled region does not rewrite committed tokens.
+        state.apply_decode(&window, &[token(7, 2, 3), token(4, 20, 22)], false);
+        assert_eq!(state.committed(), &[token(1, 2, 3), token(2, 8, 9)]);
+        assert_eq!(state.tentative(), &[token(4, 20, 22)]);
+
+        state.apply_decode(&window, &[token(4, 20, 22)], true);
+        assert_eq!(state.committed().len(), 3);
+        assert!(state.tentative().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn tokens_shifted_by_a_rerun_are_kept_exactly_once() -> Result<()> {
+        // 100 feature frames = 25 encoder frames; tokens ending before frame 10 are stable.
+        let mut state = state_with_frames(100);
+        let window = state.next_window(false)?.expect("enough audio");
+        state.apply_decode(
+            &window,
+            &[token(1, 2, 3), token(2, 8, 9), token(3, 12, 13)],
+            false,
+        );
+        assert_eq!(state.committed(), &[token(1, 2, 3), token(2, 8, 9)]);
+        assert_eq!(state.tentative(), &[token(3, 12, 13)]);
+
+        // The re-run moves the tentative token back two frames and the last committed
+        // one forward by one: neither is lost and neither is committed twice.
+        state.append_features(Array3::zeros((1, 2, 100)).view());
+        let window = state.next_window(false)?.expect("enough audio");
+        let shifted = |frame: usize| frame - window.encoder_offset;
+        state.apply_decode(
+            &window,
+            &[
+                token(2, shifted(9), shifted(10)),
+                token(3, shifted(10), shifted(11)),
+                token(4, shifted(40), shifted(41)),
+            ],
+            false,
+        );
+        assert_eq!(
+            state.committed(),
+            &[token(1, 2, 3), token(2, 8, 9), token(3, 10, 11)]
+        );
+        assert_eq!(state.tentative(), &[token(4, 40, 41)]);
+
+        state.apply_decode(&window, &[token(3, shifted(11), shifted(11))], true);
+        state.settle_remaining();
+        let ids = state
+            .committed()
+            .iter()
+            .map(|token| token.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn window_keeps_left_context_and_is_capped() -> Result<()> {
+        let mut state = state_with_frames(MAX_WINDOW_FRAMES + 400);
+        let window = state.next_window(false)?.expect("enough audio");
+        assert_eq!(window.feature_length, MAX_WINDOW_FRAMES as i64);
+        assert_eq!(window.encoder_offset, 100);
+
+        state.apply_decode(&window, &[token(1, 0, 1), token(2, 390, 395)], false);
+        state.append_features(Array3::zeros((1, 2, DECODE_STEP_FRAMES)).view());
+        let window = state.next_window(false)?.expect("enough audio");
+        let settled_frame = state.decode_from * 4;
+        assert_eq!(
+            window.encoder_offset * 4,
+            (settled_frame - LEFT_CONTEXT_FRAMES) / 4 * 4
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn session_updates_carry_the_session_id_beside_the_update() {
+        let update = SessionUpdate {
+            session_id: 7,
+            update: StreamingUpdate {
+                stable_text: "при".to_string(),
+                text: "привет \"мир\"".to_string(),
+                audio_ms: 640,
+                is_final: false,
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&update).unwrap(),
+            "{\"session_id\":7,\"stable_text\":\"при\",\"text\":\"привет \\\"мир\\\"\",\"audio_ms\":640,\"final\":false}"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_SCRATCH_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Empty directory under the system temp dir for one test. It is removed on drop, so a
+/// failing assertion does not leave it behind.
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    pub fn new(name: &str) -> Self {
+        let id = NEXT_SCRATCH_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("gigaam-{name}-{}-{id}", std::process::id()));
+        // A run that was killed before dropping its directories may have left this one.
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("temp dir is writable");
+        Self { path }
+    }
+}
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for ScratchDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::confidence::aggregate_confidence;
+use crate::decoder::AlignedToken;
+use serde::Serialize;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenTiming {
+    #[serde(rename = "id")]
+    pub token_id: usize,
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Mean posterior of the token over its frames.
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Geometric mean of the word's token confidences.
+    pub confidence: f32,
+    /// Indices into the report's token list that make up this word.
+    #[serde(skip)]
+    pub tokens: Range<usize>,
+}
+
+/// Duration of one encoder output frame in milliseconds.
+pub fn encoder_frame_ms(hop_length: usize, subsampling_factor: usize, sample_rate: usize) -> f64 {
+    if sample_rate == 0 {
+        return 0.0;
+    }
+    (hop_length * subsampling_factor) as f64 * 1000.0 / sample_rate as f64
+}
+
+pub fn token_timings(
+    tokens: &[AlignedToken],
+    confidences: &[f32],
+    vocab: &[String],
+    frame_ms: f64,
+) -> Vec<TokenTiming> {
+    tokens
+        .iter()
+        .zip(confidences)
+        .map(|(token, &confidence)| TokenTiming {
+            token_id: token.id,
+            text: vocab.get(token.id).cloned().unwrap_or_default(),
+            start_ms: (token.start_frame as f64 * frame_ms).round() as u64,
+            end_ms: ((token.end_frame + 1) as f64 * frame_ms).round() as u64,
+            confidence,
+        })
+        .collect()
+}
+
+/// Groups tokens into words the same way `decode_token_ids_to_text` places spaces: a
+/// word starts at a token whose leading space is followed by a word character, while
+/// space-prefixed punctuation sticks to the previous word.
+pub fn word_timings(tokens: &[TokenTiming]) -> Vec<WordTiming> {
+    let mut words = Vec::<WordTiming>::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let trimmed = token.text.trim_start();
+        let starts_word = trimmed.len() != token.text.len()
+            && trimmed
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let piece = token.text.split_whitespace().collect::<String>();
+
+        match words.last_mut() {
+            Some(word) if !starts_word => {
+                word.text.push_str(&piece);
+                word.end_ms = word.end_ms.max(token.end_ms);
+                word.tokens.end = index + 1;
+            }
+            _ => {
+                if piece.is_empty() {
+                    continue;
+                }
+                words.push(WordTiming {
+                    text: piece,
+                    start_ms: token.start_ms,
+                    end_ms: token.end_ms,
+                    confidence: 0.0,
+                    tokens: index..index + 1,
+                });
+            }
+        }
+    }
+
+    for word in &mut words {
+        let confidences = tokens[word.tokens.clone()]
+            .iter()
+            .map(|token| token.confidence)
+            .collect::<Vec<_>>();
+        word.confidence = aggregate_confidence(&confidences);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(text: &str, start_ms: u64, end_ms: u64) -> TokenTiming {
+        TokenTiming {
+            token_id: 0,
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 0.5,
+        }
+    }
+
+    #[test]
+    fn frame_duration_follows_hop_and_subsampling() {
+        assert_eq!(encoder_frame_ms(160, 4, 16_000), 40.0);
+        assert_eq!(encoder_frame_ms(160, 4, 0), 0.0);
+    }
+
+    #[test]
+    fn tokens_are_converted_to_millisecond_spans() {
+        let vocab = vec![" при".to_string(), "вет".to_string()];
+        let tokens = [
+            AlignedToken {
+                id: 0,
+                start_frame: 2,
+                end_frame: 3,
+            },
+            AlignedToken {
+                id: 1,
+                start_frame: 5,
+                end_frame: 5,
+            },
+        ];
+
+        let timings = token_timings(&tokens, &[0.5, 0.9], &vocab, 40.0);
+        assert_eq!(timings[0], token(" при", 80, 160));
+        assert_eq!(timings[1].start_ms, 200);
+        assert_eq!(timings[1].end_ms, 240);
+        assert_eq!(timings[1].confidence, 0.9);
+    }
+
+    #[test]
+    fn words_follow_space_boundaries_and_keep_punctuation_attached() {
+        let tokens = [
+            token(" при", 0, 40),
+            token("вет", 40, 80),
+            token(" ,", 80, 120),
+            token(" мир", 200, 280),
+            token("!", 280, 320),
+        ];
+
+        let words = word_timings(&tokens);
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "привет,");
+        assert_eq!((words[0].start_ms, words[0].end_ms), (0, 120));
+        assert_eq!(words[0].tokens, 0..3);
+        assert_eq!(words[1].text, "мир!");
+        assert_eq!((words[1].start_ms, words[1].end_ms), (200, 320));
+        assert_eq!(words[1].tokens, 3..5);
+        assert!((words[1].confidence - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,592 @@
+use crate::gigaam::{build_ort_session, GigaamConfig, GigaamFrontend, RuntimeOptions};
+use anyhow::{Context, Result};
+use ndarray::{arr0, Array2, Array3, Axis};
+use ort::inputs;
+use ort::session::Session;
+use ort::value::TensorRef;
+use serde::Serialize;
+use std::path::Path;
+
+pub const SILERO_MODEL_FILENAME: &str = "silero_vad.onnx";
+pub const VAD_SAMPLE_RATE: usize = 16_000;
+
+const DEFAULT_THRESHOLD: f32 = 0.5;
+const DEFAULT_MIN_SPEECH_MS: u64 = 250;
+const DEFAULT_MIN_SILENCE_MS: u64 = 300;
+const DEFAULT_SPEECH_PAD_MS: u64 = 100;
+// Speech ends only once the probability drops this far below the start threshold.
+const HYSTERESIS: f32 = 0.15;
+
+// Energy/flux detector tuning; levels are mean-square dB of the windowed frame.
+const MIN_SPEECH_DB: f32 = -38.0;
+const INITIAL_NOISE_FLOOR_DB: f32 = -42.0;
+const SNR_MARGIN_DB: f32 = 7.0;
+const SNR_SLOPE_DB: f32 = 3.0;
+const FLUX_BIAS: f32 = 0.15;
+const FLUX_WEIGHT: f32 = 6.0;
+const NOISE_DECAY: f32 = 0.95;
+// During speech the floor still creeps up so a sudden, steady noise is learned.
+const NOISE_DECAY_IN_SPEECH: f32 = 0.999;
+
+const SILERO_CHUNK_SAMPLES: usize = 512;
+const SILERO_CONTEXT_SAMPLES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadMode {
+    Off,
+    Energy,
+    Silero,
+}
+
+impl VadMode {
+    pub fn from_id(value: &str) -> Self {
+        match value {
+            "energy" => Self::Energy,
+            "silero" => Self::Silero,
+            _ => Self::Off,
+        }
+    }
+
+    pub fn as_id(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Energy => "energy",
+            Self::Silero => "silero",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadOptions {
+    /// Frame speech probability at which a segment starts.
+    pub threshold: f32,
+    /// Shorter speech bursts are dropped.
+    pub min_speech_ms: u64,
+    /// Pauses shorter than this do not end a segment.
+    pub min_silence_ms: u64,
+    /// Extra audio kept on both sides of every segment.
+    pub speech_pad_ms: u64,
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            min_speech_ms: DEFAULT_MIN_SPEECH_MS,
+            min_silence_ms: DEFAULT_MIN_SILENCE_MS,
+            speech_pad_ms: DEFAULT_SPEECH_PAD_MS,
+        }
+    }
+}
+
+impl VadOptions {
+    /// Builds options from host-supplied values, keeping defaults for out-of-range ones.
+    pub fn from_values(
+        threshold: f32,
+        min_speech_ms: i32,
+        min_silence_ms: i32,
+        speech_pad_ms: i32,
+    ) -> Self {
+        let defaults = Self::default();
+        Self {
+            threshold: if threshold > 0.0 && threshold < 1.0 {
+                threshold
+            } else {
+                defaults.threshold
+            },
+            min_speech_ms: u64::try_from(min_speech_ms).unwrap_or(defaults.min_speech_ms),
+            min_silence_ms: u64::try_from(min_silence_ms).unwrap_or(defaults.min_silence_ms),
+            speech_pad_ms: u64::try_from(speech_pad_ms).unwrap_or(defaults.speech_pad_ms),
+        }
+    }
+}
+
+/// A detected speech region of 16 kHz input; `end_sample` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SpeechSegment {
+    #[serde(skip)]
+    pub start_sample: usize,
+    #[serde(skip)]
+    pub end_sample: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// `data` of `detectSpeech`: the segments found by `backend` and their total length.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpeechDetection {
+    pub backend: &'static str,
+    pub speech_ms: u64,
+    pub segments: Vec<SpeechSegment>,
+}
+
+impl SpeechDetection {
+    pub fn new(segments: Vec<SpeechSegment>, backend: &'static str) -> Self {
+        let speech_ms = segments
+            .iter()
+            .map(|segment| segment.end_ms - segment.start_ms)
+            .sum();
+        Self {
+            backend,
+            speech_ms,
+            segments,
+        }
+    }
+}
+
+/// Speech detector over 16 kHz mono audio.
+pub struct VoiceActivityDetector {
+    backend: VadBackend,
+    options: VadOptions,
+}
+
+enum VadBackend {
+    Energy(EnergyFluxVad),
+    Silero(SileroVad),
+}
+
+impl VoiceActivityDetector {
+    /// Adaptive energy + spectral-flux detector on the GigaAM STFT; needs no model file.
+    pub fn energy(options: VadOptions) -> Result<Self> {
+        Ok(Self {
+            backend: VadBackend::Energy(EnergyFluxVad::new(&GigaamConfig::default())?),
+            options,
+        })
+    }
+
+    /// Silero-style ONNX VAD (v4 `h`/`c` or v5 `state` inputs).
+    pub fn silero(model_path: &Path, options: VadOptions) -> Result<Self> {
+        Ok(Self {
+            backend: VadBackend::Silero(SileroVad::new(model_path)?),
+            options,
+        })
+    }
+
+    /// `None` for [`VadMode::Off`]. Silero models are looked up in `models_root`.
+    pub fn from_mode(
+        mode: VadMode,
+        models_root: &Path,
+        options: VadOptions,
+    ) -> Result<Option<Self>> {
+        match mode {
+            VadMode::Off => Ok(None),
+            VadMode::Energy => Self::energy(options).map(Some),
+            VadMode::Silero => {
+                Self::silero(&models_root.join(SILERO_MODEL_FILENAME), options).map(Some)
+            }
+        }
+    }
+
+    pub fn mode(&self) -> VadMode {
+        match self.backend {
+            VadBackend::Energy(_) => VadMode::Energy,
+            VadBackend::Silero(_) => VadMode::Silero,
+        }
+    }
+
+    pub fn detect(&mut self, samples: &[f32]) -> Result<Vec<SpeechSegment>> {
+        let (probabilities, frame_samples) = match &mut self.backend {
+            VadBackend::Energy(vad) => (vad.speech_probabilities(samples)?, vad.frame_samples),
+            VadBackend::Silero(vad) => (vad.speech_probabilities(samples)?, SILERO_CHUNK_SAMPLES),
+        };
+        Ok(segments_from_probabilities(
+            &probabilities,
+            frame_samples,
+            samples.len(),
+            &self.options,
+        ))
+    }
+}
+
+/// Turns per-frame speech probabilities into padded segments with hysteresis, dropping
+/// short bursts and bridging short pauses.
+pub fn segments_from_probabilities(
+    probabilities: &[f32],
+    frame_samples: usize,
+    total_samples: usize,
+    options: &VadOptions,
+) -> Vec<SpeechSegment> {
+    let ms_to_samples = |ms: u64| (ms as usize * VAD_SAMPLE_RATE) / 1000;
+    let min_speech = ms_to_samples(options.min_speech_ms);
+    let min_silence = ms_to_samples(options.min_silence_ms);
+    let pad = ms_to_samples(options.speech_pad_ms);
+    let end_threshold = (options.threshold - HYSTERESIS).max(0.0);
+
+    let mut raw = Vec::<(usize, usize)>::new();
+    let mut speech_start = None;
+    let mut silence_start = None;
+    for (frame_idx, &probability) in probabilities.iter().enumerate() {
+        let position = frame_idx * frame_samples;
+        match speech_start {
+            None if probability >= options.threshold => speech_start = Some(position),
+            None => {}
+            Some(start) => {
+                if probability >= end_threshold {
+                    silence_start = None;
+                    continue;
+                }
+                let silence = *silence_start.get_or_insert(position);
+                if position + frame_samples - silence >= min_silence {
+                    raw.push((start, silence));
+                    speech_start = None;
+                    silence_start = None;
+                }
+            }
+        }
+    }
+    if let Some(start) = speech_start {
+        raw.push((start, silence_start.unwrap_or(total_samples)));
+    }
+
+    let mut segments = Vec::<SpeechSegment>::new();
+    for (start, end) in raw {
+        let end = end.min(total_samples);
+        if end <= start || end - start < min_speech {
+            continue;
+        }
+        let start = start.saturating_sub(pad);
+        let end = (end + pad).min(total_samples);
+        match segments.last_mut() {
+            Some(previous) if start <= previous.end_sample => {
+                previous.end_sample = end;
+                previous.end_ms = samples_to_ms(end);
+            }
+            _ => segments.push(SpeechSegment {
+                start_sample: start,
+                end_sample: end,
+                start_ms: samples_to_ms(start),
+                end_ms: samples_to_ms(end),
+            }),
+        }
+    }
+    segments
+}
+
+fn samples_to_ms(samples: usize) -> u64 {
+    (samples as u64 * 1000) / VAD_SAMPLE_RATE as u64
+}
+
+struct EnergyFluxVad {
+    frontend: GigaamFrontend,
+    frame_samples: usize,
+}
+
+impl EnergyFluxVad {
+    fn new(config: &GigaamConfig) -> Result<Self> {
+        if config.sample_rate() != VAD_SAMPLE_RATE {
+            return Err(anyhow::anyhow!(
+                "Energy VAD expects {VAD_SAMPLE_RATE} Hz input, got {} Hz",
+                config.sample_rate()
+            ));
+        }
+        let frontend = GigaamFrontend::from_config(config)?;
+        Ok(Self {
+            frame_samples: frontend.hop_length(),
+            frontend,
+        })
+    }
+
+    fn speech_probabilities(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        let normalization = self.frontend.power_normalization();
+        let mut noise_floor_db = INITIAL_NOISE_FLOOR_DB;
+        let mut previous_magnitude = Vec::<f32>::new();
+        let mut probabilities = Vec::new();
+
+        self.frontend
+            .visit_power_spectra(samples, |_, power_spectrum| {
+                let mean_square = power_spectrum.iter().sum::<f32>() / normalization;
+                let energy_db = 10.0 * (mean_square + 1e-10).log10();
+
+                // Positive spectral flux relative to the frame's total magnitude: high at
+                // onsets and through the formant movement of speech, low for steady noise.
+                let magnitude = power_spectrum
+                    .iter()
+                    .map(|power| power.sqrt())
+                    .collect::<Vec<_>>();
+                let flux = if previous_magnitude.is_empty() {
+                    0.0
+                } else {
+                    let rise = magnitude
+                        .iter()
+                        .zip(&previous_magnitude)
+                        .map(|(current, previous)| (current - previous).max(0.0))
+                        .sum::<f32>();
+                    rise / (magnitude.iter().sum::<f32>() + 1e-6)
+                };
+                previous_magnitude = magnitude;
+
+                let probability = if energy_db < MIN_SPEECH_DB {
+                    0.0
+                } else {
+                    let snr_db = energy_db - noise_floor_db;
+                    sigmoid(
+                        (snr_db - SNR_MARGIN_DB) / SNR_SLOPE_DB + FLUX_WEIGHT * (flux - FLUX_BIAS),
+                    )
+                };
+
+                let decay = if probability < DEFAULT_THRESHOLD {
+                    NOISE_DECAY
+                } else {
+                    NOISE_DECAY_IN_SPEECH
+                };
+                noise_floor_db = (noise_floor_db * decay + energy_db * (1.0 - decay))
+                    .min(energy_db.max(INITIAL_NOISE_FLOOR_DB));
+                probabilities.push(probability);
+            })?;
+
+        Ok(probabilities)
+    }
+}
+
+fn sigmoid(value: f32) -> f32 {
+    1.0 / (1.0 + (-value).exp())
+}
+
+enum SileroState {
+    /// v5 models: one `[2, 1, 128]` state and a 64-sample context prefix.
+    Combined,
+    /// v4 models: LSTM `h` and `c`, `[2, 1, 64]` each.
+    Split,
+}
+
+struct SileroVad {
+    session: Session,
+    state_kind: SileroState,
+}
+
+impl SileroVad {
+    fn new(model_path: &Path) -> Result<Self> {
+        if !model_path.exists() {
+            return Err(anyhow::anyhow!(
+                "Missing Silero VAD model file: {}",
+                model_path.display()
+            ));
+        }
+        let (session, _) = build_ort_session(
+            model_path,
+            &RuntimeOptions::from_ids("balanced", "cpu"),
+            None,
+            None,
+        )
+        .with_context(|| format!("Failed to load Silero VAD model: {}", model_path.display()))?;
+        let has_input = |name: &str| session.inputs.iter().any(|input| input.name == name);
+        let state_kind = if has_input("state") {
+            SileroState::Combined
+        } else if has_input("h") && has_input("c") {
+            SileroState::Split
+        } else {
+            return Err(anyhow::anyhow!(
+                "Unsupported Silero VAD model: expected 'state' or 'h'/'c' inputs"
+            ));
+        };
+        Ok(Self {
+            session,
+            state_kind,
+        })
+    }
+
+    fn speech_probabilities(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        let sample_rate = arr0(VAD_SAMPLE_RATE as i64);
+        let mut probabilities = Vec::with_capacity(samples.len() / SILERO_CHUNK_SAMPLES + 1);
+        let mut context = vec![0.0_f32; SILERO_CONTEXT_SAMPLES];
+
+        match self.state_kind {
+            SileroState::Combined => {
+                let mut state = Array3::<f32>::zeros((2, 1, 128));
+                for chunk in samples.chunks(SILERO_CHUNK_SAMPLES) {
+                    let mut frame = context.clone();
+                    frame.extend_from_slice(chunk);
+                    frame.resize(SILERO_CONTEXT_SAMPLES + SILERO_CHUNK_SAMPLES, 0.0);
+                    context.copy_from_slice(&frame[frame.len() - SILERO_CONTEXT_SAMPLES..]);
+                    let input = Array2::from_shape_vec((1, frame.len()), frame)?;
+
+                    let outputs = self.session.run(inputs![
+                        "input" => TensorRef::from_array_view(input.view())?,
+                        "state" => TensorRef::from_array_view(state.view())?,
+                        "sr" => TensorRef::from_array_view(sample_rate.view())?,
+                    ])?;
+                    probabilities.push(silero_probability(&outputs)?);
+                    state = outputs
+                        .get("stateN")
+                        .ok_or_else(|| anyhow::anyhow!("Silero VAD output 'stateN' not found"))?
+                        .try_extract_array::<f32>()?
+                        .to_owned()
+                        .into_dimensionality()?;
+                }
+            }
+            SileroState::Split => {
+                let mut h = Array3::<f32>::zeros((2, 1, 64));
+                let mut c = Array3::<f32>::zeros((2, 1, 64));
+                for chunk in samples.chunks(SILERO_CHUNK_SAMPLES) {
+                    let mut frame = chunk.to_vec();
+                    frame.resize(SILERO_CHUNK_SAMPLES, 0.0);
+                    let input = Array2::from_shape_vec((1, frame.len()), frame)?;
+
+                    let outputs = self.session.run(inputs![
+                        "input" => TensorRef::from_array_view(input.view())?,
+                        "sr" => TensorRef::from_array_view(sample_rate.view())?,
+                        "h" => TensorRef::from_array_view(h.view())?,
+                        "c" => TensorRef::from_array_view(c.view())?,
+                    ])?;
+                    probabilities.push(silero_probability(&outputs)?);
+                    let next_state = |name: &str| -> Result<Array3<f32>> {
+                        Ok(outputs
+                            .get(name)
+                            .ok_or_else(|| anyhow::anyhow!("Silero VAD output '{name}' not found"))?
+                            .try_extract_array::<f32>()?
+                            .to_owned()
+                            .into_dimensionality()?)
+                    };
+                    h = next_state("hn")?;
+                    c = next_state("cn")?;
+                }
+            }
+        }
+
+        Ok(probabilities)
+    }
+}
+
+fn silero_probability(outputs: &ort::session::SessionOutputs<'_>) -> Result<f32> {
+    let output = outputs
+        .get("output")
+        .ok_or_else(|| anyhow::anyhow!("Silero VAD output 'output' not found"))?
+        .try_extract_array::<f32>()?;
+    output
+        .lanes(Axis(0))
+        .into_iter()
+        .next()
+        .and_then(|lane| lane.iter().next().copied())
+        .ok_or_else(|| anyhow::anyhow!("Silero VAD returned an empty output"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLES_PER_MS: usize = VAD_SAMPLE_RATE / 1000;
+
+    fn noise(len: usize, amplitude: f32, seed: &mut u32) -> Vec<f32> {
+        (0..len)
+            .map(|_| {
+                *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (*seed as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    // A gliding harmonic tone with syllable-rate amplitude modulation.
+    fn voiced(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|idx| {
+                let t = idx as f32 / VAD_SAMPLE_RATE as f32;
+                let pitch = 140.0 + 40.0 * (2.0 * PI * 3.0 * t).sin();
+                let envelope = 0.6 + 0.4 * (2.0 * PI * 4.0 * t).sin();
+                let tone = (1..=4)
+                    .map(|harmonic| {
+                        (2.0 * PI * pitch * harmonic as f32 * t).sin() / harmonic as f32
+                    })
+                    .sum::<f32>();
+                0.15 * envelope * tone
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hysteresis_bridges_short_pauses_and_drops_short_bursts() {
+        let options = VadOptions {
+            threshold: 0.5,
+            min_speech_ms: 100,
+            min_silence_ms: 100,
+            speech_pad_ms: 0,
+        };
+        // 32 ms frames: a 2-frame burst, silence, speech with a 2-frame dip, silence.
+        let probabilities = [
+            0.9, 0.9, 0.0, 0.0, 0.0, 0.0, 0.8, 0.9, 0.4, 0.1, 0.1, 0.9, 0.9, 0.9, 0.0, 0.0, 0.0,
+            0.0,
+        ];
+        let frame = SILERO_CHUNK_SAMPLES;
+        let segments = segments_from_probabilities(&probabilities, frame, 18 * frame, &options);
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].start_sample, 6 * frame);
+        assert_eq!(segments[0].end_sample, 14 * frame);
+        assert_eq!((segments[0].start_ms, segments[0].end_ms), (192, 448));
+    }
+
+    #[test]
+    fn padding_merges_neighbours_and_is_clamped_to_input() {
+        let options = VadOptions {
+            threshold: 0.5,
+            min_speech_ms: 0,
+            min_silence_ms: 0,
+            speech_pad_ms: 64,
+        };
+        let probabilities = [0.9, 0.0, 0.0, 0.9, 0.9, 0.0, 0.0, 0.0, 0.0, 0.0, 0.9];
+        let frame = SILERO_CHUNK_SAMPLES;
+        let segments = segments_from_probabilities(&probabilities, frame, 11 * frame, &options);
+
+        let spans = segments
+            .iter()
+            .map(|segment| (segment.start_sample, segment.end_sample))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![(0, 5 * frame + 1024), (10 * frame - 1024, 11 * frame)]
+        );
+    }
+
+    #[test]
+    fn energy_detector_finds_voiced_region_in_noise() -> Result<()> {
+        let mut seed = 7;
+        let mut samples = noise(600 * SAMPLES_PER_MS, 0.003, &mut seed);
+        let speech = voiced(1_000 * SAMPLES_PER_MS);
+        let background = noise(speech.len(), 0.003, &mut seed);
+        samples.extend(speech.iter().zip(&background).map(|(s, n)| s + n));
+        samples.extend(noise(600 * SAMPLES_PER_MS, 0.003, &mut seed));
+
+        let mut vad = VoiceActivityDetector::energy(VadOptions {
+            speech_pad_ms: 0,
+            ..VadOptions::default()
+        })?;
+        let segments = vad.detect(&samples)?;
+
+        assert_eq!(segments.len(), 1, "segments: {segments:?}");
+        assert!(segments[0].start_ms.abs_diff(600) <= 60, "{segments:?}");
+        assert!(segments[0].end_ms.abs_diff(1_600) <= 60, "{segments:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn vad_modes_round_trip_ids() {
+        for mode in [VadMode::Off, VadMode::Energy, VadMode::Silero] {
+            assert_eq!(VadMode::from_id(mode.as_id()), mode);
+        }
+        assert_eq!(VadMode::from_id("unknown"), VadMode::Off);
+        assert_eq!(
+            VadOptions::from_values(1.5, -1, 200, 0),
+            VadOptions {
+                min_silence_ms: 200,
+                speech_pad_ms: 0,
+                ..VadOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn speech_detection_sums_segments_and_reports_milliseconds_only() {
+        let segment = |start_ms: u64, end_ms: u64| SpeechSegment {
+            start_sample: start_ms as usize * SAMPLES_PER_MS,
+            end_sample: end_ms as usize * SAMPLES_PER_MS,
+            start_ms,
+            end_ms,
+        };
+        let detection = SpeechDetection::new(vec![segment(100, 400), segment(900, 1000)], "energy");
+        assert_eq!(detection.speech_ms, 400);
+        assert_eq!(
+            serde_json::to_string(&detection).unwrap(),
+            "{\"backend\":\"energy\",\"speech_ms\":400,\"segments\":[{\"start_ms\":100,\"end_ms\":400},{\"start_ms\":900,\"end_ms\":1000}]}"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+#!/usr/bin/env python3
+"""Generates the GigaAM frontend golden references in this directory.
+
+The features come from GigaAM's reference preprocessing itself:
+`torchaudio.transforms.MelSpectrogram` (power 2) followed by
+`log(clamp(x, 1e-9, 1e9))`. The quantized v3 configuration does not rebuild its
+window and filterbank; it loads the bfloat16 `spectrogram.window` and
+`mel_scale.fb` buffers from a GigaAM v3 checkpoint, which is what the shipped model
+was trained and exported with. The other configurations cover the optional keys
+with torchaudio's own float32 buffers: a centered STFT with reflect or constant
+padding, the Slaney mel scale and filter norm, `f_min`/`f_max` and NeMo's
+preemphasis, applied before padding.
+
+Requires torch and torchaudio, plus the GigaAM v3 CTC checkpoint
+(`v3_e2e_ctc.ckpt` from the GigaAM release). Every JSON records the torch and
+torchaudio versions in `provenance`, and the v3 one also the checkpoint's file name
+and SHA-256. Run it from any directory:
+
+    python3 testdata/frontend/generate_references.py --checkpoint v3_e2e_ctc.ckpt
+
+The golden tests in gigaam.rs are ignored until the references exist; run them with
+`cargo test -- --ignored matches_reference`. Only commit references written by this
+script.
+
+It also prints how the checkpoint buffers compare with the window and filterbank the
+native frontend rebuilds (periodic or symmetric Hann window, bfloat16 rounding to
+nearest even or truncation, filter edges in float32 or float64).
+"""
+
+import argparse
+import hashlib
+import json
+import math
+import os
+import struct
+import wave
+
+import torch
+import torchaudio
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+SAMPLE_RATE = 16000
+AUDIO_SAMPLES = 3200
+
+CONFIGS = {
+    # The shipped v3 configuration, with the window and filterbank of the checkpoint.
+    "v3_e2e_ctc": {
+        "model_name": "v3_e2e_ctc",
+        "sample_rate": SAMPLE_RATE,
+        "n_mels": 64,
+        "win_length": 320,
+        "hop_length": 160,
+        "n_fft": 320,
+        "quantize_bf16": True,
+    },
+    # A non-v3 model with a 400-point FFT keeps full-precision buffers.
+    "unquantized": {
+        "model_name": "v2_ctc",
+        "sample_rate": SAMPLE_RATE,
+        "n_mels": 64,
+        "win_length": 400,
+        "hop_length": 160,
+        "n_fft": 400,
+        "quantize_bf16": False,
+    },
+    # GigaAM v1/v2 keep torchaudio's default centered, reflect-padded STFT.
+    "v2_centered": {
+        "model_name": "v2_ctc",
+        "sample_rate": SAMPLE_RATE,
+        "n_mels": 64,
+        "win_length": 400,
+        "hop_length": 160,
+        "n_fft": 400,
+        "quantize_bf16": False,
+        "center": True,
+    },
+    # A NeMo-style Conformer frontend: the window is shorter than the FFT.
+    "nemo_slaney": {
+        "model_name": "conformer_ctc",
+        "sample_rate": SAMPLE_RATE,
+        "n_mels": 80,
+        "win_length": 400,
+        "hop_length": 160,
+        "n_fft": 512,
+        "quantize_bf16": False,
+        "center": True,
+        "pad_mode": "constant",
+        "mel_scale": "slaney",
+        "mel_norm": "slaney",
+        "f_min": 20.0,
+        "f_max": 7600.0,
+        "preemph": 0.97,
+    },
+}
+
+
+def checkpoint_buffers(path):
+    """The bfloat16 window and filterbank buffers of a GigaAM v3 checkpoint."""
+    checkpoint = torch.load(path, map_location="cpu", weights_only=False)
+    state = checkpoint.get("state_dict", checkpoint)
+
+    def buffer(suffix):
+        keys = [key for key in state if key.endswith(suffix)]
+        if len(keys) != 1:
+            raise SystemExit(f"{path}: expected one '{suffix}' buffer, found {keys}")
+        return state[keys[0]]
+
+    return buffer("spectrogram.window"), buffer("mel_scale.fb")
+
+
+def mel_transform(config):
+    return torchaudio.transforms.MelSpectrogram(
+        sample_rate=config["sample_rate"],
+        n_fft=config["n_fft"],
+        win_length=config["win_length"],
+        hop_length=config["hop_length"],
+        f_min=config.get("f_min", 0.0),
+        f_max=config.get("f_max"),
+        n_mels=config["n_mels"],
+        center=config.get("center", False),
+        pad_mode=config.get("pad_mode", "reflect"),
+        norm=config.get("mel_norm"),
+        mel_scale=config.get("mel_scale", "htk"),
+    )
+
+
+def truncate_bf16(values):
+    bits = values.float().contiguous().view(torch.int32) & -65536
+    return bits.view(torch.float32)
+
+
+def report_buffer_match(window, fb, config):
+    """Prints how many checkpoint values each rebuilt variant gets wrong."""
+    win_length = config["win_length"]
+    window = window.float()
+    fb = fb.float()
+    windows = {
+        "periodic hann, round to nearest even": torch.hann_window(win_length).bfloat16(),
+        "symmetric hann, round to nearest even": torch.hann_window(
+            win_length, periodic=False
+        ).bfloat16(),
+        "periodic hann, truncated": truncate_bf16(torch.hann_window(win_length)),
+    }
+    for name, candidate in windows.items():
+        mismatches = int((candidate.float() != window).sum())
+        print(f"window, {name}: {mismatches} of {window.numel()} differ")
+
+    def filterbank(dtype):
+        default_dtype = torch.get_default_dtype()
+        torch.set_default_dtype(dtype)
+        try:
+            return mel_transform(config).mel_scale.fb.float()
+        finally:
+            torch.set_default_dtype(default_dtype)
+
+    filterbanks = {
+        "float32 edges, round to nearest even": filterbank(torch.float32).bfloat16(),
+        "float64 edges, round to nearest even": filterbank(torch.float64).bfloat16(),
+        "float32 edges, truncated": truncate_bf16(filterbank(torch.float32)),
+    }
+    for name, candidate in filterbanks.items():
+        mismatches = int((candidate.float() != fb).sum())
+        print(f"filterbank, {name}: {mismatches} of {fb.numel()} differ")
+
+
+def log_mel_features(samples, config, transform):
+    signal = torch.tensor(samples, dtype=torch.float32)
+    preemph = config.get("preemph", 0.0)
+    if preemph:
+        signal = torch.cat((signal[:1], signal[1:] - preemph * signal[:-1]))
+    with torch.no_grad():
+        return torch.log(transform(signal).clamp(1e-9, 1e9))
+
+
+def sha256(path):
+    digest = hashlib.sha256()
+    with open(path, "rb") as file:
+        for block in iter(lambda: file.read(1 << 20), b""):
+            digest.update(block)
+    return digest.hexdigest()
+
+
+def lcg_noise(seed):
+    state = seed
+    while True:
+        state = (state * 1103515245 + 12345) % 2**31
+        yield state / 2**30 - 1.0
+
+
+def chirp_audio():
+    """A 100 Hz to 7 kHz sweep over a little noise, so every mel band has energy."""
+    noise = lcg_noise(7)
+    duration = AUDIO_SAMPLES / SAMPLE_RATE
+    samples = []
+    for i in range(AUDIO_SAMPLES):
+        t = i / SAMPLE_RATE
+        phase = 2.0 * math.pi * (100.0 * t + (7000.0 - 100.0) * t * t / (2.0 * duration))
+        samples.append(0.4 * math.sin(phase) + 0.01 * next(noise))
+    return samples
+
+
+def noise_audio():
+    """White noise under a rising envelope, with a 440 Hz tone in the second half."""
+    noise = lcg_noise(2024)
+    samples = []
+    for i in range(AUDIO_SAMPLES):
+        envelope = 0.05 + 0.3 * i / AUDIO_SAMPLES
+        tone = 0.2 * math.sin(2.0 * math.pi * 440.0 * i / SAMPLE_RATE) if i >= 1600 else 0.0
+        samples.append(envelope * next(noise) + tone)
+    return samples
+
+
+def write_wav(name, samples):
+    pcm = [max(-32768, min(32767, round(value * 32768.0))) for value in samples]
+    with wave.open(os.path.join(HERE, name), "wb") as wav:
+        wav.setnchannels(1)
+        wav.setsampwidth(2)
+        wav.setframerate(SAMPLE_RATE)
+        wav.writeframes(struct.pack(f"<{len(pcm)}h", *pcm))
+    # The frontend sees the samples the WAV file stores.
+    return [value / 32768.0 for value in pcm]
+
+
+def rounded(values):
+    return [float(f"{value:.9g}") for value in values]
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument(
+        "--checkpoint", required=True, help="GigaAM v3 CTC checkpoint (v3_e2e_ctc.ckpt)"
+    )
+    args = parser.parse_args()
+
+    audio = {
+        "chirp.wav": write_wav("chirp.wav", chirp_audio()),
+        "noise.wav": write_wav("noise.wav", noise_audio()),
+    }
+    versions = {"torch": torch.__version__, "torchaudio": torchaudio.__version__}
+    for name, config in CONFIGS.items():
+        transform = mel_transform(config)
+        provenance = dict(versions)
+        if config["quantize_bf16"]:
+            window, fb = checkpoint_buffers(args.checkpoint)
+            report_buffer_match(window, fb, config)
+            transform.spectrogram.window = window.float()
+            transform.mel_scale.fb = fb.float()
+            provenance["checkpoint"] = os.path.basename(args.checkpoint)
+            provenance["checkpoint_sha256"] = sha256(args.checkpoint)
+        reference = {
+            "config": config,
+            "provenance": provenance,
+            "window": rounded(transform.spectrogram.window.tolist()),
+            # Only the non-zero span of each band: [first bin, weights...].
+            "filterbank": [],
+            "features": {},
+        }
+        for weights in transform.mel_scale.fb.T.tolist():
+            nonzero = [i for i, weight in enumerate(weights) if weight > 0.0]
+            first, last = nonzero[0], nonzero[-1]
+            reference["filterbank"].append([first, rounded(weights[first : last + 1])])
+        for audio_name, samples in audio.items():
+            features = log_mel_features(samples, config, transform)
+            reference["features"][audio_name] = [rounded(row) for row in features.tolist()]
+        with open(os.path.join(HERE, f"{name}.json"), "w") as output:
+            json.dump(reference, output, separators=(",", ":"))
+            output.write("\n")
+
+
+if __name__ == "__main__":
+    main()